@@ -1,40 +1,564 @@
-use clap::Parser as cParser;
-use noorm::{parser::Language, prelude::*};
-use std::path::PathBuf;
+use clap::{Parser as cParser, Subcommand};
+use noorm::{parser::{is_schema_file, is_sqlite_database, read_sql_files, Drift, DriftKind, Language, ParserConfigError, ParserError, Schema, Severity, lints_to_json, lints_to_sarif}, prelude::*};
+use notify::{EventKind, RecursiveMode, Watcher};
+use std::{error::Error, fs, io, path::{Path, PathBuf}, process::ExitCode, sync::mpsc, time::Duration};
+
+/// Exit code of configuration errors, the same as clap's usage errors
+const CONFIG_ERROR: u8 = 2;
+/// Exit code of SQL parsing and output errors
+const RUN_ERROR: u8 = 1;
+/// Exit code of `check` when the generated files are out of date
+const CHECK_FAILED: u8 = 1;
+/// Exit code of `lint` when a migration has errors
+const LINT_FAILED: u8 = 1;
+/// Quiet time after a change before regenerating, to group the saves of a burst
+const DEBOUNCE: Duration = Duration::from_millis(200);
 
 /// A simple CLI to generate NoORM code.
 #[derive(cParser)]
 #[command(author, version, about, long_about = None)]
 struct Cli {
-    /// The path of the migrations
-    #[arg(short, long)]
-    migrations: String,
-    /// The path of the queries
-    #[arg(short, long)]
-    queries: String,
+    #[command(subcommand)]
+    command: Option<Command>,
+    /// The path of the migrations: a directory of .sql files, a single file, an SQLite database,
+    /// or a .json or .yaml schema
+    #[arg(short, long, global = true)]
+    migrations: Option<PathBuf>,
+    /// The path of the queries: a directory of .sql files, or a single file
+    #[arg(short, long, global = true)]
+    queries: Option<PathBuf>,
+    /// The language of the generated code
+    #[arg(short, long, global = true, ignore_case = true, value_parser = ["rust", "python", "javascript", "dummy"])]
+    lang: Option<String>,
+    /// The SQL dialect of the migrations and queries
+    #[arg(short, long, global = true, ignore_case = true, value_parser = ["generic", "mysql", "postgresql", "postgres", "sqlite"])]
+    dialect: Option<String>,
+    /// The directory where the generated files are written, instead of printing the code
+    #[arg(short, long, global = true)]
+    out: Option<PathBuf>,
+    /// The TOML configuration file: a noorm.toml, or a Cargo.toml with [package.metadata.noorm].
+    /// Without it nor --migrations, noorm.toml is looked for in the current directory and its parents.
+    /// The other options override it
+    #[arg(short, long, global = true)]
+    config: Option<PathBuf>,
+    /// List the relationships between the tables
+    #[arg(short, long, global = true)]
+    relations: bool,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Generate the code in memory and compare it with the output directory,
+    /// printing a diff and failing if they differ
+    Check,
+    /// Regenerate the output directory whenever the migrations or queries change
+    Watch,
+    /// Print the migration turning the schema of the migrations into the desired one
+    Diff {
+        /// The desired schema: a .sql file, a directory of them, an SQLite database, or a .json or .yaml schema
+        schema: PathBuf,
+        /// Save the migration in the migrations directory, numbered after the last one, e.g. `--save add_age`
+        #[arg(long)]
+        save: Option<String>,
+    },
+    /// Print the schema of the migrations as JSON or YAML, or as CREATE statements
+    Export {
+        /// The output format
+        #[arg(long, ignore_case = true, value_parser = ["json", "yaml", "sql"], default_value = "json")]
+        format: String,
+    },
+    /// Print an entity-relationship diagram of the tables of the migrations
+    Diagram {
+        /// The diagram format: a Mermaid erDiagram, or a Graphviz DOT graph
+        #[arg(long, ignore_case = true, value_parser = ["mermaid", "dot"], default_value = "mermaid")]
+        format: String,
+        /// Only the tables matching these names, with `*` wildcards, e.g. `--tables 'person,office*'`
+        #[arg(long, value_delimiter = ',')]
+        tables: Vec<String>,
+        /// Leave out the tables matching these names, with `*` wildcards
+        #[arg(long, value_delimiter = ',')]
+        exclude: Vec<String>,
+    },
+    /// Write the documentation of the tables of the migrations: an index page, and a page per table
+    Docs {
+        /// The directory of the pages; the pages of tables no longer in the schema are removed
+        dir: PathBuf,
+        /// The format of the pages
+        #[arg(long, ignore_case = true, value_parser = ["markdown", "html"], default_value = "markdown")]
+        format: String,
+        /// Only the tables matching these names, with `*` wildcards, e.g. `--tables 'person,office*'`
+        #[arg(long, value_delimiter = ',')]
+        tables: Vec<String>,
+        /// Leave out the tables matching these names, with `*` wildcards
+        #[arg(long, value_delimiter = ',')]
+        exclude: Vec<String>,
+    },
+    /// Print the CREATE TABLE statements of the Rust structs annotated with `#[noorm]`
+    FromRust {
+        /// The Rust source files
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+        /// Save the statements in the migrations directory, numbered after the last migration, e.g. `--save init`
+        #[arg(long)]
+        save: Option<String>,
+    },
+    /// Check the migrations for destructive or locking changes, and the schema they create for design
+    /// problems, failing on errors
+    Lint {
+        /// The migrations to lint, by default all of them: the others are applied without linting
+        files: Vec<PathBuf>,
+        /// The output format
+        #[arg(long, ignore_case = true, value_parser = ["text", "json", "sarif"], default_value = "text")]
+        format: String,
+    },
 }
 
-fn main() {
+fn main() -> ExitCode {
     let cli = Cli::parse();
-    let migrations = PathBuf::from(cli.migrations);
-    let queries = PathBuf::from(cli.queries);
-
-    let config = ParserConfig {
-        sql_dialect: Dialect::Generic,
-        language: Language::new("dummy").expect("Unsupported language!"),
-        migrations,
-        queries,
+    let config = match load_config(&cli) {
+        Ok(config) => config,
+        Err(e) => {
+            report(e.as_ref());
+            return ExitCode::from(CONFIG_ERROR);
+        }
+    };
+    let result = match &cli.command {
+        Some(Command::Diff { schema, save }) => diff(&config, schema, save.as_deref()).map(|()| ExitCode::SUCCESS),
+        Some(Command::Export { format }) => export(&config, format).map(|()| ExitCode::SUCCESS),
+        Some(Command::Diagram { format, tables, exclude }) => diagram(&config, format, tables, exclude).map(|()| ExitCode::SUCCESS),
+        Some(Command::Docs { dir, format, tables, exclude }) => docs(&config, dir, format, tables, exclude).map(|()| ExitCode::SUCCESS),
+        Some(Command::FromRust { files, save }) => from_rust(&config, files, save.as_deref()).map(|()| ExitCode::SUCCESS),
+        Some(Command::Lint { files, format }) => lint(&config, files, format),
+        Some(Command::Watch) => watch(config).map(|()| ExitCode::SUCCESS),
+        Some(Command::Check) | None => run(&cli, config),
+    };
+    result.unwrap_or_else(|e| {
+        report(e.as_ref());
+        ExitCode::from(RUN_ERROR)
+    })
+}
+
+/// Read the configuration file, if any, and apply the command line options over it
+fn load_config(cli: &Cli) -> Result<ParserConfig, Box<dyn Error>> {
+    let mut config = match (&cli.config, &cli.migrations) {
+        (Some(path), _) => ParserConfig::from_file(path)?,
+        (None, Some(_)) => ParserConfig::new(),
+        (None, None) => match ParserConfig::discover() {
+            // Printing the tables of Rust structs needs no migrations
+            Err(ParserConfigError::NotFound(_)) if matches!(cli.command, Some(Command::FromRust { save: None, .. })) => ParserConfig::new(),
+            Err(ParserConfigError::NotFound(_)) => {
+                return Err("No migrations path: pass --migrations, a --config file, or add a noorm.toml".into());
+            }
+            config => config?,
+        },
     };
-    let sql_statement = "
-    CREATE TABLE person ( Id INTEGER NOT NULL, name VARCHAR(255), phone_number VARCHAR(255) );
-    CREATE TABLE office ( Id INTEGER NOT NULL, name VARCHAR(255) );
-    ";
-    let mut parser = Parser::new()
-        .set_config(config)
-        .statement(sql_statement);
-    if parser.parse().is_ok() {
-        println!("{}", parser.output.unwrap())
-    } else {
-        println!("No bueno!");
+    if let Some(migrations) = &cli.migrations {
+        config.migrations = migrations.clone();
+    }
+    if let Some(queries) = &cli.queries {
+        config.queries = queries.clone();
+    }
+    // An explicit language or output directory selects a single target
+    if cli.lang.is_some() || cli.out.is_some() {
+        config.targets.clear();
+    }
+    // Keep the options of the configuration for the language selected
+    if let Some(lang) = cli.lang.as_deref().filter(|lang| !lang.eq_ignore_ascii_case(config.language.name())) {
+        config.language = Language::new(lang).ok_or_else(|| format!("Unsupported language: {}", lang))?;
+    }
+    if let Some(dialect) = &cli.dialect {
+        config.sql_dialect = Dialect::new(dialect).ok_or_else(|| format!("Unsupported dialect: {}", dialect))?;
+    }
+    if let Some(out) = &cli.out {
+        config.output = Some(out.clone());
+    }
+    let has_output = config.output.is_some() || config.targets.iter().any(|target| target.output.is_some());
+    if matches!(cli.command, Some(Command::Check | Command::Watch)) && !has_output {
+        return Err("No output directory: pass --out or set `output` in the configuration".into());
+    }
+    Ok(config)
+}
+
+fn run(cli: &Cli, config: ParserConfig) -> Result<ExitCode, Box<dyn Error>> {
+    let mut parser = Parser::new().set_config(config).load()?;
+    if cli.relations {
+        for relation in parser.schema()?.relations() {
+            println!("{}", relation);
+        }
+        return Ok(ExitCode::SUCCESS);
+    }
+    parser.parse()?;
+    if let Some(Command::Check) = cli.command {
+        let drifts = parser.check_output()?;
+        for drift in &drifts {
+            eprintln!("{}", drift);
+            print!("{}", drift.diff);
+        }
+        if drifts.is_empty() {
+            return Ok(ExitCode::SUCCESS);
+        }
+        eprintln!("error: {} generated files differ, run noorm-gen to regenerate them", drifts.len());
+        return Ok(ExitCode::from(CHECK_FAILED));
+    }
+    if parser.targets.iter().any(|target| target.output.is_some()) {
+        for path in parser.write_output()? {
+            eprintln!("warning: {} is no longer generated, kept as edited by hand", path.display());
+        }
+    }
+    for target in &parser.targets {
+        match &target.output {
+            Some(dir) => eprintln!("Wrote {} {} files to {}", target.files.files.len(), target.language, dir.display()),
+            None => print!("{}", target.code),
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Print or save the SQL changing the schema of the migrations into the one of `schema`
+fn diff(config: &ParserConfig, schema: &Path, save: Option<&str>) -> Result<(), Box<dyn Error>> {
+    let dialect = &config.sql_dialect;
+    let current = match config.migrations.exists() {
+        true => schema_at(&config.migrations, dialect)?,
+        false => Schema::default(),
+    };
+    let target = schema_at(schema, dialect)?;
+    let diff = current.diff(&target);
+    if diff.is_empty() {
+        eprintln!("The migrations are up to date with {}", schema.display());
+        return Ok(());
+    }
+    let sql = diff.to_sql(dialect);
+    match save {
+        Some(name) => {
+            let path = next_migration(&config.migrations, name)?;
+            fs::write(&path, sql)?;
+            eprintln!("Wrote {} changes to {}", diff.changes.len(), path.display());
+        }
+        None => print!("{}", sql),
+    }
+    Ok(())
+}
+
+/// Print the schema of the migrations in a format
+fn export(config: &ParserConfig, format: &str) -> Result<(), Box<dyn Error>> {
+    let schema = schema_at(&config.migrations, &config.sql_dialect)?;
+    match format.to_lowercase().as_str() {
+        "yaml" => print!("{}", schema.to_yaml()),
+        "sql" => print!("{}", schema.create_sql(&config.sql_dialect)),
+        _ => println!("{}", schema.to_json()),
+    }
+    Ok(())
+}
+
+/// Print the diagram of the selected tables of the migrations
+fn diagram(config: &ParserConfig, format: &str, tables: &[String], exclude: &[String]) -> Result<(), Box<dyn Error>> {
+    let schema = schema_at(&config.migrations, &config.sql_dialect)?.subset(tables, exclude);
+    match format.to_lowercase().as_str() {
+        "dot" => print!("{}", schema.to_dot()),
+        _ => print!("{}", schema.to_mermaid()),
+    }
+    Ok(())
+}
+
+/// Write the documentation pages of the selected tables of the migrations
+fn docs(config: &ParserConfig, dir: &Path, format: &str, tables: &[String], exclude: &[String]) -> Result<(), Box<dyn Error>> {
+    let schema = schema_at(&config.migrations, &config.sql_dialect)?.subset(tables, exclude);
+    let files = match format.to_lowercase().as_str() {
+        "html" => schema.to_html_docs(),
+        _ => schema.to_markdown_docs(),
+    };
+    for path in files.write_to(dir)? {
+        eprintln!("warning: {} is no longer generated, kept as edited by hand", dir.join(path).display());
+    }
+    eprintln!("Wrote {} pages to {}", files.files.len(), dir.display());
+    Ok(())
+}
+
+/// Print or save the CREATE TABLE statements of the annotated Rust structs
+fn from_rust(config: &ParserConfig, files: &[PathBuf], save: Option<&str>) -> Result<(), Box<dyn Error>> {
+    let mut schema = Schema::default();
+    for file in files {
+        let code = fs::read_to_string(file).map_err(|source| ParserError::Read { path: file.clone(), source })?;
+        schema.add_rust(&code, Some(&file.display().to_string()), &config.sql_dialect)?;
+    }
+    let sql = schema.create_sql(&config.sql_dialect);
+    match save {
+        Some(name) => {
+            let path = next_migration(&config.migrations, name)?;
+            fs::write(&path, sql)?;
+            eprintln!("Wrote {} tables to {}", schema.tables.len(), path.display());
+        }
+        None => print!("{}", sql),
+    }
+    Ok(())
+}
+
+/// Lint the selected migrations, or all of them, and the tables they create, printing the lints
+fn lint(config: &ParserConfig, files: &[PathBuf], format: &str) -> Result<ExitCode, Box<dyn Error>> {
+    let dialect = &config.sql_dialect;
+    // The tables of a database or schema file are linted when no migration is given
+    let base_schema = read_schema(&config.migrations)?;
+    let is_database = base_schema.is_some();
+    let (mut schema, migrations) = match base_schema {
+        Some(schema) => (schema, Vec::new()),
+        None => (Schema::default(), read_sql_files(&config.migrations)?),
+    };
+    let is_migration = |file: &PathBuf| migrations.iter().any(|(path, _)| same_file(file, path));
+    let mut others = Vec::new();
+    for file in files.iter().filter(|file| !is_migration(file)) {
+        others.extend(read_sql_files(file)?);
+    }
+    let mut lints = Vec::new();
+    let mut linted = Vec::new();
+    if is_database && files.is_empty() {
+        linted.push(config.migrations.display().to_string());
+    }
+    for (path, sql) in migrations.iter().chain(&others) {
+        let name = path.display().to_string();
+        if files.is_empty() || files.iter().any(|file| same_file(file, path)) || !is_migration(path) {
+            lints.extend(schema.lint_migration(&name, sql, dialect)?);
+            linted.push(name);
+        } else {
+            schema.add_sql(sql, Some(&name), dialect)?;
+        }
+    }
+    let mut lints = config.lint.apply(lints);
+    lints.extend(schema.lint(&config.lint).into_iter().filter(|lint| linted.contains(&lint.source)));
+    match format.to_lowercase().as_str() {
+        "json" => println!("{}", lints_to_json(&lints)),
+        "sarif" => println!("{}", lints_to_sarif(&lints)),
+        _ => {
+            for lint in &lints {
+                println!("{}", lint);
+            }
+        }
+    }
+    let errors = lints.iter().filter(|lint| lint.severity == Severity::Error).count();
+    if format.eq_ignore_ascii_case("text") {
+        eprintln!("{} errors, {} warnings", errors, lints.len() - errors);
+    }
+    Ok(if errors > 0 { ExitCode::from(LINT_FAILED) } else { ExitCode::SUCCESS })
+}
+
+/// Whether two paths are the same file
+fn same_file(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+/// Schema of an SQLite database or a JSON or YAML schema file, `None` for SQL files
+fn read_schema(path: &Path) -> Result<Option<Schema>, ParserError> {
+    if is_sqlite_database(path) {
+        return Schema::from_sqlite(path).map(Some);
+    }
+    if is_schema_file(path) {
+        return Schema::from_schema_file(path).map(Some);
+    }
+    Ok(None)
+}
+
+/// Schema of an SQLite database, a schema file, or the SQL files of a directory
+fn schema_at(path: &Path, dialect: &Dialect) -> Result<Schema, ParserError> {
+    match read_schema(path)? {
+        Some(schema) => Ok(schema),
+        None => schema_of(&read_sql_files(path)?, dialect),
+    }
+}
+
+/// Schema of SQL files, in order
+fn schema_of(files: &[(PathBuf, String)], dialect: &Dialect) -> Result<Schema, ParserError> {
+    let names: Vec<String> = files.iter().map(|(path, _)| path.display().to_string()).collect();
+    Schema::from_sources(names.iter().map(String::as_str).zip(files.iter().map(|(_, sql)| sql.as_str())), dialect)
+}
+
+/// Path of a new migration in the directory, numbered after the last one, e.g. `003_add_age.sql`
+fn next_migration(dir: &Path, name: &str) -> io::Result<PathBuf> {
+    if dir.is_file() {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "--save needs a directory of migrations, not a file"));
+    }
+    fs::create_dir_all(dir)?;
+    let (mut last, mut width) = (0, 3);
+    for entry in fs::read_dir(dir)? {
+        let file_name = entry?.file_name();
+        let digits: String = file_name.to_string_lossy().chars().take_while(char::is_ascii_digit).collect();
+        if let Ok(number) = digits.parse::<u64>() {
+            last = last.max(number);
+            width = width.max(digits.len());
+        }
+    }
+    Ok(dir.join(format!("{:0width$}_{}.sql", last + 1, name, width = width)))
+}
+
+/// Regenerate on each burst of changes to the migrations, and check the queries on each burst
+/// of changes to them, until interrupted
+fn watch(config: ParserConfig) -> Result<(), Box<dyn Error>> {
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx)?;
+    let migrations = watch_path(&mut watcher, &config.migrations)?;
+    let queries = watch_path(&mut watcher, &config.queries)?;
+    check_queries(&config);
+    regenerate(&config);
+    eprintln!("Watching {} for changes", config.migrations.display());
+    while let Ok(event) = rx.recv() {
+        let (mut migrations_changed, mut queries_changed) = (false, false);
+        for event in std::iter::once(event).chain(std::iter::from_fn(|| rx.recv_timeout(DEBOUNCE).ok())) {
+            match event {
+                Ok(event) => {
+                    migrations_changed |= migrations.as_ref().is_some_and(|root| is_sql_change(&event, root));
+                    queries_changed |= queries.as_ref().is_some_and(|root| is_sql_change(&event, root));
+                }
+                Err(e) => report(&e),
+            }
+        }
+        // The queries are only checked, so a change of the migrations alone does not parse them again
+        if queries_changed {
+            check_queries(&config);
+        }
+        if migrations_changed {
+            regenerate(&config);
+        }
+    }
+    Ok(())
+}
+
+/// Watch a path of the configuration, returning its canonical path as named by the events,
+/// or none when empty; a file is watched through its directory, to survive being replaced
+fn watch_path(watcher: &mut impl Watcher, path: &Path) -> Result<Option<PathBuf>, Box<dyn Error>> {
+    if path.as_os_str().is_empty() {
+        return Ok(None);
+    }
+    let path = path.canonicalize().map_err(|source| ParserError::Read { path: path.to_path_buf(), source })?;
+    match path.parent().filter(|_| path.is_file()) {
+        Some(dir) => watcher.watch(dir, RecursiveMode::NonRecursive)?,
+        None => watcher.watch(&path, RecursiveMode::Recursive)?,
+    }
+    Ok(Some(path))
+}
+
+/// Whether a watcher event creates, changes or removes an SQL file of a watched directory,
+/// or a watched file itself, e.g. an SQLite database or a schema file
+fn is_sql_change(event: &notify::Event, root: &Path) -> bool {
+    let is_sql = |path: &Path| path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("sql"));
+    matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_))
+        && event.paths.iter().any(|path| path == root || (path.starts_with(root) && is_sql(path)))
+}
+
+/// Check that the queries parse; errors are printed, not returned
+fn check_queries(config: &ParserConfig) {
+    match Parser::new().set_config(config.clone()).check_queries() {
+        Ok(()) if !config.queries.as_os_str().is_empty() => eprintln!("The queries parse"),
+        Ok(()) => {}
+        Err(e) => report(&e),
+    }
+}
+
+/// Regenerate the code of the migrations, writing only the targets that changed; errors are printed, not returned
+fn regenerate(config: &ParserConfig) {
+    match regenerate_files(config) {
+        Ok(drifts) if drifts.is_empty() => eprintln!("The generated files are up to date"),
+        Ok(drifts) => {
+            for drift in drifts {
+                match drift.kind {
+                    // Stale files edited by hand are left in place
+                    DriftKind::Removed if drift.path.exists() => eprintln!("Kept {}, edited by hand", drift.path.display()),
+                    DriftKind::Removed => eprintln!("Removed {}", drift.path.display()),
+                    _ => eprintln!("Wrote {}", drift.path.display()),
+                }
+            }
+        }
+        Err(e) => report(&e),
+    }
+}
+
+/// Regenerate the code of the migrations, returning the files written or removed
+fn regenerate_files(config: &ParserConfig) -> Result<Vec<Drift>, ParserError> {
+    let mut parser = Parser::new().set_config(config.clone()).load_migrations()?;
+    parser.parse()?;
+    parser.update_output()
+}
+
+/// Print an error and its causes to stderr, skipping the causes already in the message
+fn report(error: &dyn Error) {
+    let mut message = error.to_string();
+    eprintln!("error: {}", message);
+    let mut source = error.source();
+    while let Some(cause) = source {
+        let text = cause.to_string();
+        if !message.contains(&text) {
+            eprintln!("  caused by: {}", text);
+            message = text;
+        }
+        source = cause.source();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use notify::event::{AccessKind, CreateKind, ModifyKind, RemoveKind};
+
+    fn event(kind: EventKind, path: impl Into<PathBuf>) -> notify::Event {
+        notify::Event::new(kind).add_path(path.into())
+    }
+
+    #[test]
+    fn test_is_sql_change() {
+        let dir = Path::new("/project/migrations");
+        assert!(is_sql_change(&event(EventKind::Create(CreateKind::File), dir.join("002_office.sql")), dir));
+        assert!(is_sql_change(&event(EventKind::Modify(ModifyKind::Any), dir.join("sub/001_PERSON.SQL")), dir));
+        assert!(is_sql_change(&event(EventKind::Remove(RemoveKind::File), dir.join("001_person.sql")), dir));
+        assert!(!is_sql_change(&event(EventKind::Access(AccessKind::Any), dir.join("001_person.sql")), dir));
+        assert!(!is_sql_change(&event(EventKind::Modify(ModifyKind::Any), dir.join("notes.txt")), dir));
+        assert!(!is_sql_change(&event(EventKind::Modify(ModifyKind::Any), "/project/queries/get.sql"), dir));
+
+        // A database or schema file is watched through its directory: only the file itself counts
+        let file = Path::new("/project/schema.yaml");
+        assert!(is_sql_change(&event(EventKind::Modify(ModifyKind::Any), file), file));
+        assert!(is_sql_change(&event(EventKind::Create(CreateKind::File), file), file));
+        assert!(!is_sql_change(&event(EventKind::Modify(ModifyKind::Any), "/project/other.yaml"), file));
+        assert!(!is_sql_change(&event(EventKind::Modify(ModifyKind::Any), "/project/init.sql"), file));
+    }
+
+    #[test]
+    fn test_regenerate_on_change() {
+        let dir = std::env::temp_dir().join(format!("noorm-gen-watch-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("migrations")).unwrap();
+        fs::create_dir_all(dir.join("queries")).unwrap();
+        fs::write(dir.join("migrations/001_person.sql"), "CREATE TABLE person ( id INTEGER PRIMARY KEY );").unwrap();
+        // Not parsed when regenerating: only the migrations are loaded
+        fs::write(dir.join("queries/broken.sql"), "SELEC oops").unwrap();
+
+        let toml = "migrations = \"migrations\"\nqueries = \"queries\"\n\n\
+            [[targets]]\nlanguage = \"python\"\noutput = \"py\"\n\n\
+            [[targets]]\nlanguage = \"javascript\"\noutput = \"js\"\n";
+        fs::write(dir.join("noorm.toml"), toml).unwrap();
+        let config = ParserConfig::from_file(dir.join("noorm.toml")).unwrap();
+
+        let drifts = regenerate_files(&config).unwrap();
+        assert!(drifts.iter().any(|drift| drift.path == dir.join("py/person.py") && drift.kind == DriftKind::Missing));
+        assert!(drifts.iter().any(|drift| drift.path.starts_with(dir.join("js"))));
+        assert!(regenerate_files(&config).unwrap().is_empty());
+
+        // Only the target that differs is written
+        fs::remove_file(dir.join("py/person.py")).unwrap();
+        let drifts = regenerate_files(&config).unwrap();
+        assert_eq!(drifts.iter().map(|drift| &drift.path).collect::<Vec<_>>(), [&dir.join("py/person.py")]);
+        assert!(dir.join("py/person.py").exists());
+
+        // A new migration adds its table to every target
+        fs::write(dir.join("migrations/002_office.sql"), "CREATE TABLE office ( id INTEGER PRIMARY KEY );").unwrap();
+        let drifts = regenerate_files(&config).unwrap();
+        assert!(drifts.iter().any(|drift| drift.path == dir.join("py/office.py")));
+        assert!(drifts.iter().any(|drift| drift.path.starts_with(dir.join("js")) && drift.kind == DriftKind::Missing));
+        assert!(dir.join("py/office.py").exists());
+
+        // Watching fails instead of waiting forever on paths that do not exist
+        let missing = ParserConfig { migrations: dir.join("missing"), ..config };
+        assert!(watch(missing).is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
     }
 }
@@ -0,0 +1,177 @@
+use std::fmt::Write;
+
+use super::{Column, ForeignKey, Schema, Table};
+
+/// Cardinality of the two ends of a foreign key
+struct Cardinality {
+    /// The referencing row may have no referenced row, the foreign key is nullable
+    optional: bool,
+    /// A referenced row has at most one referencing row, the foreign key is unique
+    unique: bool,
+}
+
+impl Schema {
+    /// Mermaid `erDiagram` of the tables, with their columns, keys and relationships.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use noorm::prelude::*;
+    /// use noorm::parser::Schema;
+    ///
+    /// let sql = "CREATE TABLE person ( id INT PRIMARY KEY, name VARCHAR(100) );
+    ///     CREATE TABLE office ( id INT PRIMARY KEY, person_id INT NULL REFERENCES person(id) )";
+    /// let schema = Schema::from_sql(sql, &Dialect::Generic).unwrap();
+    /// assert_eq!(schema.to_mermaid(), "erDiagram
+    ///     person {
+    ///         INT id PK
+    ///         VARCHAR(100) name
+    ///     }
+    ///     office {
+    ///         INT id PK
+    ///         INT person_id FK
+    ///     }
+    ///     person |o--o{ office : \"person_id\"
+    /// ");
+    /// ```
+    pub fn to_mermaid(&self) -> String {
+        let mut mermaid = String::from("erDiagram\n");
+        for table in self.diagram_tables() {
+            let _ = writeln!(mermaid, "    {} {{", table.name);
+            for column in &table.columns {
+                let _ = write!(mermaid, "        {} {}", mermaid_type(column), column.name);
+                let keys = column_keys(table, column);
+                if !keys.is_empty() {
+                    let _ = write!(mermaid, " {}", keys.join(", "));
+                }
+                if let Some(comment) = &column.comment {
+                    let _ = write!(mermaid, " \"{}\"", comment.replace('"', "'").replace('\n', " "));
+                }
+                mermaid.push('\n');
+            }
+            mermaid.push_str("    }\n");
+        }
+        for (table, foreign_key) in self.diagram_foreign_keys() {
+            let cardinality = cardinality(table, foreign_key);
+            let _ = writeln!(
+                mermaid,
+                "    {} {}--{} {} : \"{}\"",
+                foreign_key.foreign_table,
+                if cardinality.optional { "|o" } else { "||" },
+                if cardinality.unique { "o|" } else { "o{" },
+                table.name,
+                foreign_key.columns.join(", "),
+            );
+        }
+        mermaid
+    }
+
+    /// Graphviz DOT graph of the tables, with their columns, keys and relationships
+    /// drawn with crow's foot arrows.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use noorm::prelude::*;
+    /// use noorm::parser::Schema;
+    ///
+    /// let sql = "CREATE TABLE person ( id INT PRIMARY KEY );
+    ///     CREATE TABLE office ( id INT PRIMARY KEY, person_id INT REFERENCES person(id) )";
+    /// let dot = Schema::from_sql(sql, &Dialect::Generic).unwrap().to_dot();
+    /// assert!(dot.starts_with("digraph schema {\n"));
+    /// assert!(dot.contains("\"office\":\"person_id\" -> \"person\":\"id\" [arrowtail=crowodot, arrowhead=teetee];"));
+    /// ```
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph schema {\n");
+        dot.push_str("    rankdir=LR;\n");
+        dot.push_str("    node [shape=plaintext, fontname=\"Helvetica\"];\n");
+        dot.push_str("    edge [dir=both];\n");
+        for table in self.diagram_tables() {
+            let _ = writeln!(dot, "    \"{}\" [label=<", table.name);
+            dot.push_str("        <TABLE BORDER=\"0\" CELLBORDER=\"1\" CELLSPACING=\"0\" CELLPADDING=\"4\">\n");
+            let _ = writeln!(dot, "        <TR><TD COLSPAN=\"3\" BGCOLOR=\"lightgrey\"><B>{}</B></TD></TR>", html(&table.name));
+            for column in &table.columns {
+                let name = match table.is_primary_key(&column.name) {
+                    true => format!("<U>{}</U>", html(&column.name)),
+                    false => html(&column.name),
+                };
+                let data_type = match column.nullable {
+                    true => format!("{} NULL", column.data_type),
+                    false => column.data_type.to_string(),
+                };
+                let _ = writeln!(
+                    dot,
+                    "        <TR><TD PORT=\"{}\" ALIGN=\"LEFT\">{}</TD><TD ALIGN=\"LEFT\">{}</TD><TD>{}</TD></TR>",
+                    html(&column.name), name, html(&data_type), column_keys(table, column).join(", "),
+                );
+            }
+            dot.push_str("        </TABLE>\n    >];\n");
+        }
+        for (table, foreign_key) in self.diagram_foreign_keys() {
+            let cardinality = cardinality(table, foreign_key);
+            let _ = writeln!(
+                dot,
+                "    \"{}\":\"{}\" -> \"{}\":\"{}\" [arrowtail={}, arrowhead={}];",
+                table.name,
+                foreign_key.columns.first().map(String::as_str).unwrap_or_default(),
+                foreign_key.foreign_table,
+                foreign_key.referred_columns.first().map(String::as_str).unwrap_or_default(),
+                if cardinality.unique { "teeodot" } else { "crowodot" },
+                if cardinality.optional { "teeodot" } else { "teetee" },
+            );
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Tables drawn in the diagrams, leaving out the views
+    fn diagram_tables(&self) -> impl Iterator<Item = &Table> {
+        self.tables.iter().filter(|table| !table.is_view())
+    }
+
+    /// Foreign keys between tables of the diagram
+    fn diagram_foreign_keys(&self) -> impl Iterator<Item = (&Table, &ForeignKey)> {
+        self.diagram_tables()
+            .flat_map(|table| table.foreign_keys.iter().map(move |foreign_key| (table, foreign_key)))
+            .filter(|(_, foreign_key)| self.diagram_tables().any(|table| table.name.eq_ignore_ascii_case(&foreign_key.foreign_table)))
+    }
+}
+
+/// `PK`, `FK` and `UK` marks of a column
+pub(super) fn column_keys(table: &Table, column: &Column) -> Vec<&'static str> {
+    let mut keys = Vec::new();
+    if table.is_primary_key(&column.name) {
+        keys.push("PK");
+    }
+    if table.foreign_keys.iter().any(|fk| fk.columns.iter().any(|c| c.eq_ignore_ascii_case(&column.name))) {
+        keys.push("FK");
+    }
+    if table.unique_keys.iter().any(|key| key.len() == 1 && key[0].eq_ignore_ascii_case(&column.name)) {
+        keys.push("UK");
+    }
+    keys
+}
+
+fn cardinality(table: &Table, foreign_key: &ForeignKey) -> Cardinality {
+    let same_columns = |columns: &[String]| {
+        columns.len() == foreign_key.columns.len()
+            && columns.iter().all(|c| foreign_key.columns.iter().any(|fk| fk.eq_ignore_ascii_case(c)))
+    };
+    Cardinality {
+        optional: foreign_key.columns.iter().any(|name| table.column(name).is_some_and(|column| column.nullable)),
+        unique: same_columns(&table.primary_key)
+            || table.unique_keys.iter().any(|key| same_columns(key))
+            || table.indexes.iter().any(|index| index.unique && index.predicate.is_none() && same_columns(&index.columns)),
+    }
+}
+
+/// Mermaid attribute types are single words, e.g. `DOUBLE_PRECISION` or `NUMERIC(10_2)`
+fn mermaid_type(column: &Column) -> String {
+    let data_type = column.data_type.to_string().split_whitespace().collect::<Vec<_>>().join("_").replace(',', "_");
+    if data_type.is_empty() { "ANY".to_string() } else { data_type }
+}
+
+/// Escape text for HTML, or a Graphviz HTML label
+pub(super) fn html(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
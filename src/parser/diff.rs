@@ -0,0 +1,689 @@
+use std::fmt;
+
+use super::schema::is_serial_type;
+use super::{Column, Dialect, ForeignKey, Index, Schema, Table, TableKind};
+
+/// A change turning a schema into another, see `Schema::diff`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SchemaChange {
+    /// Create a table or view
+    CreateTable(Table),
+    /// Drop a table or view
+    DropTable(Table),
+    /// Rename a table, guessed from a dropped and a created table with the same columns
+    RenameTable {
+        /// Current name
+        from: String,
+        /// New name
+        to: String,
+    },
+    /// Add a column to a table
+    AddColumn {
+        /// Name of the table
+        table: String,
+        /// New column
+        column: Column,
+    },
+    /// Drop a column of a table
+    DropColumn {
+        /// Name of the table
+        table: String,
+        /// Name of the column
+        column: String,
+    },
+    /// Rename a column, guessed from a dropped and an added column with the same definition
+    RenameColumn {
+        /// Name of the table
+        table: String,
+        /// Current name
+        from: String,
+        /// New name
+        to: String,
+    },
+    /// Change the type, nullability or default of a column
+    AlterColumn {
+        /// Name of the table
+        table: String,
+        /// Current column
+        from: Box<Column>,
+        /// New column
+        to: Box<Column>,
+    },
+    /// Replace the primary key of a table
+    SetPrimaryKey {
+        /// Name of the table
+        table: String,
+        /// Current key columns, empty without a primary key
+        from: Vec<String>,
+        /// New key columns, empty to drop the primary key
+        to: Vec<String>,
+    },
+    /// Add a `UNIQUE` constraint
+    AddUnique {
+        /// Name of the table
+        table: String,
+        /// Columns of the constraint
+        columns: Vec<String>,
+    },
+    /// Drop a `UNIQUE` constraint
+    DropUnique {
+        /// Name of the table
+        table: String,
+        /// Columns of the constraint
+        columns: Vec<String>,
+    },
+    /// Add a foreign key
+    AddForeignKey {
+        /// Name of the table
+        table: String,
+        /// New foreign key
+        foreign_key: ForeignKey,
+    },
+    /// Drop a foreign key
+    DropForeignKey {
+        /// Name of the table
+        table: String,
+        /// Dropped foreign key
+        foreign_key: ForeignKey,
+    },
+    /// Create an index
+    CreateIndex {
+        /// Name of the table
+        table: String,
+        /// New index
+        index: Index,
+    },
+    /// Drop an index
+    DropIndex {
+        /// Name of the table
+        table: String,
+        /// Dropped index
+        index: Index,
+    },
+}
+
+/// Changes turning a schema into a target one, in the order they are applied.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SchemaDiff {
+    /// The changes, ordered so that each statement finds what it depends on
+    pub changes: Vec<SchemaChange>,
+    /// The target schema, for the tables rebuilt by SQLite
+    target: Schema,
+}
+
+/// Renames guessed while comparing the schemas, in terms of the target names
+#[derive(Default)]
+struct Renames {
+    /// Current and new table names
+    tables: Vec<(String, String)>,
+    /// Table, current and new column names
+    columns: Vec<(String, String, String)>,
+}
+
+impl Schema {
+    /// Changes turning the schema into the `target` one, e.g. the schema replayed from the
+    /// migrations into the one of a `schema.sql`.
+    ///
+    /// A dropped table and a created one with the same columns are taken for a rename,
+    /// and so are a dropped column and an added one with the same definition, when
+    /// there is no other candidate. Changed views are dropped and created again.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use noorm::prelude::*;
+    /// use noorm::parser::Schema;
+    ///
+    /// let current = Schema::from_sql("CREATE TABLE person ( id INTEGER PRIMARY KEY, name TEXT )", &Dialect::PostgreSQL).unwrap();
+    /// let target = Schema::from_sql("CREATE TABLE person ( id INTEGER PRIMARY KEY, full_name TEXT, age INTEGER NULL )", &Dialect::PostgreSQL).unwrap();
+    /// let sql = current.diff(&target).to_sql(&Dialect::PostgreSQL);
+    /// assert!(sql.contains("ALTER TABLE person RENAME COLUMN name TO full_name;"));
+    /// assert!(sql.contains("ALTER TABLE person ADD COLUMN age INTEGER NULL;"));
+    /// ```
+    pub fn diff(&self, target: &Schema) -> SchemaDiff {
+        let mut changes = Vec::new();
+        let mut renames = Renames::default();
+
+        let dropped: Vec<&Table> = self.tables.iter().filter(|t| target.table(&t.name).is_none()).collect();
+        let created: Vec<&Table> = target.tables.iter().filter(|t| self.table(&t.name).is_none()).collect();
+        for from in dropped.iter().filter(|t| !t.is_view()) {
+            let same_columns = |a: &Table, b: &Table| !a.is_view() && !b.is_view() && same_definitions(&a.columns, &b.columns);
+            let candidates: Vec<&&Table> = created.iter().filter(|to| same_columns(from, to)).collect();
+            if let [to] = candidates.as_slice() {
+                if dropped.iter().filter(|other| same_columns(other, to)).count() == 1 {
+                    renames.tables.push((from.name.clone(), to.name.clone()));
+                    changes.push(SchemaChange::RenameTable { from: from.name.clone(), to: to.name.clone() });
+                }
+            }
+        }
+        let renamed_from = |name: &str| renames.tables.iter().any(|(from, _)| from.eq_ignore_ascii_case(name));
+        let renamed_to = |name: &str| renames.tables.iter().any(|(_, to)| to.eq_ignore_ascii_case(name));
+        let dropped: Vec<&Table> = dropped.into_iter().filter(|t| !renamed_from(&t.name)).collect();
+        let mut created: Vec<&Table> = created.into_iter().filter(|t| !renamed_to(&t.name)).collect();
+
+        let mut pairs: Vec<(&Table, &Table)> = Vec::new();
+        for to in &target.tables {
+            let from_name = renames.tables.iter()
+                .find(|(_, new)| new.eq_ignore_ascii_case(&to.name))
+                .map_or(to.name.as_str(), |(old, _)| old.as_str());
+            let Some(from) = self.table(from_name) else { continue };
+            if from.is_view() || to.is_view() {
+                if from.kind != to.kind || from.definition != to.definition {
+                    changes.push(SchemaChange::DropTable(from.clone()));
+                    created.push(to);
+                }
+            } else {
+                diff_columns(from, to, &mut changes, &mut renames);
+                pairs.push((from, to));
+            }
+        }
+        for (from, to) in pairs {
+            diff_constraints(from, to, &renames, &mut changes);
+        }
+        for table in dependency_order(created) {
+            changes.push(SchemaChange::CreateTable(table.clone()));
+        }
+        for table in dependency_order(dropped).into_iter().rev() {
+            changes.push(SchemaChange::DropTable(table.clone()));
+        }
+
+        changes.sort_by_key(SchemaChange::phase);
+        SchemaDiff { changes, target: target.clone() }
+    }
+}
+
+/// Add the column changes of a table, guessing the renames
+fn diff_columns(from: &Table, to: &Table, changes: &mut Vec<SchemaChange>, renames: &mut Renames) {
+    let table = &to.name;
+    let dropped: Vec<&Column> = from.columns.iter().filter(|c| to.column(&c.name).is_none()).collect();
+    let added: Vec<&Column> = to.columns.iter().filter(|c| from.column(&c.name).is_none()).collect();
+    let mut renamed: Vec<(&Column, &Column)> = Vec::new();
+    for old in &dropped {
+        let candidates: Vec<&&Column> = added.iter().filter(|new| same_definition(old, new)).collect();
+        if let [new] = candidates.as_slice() {
+            if dropped.iter().filter(|other| same_definition(other, new)).count() == 1 {
+                renamed.push((old, new));
+            }
+        }
+    }
+    for (old, new) in &renamed {
+        renames.columns.push((table.clone(), old.name.clone(), new.name.clone()));
+        changes.push(SchemaChange::RenameColumn { table: table.clone(), from: old.name.clone(), to: new.name.clone() });
+    }
+    for column in added.iter().filter(|c| !renamed.iter().any(|(_, new)| new.name == c.name)) {
+        changes.push(SchemaChange::AddColumn { table: table.clone(), column: (*column).clone() });
+    }
+    for column in dropped.iter().filter(|c| !renamed.iter().any(|(old, _)| old.name == c.name)) {
+        changes.push(SchemaChange::DropColumn { table: table.clone(), column: column.name.clone() });
+    }
+    for new in &to.columns {
+        if let Some(old) = from.column(&new.name).filter(|old| !same_definition(old, new)) {
+            changes.push(SchemaChange::AlterColumn { table: table.clone(), from: Box::new(old.clone()), to: Box::new(new.clone()) });
+        }
+    }
+}
+
+/// Add the changes of the keys, foreign keys and indexes of a table
+fn diff_constraints(from: &Table, to: &Table, renames: &Renames, changes: &mut Vec<SchemaChange>) {
+    let table = &to.name;
+    let columns = |names: &[String]| renames.columns_of(table, names);
+
+    let primary_key = columns(&from.primary_key);
+    if !same_names(&primary_key, &to.primary_key) {
+        changes.push(SchemaChange::SetPrimaryKey { table: table.clone(), from: primary_key, to: to.primary_key.clone() });
+    }
+
+    let unique_keys: Vec<Vec<String>> = from.unique_keys.iter().map(|key| columns(key)).collect();
+    for (old, renamed) in from.unique_keys.iter().zip(&unique_keys) {
+        if !to.unique_keys.iter().any(|other| same_names(renamed, other)) {
+            changes.push(SchemaChange::DropUnique { table: from.name.clone(), columns: old.clone() });
+        }
+    }
+    for key in to.unique_keys.iter().filter(|key| !unique_keys.iter().any(|other| same_names(key, other))) {
+        changes.push(SchemaChange::AddUnique { table: table.clone(), columns: key.clone() });
+    }
+
+    let foreign_keys: Vec<ForeignKey> = from.foreign_keys.iter().map(|fk| {
+        let foreign_table = renames.table(&fk.foreign_table);
+        ForeignKey {
+            columns: columns(&fk.columns),
+            referred_columns: renames.columns_of(&foreign_table, &fk.referred_columns),
+            foreign_table,
+            ..fk.clone()
+        }
+    }).collect();
+    for (old, renamed) in from.foreign_keys.iter().zip(&foreign_keys) {
+        if !to.foreign_keys.iter().any(|fk| same_foreign_key(renamed, fk)) {
+            changes.push(SchemaChange::DropForeignKey { table: from.name.clone(), foreign_key: old.clone() });
+        }
+    }
+    for foreign_key in to.foreign_keys.iter().filter(|fk| !foreign_keys.iter().any(|other| same_foreign_key(fk, other))) {
+        changes.push(SchemaChange::AddForeignKey { table: table.clone(), foreign_key: foreign_key.clone() });
+    }
+
+    let indexes: Vec<Index> = from.indexes.iter().map(|index| Index { columns: columns(&index.columns), ..index.clone() }).collect();
+    for (old, renamed) in from.indexes.iter().zip(&indexes) {
+        if !to.indexes.iter().any(|index| same_index(renamed, index)) {
+            changes.push(SchemaChange::DropIndex { table: from.name.clone(), index: old.clone() });
+        }
+    }
+    for index in to.indexes.iter().filter(|index| !indexes.iter().any(|other| same_index(index, other))) {
+        changes.push(SchemaChange::CreateIndex { table: table.clone(), index: index.clone() });
+    }
+}
+
+impl Renames {
+    /// New name of a table
+    fn table(&self, name: &str) -> String {
+        self.tables.iter()
+            .find(|(from, _)| from.eq_ignore_ascii_case(name))
+            .map_or(name, |(_, to)| to.as_str())
+            .to_string()
+    }
+
+    /// New names of columns of a table, given by its new name
+    fn columns_of(&self, table: &str, names: &[String]) -> Vec<String> {
+        names.iter().map(|name| {
+            self.columns.iter()
+                .find(|(t, from, _)| t.eq_ignore_ascii_case(table) && from.eq_ignore_ascii_case(name))
+                .map_or(name, |(_, _, to)| to)
+                .clone()
+        }).collect()
+    }
+}
+
+/// Order tables so that the ones referenced by foreign keys come first
+pub(super) fn dependency_order(mut pending: Vec<&Table>) -> Vec<&Table> {
+    let mut ordered = Vec::new();
+    while !pending.is_empty() {
+        let is_ready = |table: &Table, pending: &[&Table]| table.foreign_keys.iter().all(|fk| {
+            fk.foreign_table.eq_ignore_ascii_case(&table.name) || !pending.iter().any(|p| p.name.eq_ignore_ascii_case(&fk.foreign_table))
+        });
+        let Some(position) = pending.iter().position(|table| is_ready(table, &pending)) else {
+            // Tables referencing each other, in declaration order
+            ordered.append(&mut pending);
+            break;
+        };
+        ordered.push(pending.remove(position));
+    }
+    ordered
+}
+
+fn same_names(a: &[String], b: &[String]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).all(|(a, b)| a.eq_ignore_ascii_case(b))
+}
+
+/// Same type, nullability and default, regardless of the name
+fn same_definition(a: &Column, b: &Column) -> bool {
+    a.data_type == b.data_type && a.nullable == b.nullable && a.default == b.default
+}
+
+fn same_definitions(a: &[Column], b: &[Column]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).all(|(a, b)| a.name.eq_ignore_ascii_case(&b.name) && same_definition(a, b))
+}
+
+fn same_foreign_key(a: &ForeignKey, b: &ForeignKey) -> bool {
+    same_names(&a.columns, &b.columns)
+        && a.foreign_table.eq_ignore_ascii_case(&b.foreign_table)
+        && same_names(&a.referred_columns, &b.referred_columns)
+        && a.on_delete == b.on_delete
+        && a.on_update == b.on_update
+}
+
+fn same_index(a: &Index, b: &Index) -> bool {
+    same_names(&a.columns, &b.columns) && a.unique == b.unique && a.predicate == b.predicate
+}
+
+impl SchemaChange {
+    /// Position of the change in a migration: drops of dependent objects first, then renames,
+    /// creations and alterations, and the constraints and views depending on them last
+    fn phase(&self) -> u8 {
+        match self {
+            SchemaChange::DropTable(table) if table.is_view() => 0,
+            SchemaChange::DropForeignKey { .. } | SchemaChange::DropIndex { .. } | SchemaChange::DropUnique { .. } => 1,
+            SchemaChange::RenameTable { .. } => 2,
+            SchemaChange::RenameColumn { .. } => 3,
+            SchemaChange::CreateTable(table) if !table.is_view() => 4,
+            SchemaChange::AddColumn { .. } => 5,
+            SchemaChange::AlterColumn { .. } | SchemaChange::SetPrimaryKey { .. } => 6,
+            SchemaChange::DropColumn { .. } => 7,
+            SchemaChange::DropTable(_) => 8,
+            SchemaChange::AddUnique { .. } => 9,
+            SchemaChange::AddForeignKey { .. } | SchemaChange::CreateIndex { .. } => 10,
+            SchemaChange::CreateTable(_) => 11,
+        }
+    }
+
+    /// Name of the table changed
+    pub(super) fn table(&self) -> &str {
+        match self {
+            SchemaChange::CreateTable(table) | SchemaChange::DropTable(table) => &table.name,
+            SchemaChange::RenameTable { to, .. } => to,
+            SchemaChange::AddColumn { table, .. }
+            | SchemaChange::DropColumn { table, .. }
+            | SchemaChange::RenameColumn { table, .. }
+            | SchemaChange::AlterColumn { table, .. }
+            | SchemaChange::SetPrimaryKey { table, .. }
+            | SchemaChange::AddUnique { table, .. }
+            | SchemaChange::DropUnique { table, .. }
+            | SchemaChange::AddForeignKey { table, .. }
+            | SchemaChange::DropForeignKey { table, .. }
+            | SchemaChange::CreateIndex { table, .. }
+            | SchemaChange::DropIndex { table, .. } => table,
+        }
+    }
+
+    /// SQLite cannot `ALTER` columns nor constraints: the table is rebuilt instead
+    fn needs_rebuild(&self, dialect: &Dialect) -> bool {
+        *dialect == Dialect::SQLite && matches!(
+            self,
+            SchemaChange::AlterColumn { .. }
+                | SchemaChange::SetPrimaryKey { .. }
+                | SchemaChange::AddUnique { .. }
+                | SchemaChange::DropUnique { .. }
+                | SchemaChange::AddForeignKey { .. }
+                | SchemaChange::DropForeignKey { .. }
+        )
+    }
+
+    /// Statements applying the change
+    fn statements(&self, dialect: &Dialect) -> Vec<String> {
+        let is_mysql = *dialect == Dialect::MySQL;
+        match self {
+            SchemaChange::CreateTable(table) => table.create_statements(dialect),
+            SchemaChange::DropTable(table) => vec![format!("DROP {} {}", kind_keyword(table.kind), table.name)],
+            SchemaChange::RenameTable { from, to } => vec![format!("ALTER TABLE {} RENAME TO {}", from, to)],
+            SchemaChange::AddColumn { table, column } => {
+                vec![format!("ALTER TABLE {} ADD COLUMN {}", table, column_definition(column, false, dialect))]
+            }
+            SchemaChange::DropColumn { table, column } => vec![format!("ALTER TABLE {} DROP COLUMN {}", table, column)],
+            SchemaChange::RenameColumn { table, from, to } => vec![format!("ALTER TABLE {} RENAME COLUMN {} TO {}", table, from, to)],
+            SchemaChange::AlterColumn { table, to, .. } if is_mysql => {
+                vec![format!("ALTER TABLE {} MODIFY COLUMN {}", table, column_definition(to, false, dialect))]
+            }
+            SchemaChange::AlterColumn { table, from, to } => {
+                let alter = |action: String| format!("ALTER TABLE {} ALTER COLUMN {} {}", table, to.name, action);
+                let mut statements = Vec::new();
+                if from.data_type != to.data_type {
+                    statements.push(alter(format!("SET DATA TYPE {}", to.data_type)));
+                }
+                if from.nullable != to.nullable {
+                    statements.push(alter(if to.nullable { "DROP NOT NULL" } else { "SET NOT NULL" }.to_string()));
+                }
+                if from.default != to.default {
+                    statements.push(alter(to.default.as_ref().map_or("DROP DEFAULT".to_string(), |expr| format!("SET DEFAULT {}", expr))));
+                }
+                statements
+            }
+            SchemaChange::SetPrimaryKey { table, from, to } => {
+                let mut statements = Vec::new();
+                if !from.is_empty() {
+                    statements.push(match is_mysql {
+                        true => format!("ALTER TABLE {} DROP PRIMARY KEY", table),
+                        false => format!("ALTER TABLE {} DROP CONSTRAINT {}_pkey", table, table),
+                    });
+                }
+                if !to.is_empty() {
+                    statements.push(format!("ALTER TABLE {} ADD PRIMARY KEY ({})", table, to.join(", ")));
+                }
+                statements
+            }
+            SchemaChange::AddUnique { table, columns } => vec![format!("ALTER TABLE {} ADD UNIQUE ({})", table, columns.join(", "))],
+            SchemaChange::DropUnique { table, columns } => {
+                vec![format!("ALTER TABLE {} DROP CONSTRAINT {}", table, unique_key_name(table, columns, dialect))]
+            }
+            SchemaChange::AddForeignKey { table, foreign_key } => {
+                vec![format!("ALTER TABLE {} ADD {}", table, foreign_key_definition(foreign_key))]
+            }
+            SchemaChange::DropForeignKey { table, foreign_key } => {
+                vec![format!("ALTER TABLE {} DROP CONSTRAINT {}", table, foreign_key_name(table, foreign_key))]
+            }
+            SchemaChange::CreateIndex { table, index } => vec![index_definition(table, index, dialect)],
+            SchemaChange::DropIndex { table, index } if is_mysql => {
+                vec![format!("DROP INDEX {} ON {}", index_name(table, index, dialect), table)]
+            }
+            SchemaChange::DropIndex { table, index } => vec![format!("DROP INDEX {}", index_name(table, index, dialect))],
+        }
+    }
+}
+
+/// Change listing, e.g. `rename column person.name to full_name`
+impl fmt::Display for SchemaChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SchemaChange::CreateTable(table) => write!(f, "create {} {}", kind_keyword(table.kind).to_lowercase(), table.name),
+            SchemaChange::DropTable(table) => write!(f, "drop {} {}", kind_keyword(table.kind).to_lowercase(), table.name),
+            SchemaChange::RenameTable { from, to } => write!(f, "rename table {} to {}", from, to),
+            SchemaChange::AddColumn { table, column } => write!(f, "add column {}.{}", table, column.name),
+            SchemaChange::DropColumn { table, column } => write!(f, "drop column {}.{}", table, column),
+            SchemaChange::RenameColumn { table, from, to } => write!(f, "rename column {}.{} to {}", table, from, to),
+            SchemaChange::AlterColumn { table, to, .. } => write!(f, "alter column {}.{}", table, to.name),
+            SchemaChange::SetPrimaryKey { table, to, .. } => write!(f, "set the primary key of {} to ({})", table, to.join(", ")),
+            SchemaChange::AddUnique { table, columns } => write!(f, "add unique key {}({})", table, columns.join(", ")),
+            SchemaChange::DropUnique { table, columns } => write!(f, "drop unique key {}({})", table, columns.join(", ")),
+            SchemaChange::AddForeignKey { table, foreign_key: fk } => {
+                write!(f, "add foreign key {}({}) -> {}({})", table, fk.columns.join(", "), fk.foreign_table, fk.referred_columns.join(", "))
+            }
+            SchemaChange::DropForeignKey { table, foreign_key: fk } => {
+                write!(f, "drop foreign key {}({}) -> {}({})", table, fk.columns.join(", "), fk.foreign_table, fk.referred_columns.join(", "))
+            }
+            SchemaChange::CreateIndex { table, index } => write!(f, "create index on {}({})", table, index.columns.join(", ")),
+            SchemaChange::DropIndex { table, index } => write!(f, "drop index on {}({})", table, index.columns.join(", ")),
+        }
+    }
+}
+
+impl SchemaDiff {
+    /// Check if the schemas are the same.
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// Migration applying the changes in the SQL of the dialect, each preceded by a comment
+    /// describing it.
+    ///
+    /// Constraints without a name are dropped by the default name given by the database,
+    /// e.g. `office_person_id_fkey` in PostgreSQL. SQLite tables whose columns or constraints
+    /// change are rebuilt by copying their rows into a new table.
+    pub fn to_sql(&self, dialect: &Dialect) -> String {
+        let rebuilt: Vec<&str> = self.changes.iter()
+            .filter(|change| change.needs_rebuild(dialect))
+            .map(|change| self.new_name(change.table()))
+            .fold(Vec::new(), |mut tables, table| {
+                if !tables.iter().any(|t: &&str| t.eq_ignore_ascii_case(table)) {
+                    tables.push(table);
+                }
+                tables
+            });
+        let is_rebuilt = |change: &SchemaChange| rebuilt.iter().any(|table| table.eq_ignore_ascii_case(self.new_name(change.table())));
+
+        let mut sql = String::new();
+        let mut rebuilds_done = rebuilt.is_empty();
+        for change in &self.changes {
+            if !rebuilds_done && change.phase() >= 5 {
+                rebuilds_done = true;
+                for table in &rebuilt {
+                    sql.push_str(&self.rebuild(table, dialect));
+                }
+            }
+            let replaced_by_rebuild = matches!(
+                change,
+                SchemaChange::AddColumn { .. } | SchemaChange::DropColumn { .. } | SchemaChange::CreateIndex { .. } | SchemaChange::DropIndex { .. }
+            );
+            if change.needs_rebuild(dialect) || (replaced_by_rebuild && is_rebuilt(change)) {
+                continue;
+            }
+            sql.push_str(&format!("-- {}\n", change));
+            if matches!(change, SchemaChange::RenameTable { .. } | SchemaChange::RenameColumn { .. }) {
+                sql.push_str("-- guessed from the definitions: replace with a drop and an add if it is not a rename\n");
+            }
+            for statement in change.statements(dialect) {
+                sql.push_str(&format!("{};\n", statement));
+            }
+            sql.push('\n');
+        }
+        if !rebuilds_done {
+            for table in &rebuilt {
+                sql.push_str(&self.rebuild(table, dialect));
+            }
+        }
+        sql.truncate(sql.trim_end().len());
+        if !sql.is_empty() {
+            sql.push('\n');
+        }
+        sql
+    }
+
+    /// Name of a table after the renames
+    fn new_name<'a>(&'a self, name: &'a str) -> &'a str {
+        self.changes.iter()
+            .find_map(|change| match change {
+                SchemaChange::RenameTable { from, to } if from.eq_ignore_ascii_case(name) => Some(to.as_str()),
+                _ => None,
+            })
+            .unwrap_or(name)
+    }
+
+    /// SQLite statements recreating a table with its target definition, keeping the rows
+    fn rebuild(&self, name: &str, dialect: &Dialect) -> String {
+        let Some(table) = self.target.table(name) else { return String::new() };
+        let added: Vec<&str> = self.changes.iter().filter_map(|change| match change {
+            SchemaChange::AddColumn { table, column } if table.eq_ignore_ascii_case(name) => Some(column.name.as_str()),
+            _ => None,
+        }).collect();
+        let kept: Vec<&str> = table.columns.iter()
+            .map(|column| column.name.as_str())
+            .filter(|column| !added.iter().any(|a| a.eq_ignore_ascii_case(column)))
+            .collect();
+        let new_table = Table { name: format!("{}_new", table.name), indexes: Vec::new(), ..table.clone() };
+
+        let mut sql = format!("-- rebuild table {}\n", table.name);
+        for change in self.changes.iter().filter(|c| c.needs_rebuild(dialect) && self.new_name(c.table()).eq_ignore_ascii_case(name)) {
+            sql.push_str(&format!("-- {}\n", change));
+        }
+        let mut statements = new_table.create_statements(dialect);
+        statements.push(format!("INSERT INTO {} ({}) SELECT {} FROM {}", new_table.name, kept.join(", "), kept.join(", "), table.name));
+        statements.push(format!("DROP TABLE {}", table.name));
+        statements.push(format!("ALTER TABLE {} RENAME TO {}", new_table.name, table.name));
+        statements.extend(table.indexes.iter().map(|index| index_definition(&table.name, index, dialect)));
+        for statement in statements {
+            sql.push_str(&format!("{};\n", statement));
+        }
+        sql.push('\n');
+        sql
+    }
+}
+
+impl Table {
+    /// `CREATE TABLE` statement of the table, followed by its `CREATE INDEX`es, or the
+    /// `CREATE VIEW` of a view, without the terminating semicolons.
+    pub fn create_statements(&self, dialect: &Dialect) -> Vec<String> {
+        if self.is_view() {
+            return self.definition.iter().cloned().collect();
+        }
+        // SQLite only auto-increments an `INTEGER PRIMARY KEY` declared inline
+        let inline_key = match (dialect, self.primary_key.as_slice()) {
+            (Dialect::SQLite, [key]) => self.column(key).filter(|column| column.generated && column.default.is_none()),
+            _ => None,
+        };
+        let mut lines: Vec<String> = self.columns.iter()
+            .map(|column| column_definition(column, inline_key.is_some_and(|key| key.name == column.name), dialect))
+            .collect();
+        if !self.primary_key.is_empty() && inline_key.is_none() {
+            lines.push(format!("PRIMARY KEY ({})", self.primary_key.join(", ")));
+        }
+        for key in &self.unique_keys {
+            lines.push(format!("UNIQUE ({})", key.join(", ")));
+        }
+        for foreign_key in &self.foreign_keys {
+            lines.push(foreign_key_definition(foreign_key));
+        }
+        let mut statements = vec![format!("CREATE TABLE {} (\n    {}\n)", self.name, lines.join(",\n    "))];
+        statements.extend(self.indexes.iter().map(|index| index_definition(&self.name, index, dialect)));
+        statements
+    }
+}
+
+/// Definition of a column in `CREATE TABLE` and `ADD COLUMN`
+fn column_definition(column: &Column, inline_primary_key: bool, dialect: &Dialect) -> String {
+    let mut definition = format!("{} {}", column.name, column.data_type);
+    if inline_primary_key {
+        definition.push_str(" PRIMARY KEY AUTOINCREMENT");
+        return definition;
+    }
+    definition.push_str(if column.nullable { " NULL" } else { " NOT NULL" });
+    if let Some(default) = &column.default {
+        definition.push_str(&format!(" DEFAULT {}", default));
+    } else if column.generated && !is_serial_type(&column.data_type) {
+        definition.push_str(match dialect {
+            Dialect::MySQL => " AUTO_INCREMENT",
+            Dialect::SQLite => "",
+            Dialect::Generic | Dialect::PostgreSQL => " GENERATED BY DEFAULT AS IDENTITY",
+        });
+    }
+    definition
+}
+
+fn foreign_key_definition(foreign_key: &ForeignKey) -> String {
+    let mut definition = String::new();
+    if let Some(name) = &foreign_key.name {
+        definition.push_str(&format!("CONSTRAINT {} ", name));
+    }
+    definition.push_str(&format!(
+        "FOREIGN KEY ({}) REFERENCES {} ({})",
+        foreign_key.columns.join(", "),
+        foreign_key.foreign_table,
+        foreign_key.referred_columns.join(", ")
+    ));
+    if let Some(action) = foreign_key.on_delete {
+        definition.push_str(&format!(" ON DELETE {}", action));
+    }
+    if let Some(action) = foreign_key.on_update {
+        definition.push_str(&format!(" ON UPDATE {}", action));
+    }
+    definition
+}
+
+/// Name of a foreign key, or the default one of PostgreSQL
+pub(super) fn foreign_key_name(table: &str, foreign_key: &ForeignKey) -> String {
+    foreign_key.name.clone().unwrap_or_else(|| format!("{}_{}_fkey", table, foreign_key.columns.join("_")))
+}
+
+/// Default name of a `UNIQUE` constraint in the dialect
+pub(super) fn unique_key_name(table: &str, columns: &[String], dialect: &Dialect) -> String {
+    match dialect {
+        Dialect::MySQL => columns.first().cloned().unwrap_or_default(),
+        _ => format!("{}_{}_key", table, columns.join("_")),
+    }
+}
+
+/// Name of an index, or the default one of the dialect
+pub(super) fn index_name(table: &str, index: &Index, dialect: &Dialect) -> String {
+    match (&index.name, dialect) {
+        (Some(name), _) => name.clone(),
+        (None, Dialect::MySQL) => index.columns.first().cloned().unwrap_or_default(),
+        (None, _) => format!("{}_{}_idx", table, index.columns.join("_")),
+    }
+}
+
+fn index_definition(table: &str, index: &Index, dialect: &Dialect) -> String {
+    let mut definition = format!(
+        "CREATE {}INDEX {} ON {} ({})",
+        if index.unique { "UNIQUE " } else { "" },
+        index_name(table, index, dialect),
+        table,
+        index.columns.join(", ")
+    );
+    if let Some(predicate) = &index.predicate {
+        definition.push_str(&format!(" WHERE {}", predicate));
+    }
+    definition
+}
+
+fn kind_keyword(kind: TableKind) -> &'static str {
+    match kind {
+        TableKind::Table => "TABLE",
+        TableKind::View => "VIEW",
+        TableKind::MaterializedView => "MATERIALIZED VIEW",
+    }
+}
@@ -0,0 +1,308 @@
+use std::fmt::Write;
+
+use super::diagram::{column_keys, html};
+use super::langgen_rust::capitalize_first_letter;
+use super::{GeneratedFiles, Schema, Table, TableKind};
+
+/// Name of the page listing the tables
+const INDEX_PAGE: &str = "index";
+
+/// Style of the HTML pages, kept inline so that the pages can be published as they are
+const HTML_STYLE: &str = "body { font-family: sans-serif; margin: 2em auto; max-width: 60em; padding: 0 1em; }
+table { border-collapse: collapse; margin-bottom: 1em; }
+th, td { border: 1px solid #ccc; padding: 0.3em 0.6em; text-align: left; vertical-align: top; }
+th { background: #f0f0f0; }
+code, pre { background: #f6f6f6; }
+pre { padding: 0.6em; overflow-x: auto; }";
+
+/// Format of the documentation pages
+#[derive(Debug, Clone, Copy)]
+enum Format {
+    Markdown,
+    Html,
+}
+
+impl Schema {
+    /// Markdown documentation of the schema: an `index.md` page listing the tables and views,
+    /// and one page per table with its columns, indexes, and the foreign keys from and to it,
+    /// linking to the pages of the related tables.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use noorm::prelude::*;
+    /// use noorm::parser::Schema;
+    ///
+    /// let sql = "CREATE TABLE office ( id INT PRIMARY KEY );
+    ///     CREATE TABLE person ( id INT PRIMARY KEY, office_id INT NULL REFERENCES office(id) ON DELETE SET NULL )";
+    /// let docs = Schema::from_sql(sql, &Dialect::Generic).unwrap().to_markdown_docs();
+    /// let office = &docs.get("office.md").unwrap().contents;
+    /// assert!(office.contains("| [person](person.md) | `office_id` | `id` |"));
+    /// let person = &docs.get("person.md").unwrap().contents;
+    /// assert!(person.contains("| `office_id` | `INT` | yes |  | FK |  |"));
+    /// assert!(person.contains("| `office_id` | [office](office.md) | `id` | SET NULL |  |"));
+    /// ```
+    pub fn to_markdown_docs(&self) -> GeneratedFiles {
+        self.docs(Format::Markdown)
+    }
+
+    /// HTML documentation of the schema, with the pages of `to_markdown_docs` as standalone
+    /// `.html` files.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use noorm::prelude::*;
+    /// use noorm::parser::Schema;
+    ///
+    /// let schema = Schema::from_sql("CREATE TABLE person ( id INT PRIMARY KEY )", &Dialect::Generic).unwrap();
+    /// let docs = schema.to_html_docs();
+    /// assert!(docs.get("index.html").unwrap().contents.contains("<a href=\"person.html\">person</a>"));
+    /// ```
+    pub fn to_html_docs(&self) -> GeneratedFiles {
+        self.docs(Format::Html)
+    }
+
+    fn docs(&self, format: Format) -> GeneratedFiles {
+        let mut files = GeneratedFiles::default();
+        for table in &self.tables {
+            files.push(format.file(&page_name(&table.name)), self.table_page(table, format));
+        }
+        files.push(format.file(INDEX_PAGE), self.index_page(format));
+        files
+    }
+
+    /// Page listing the tables and views, with links to their pages
+    fn index_page(&self, format: Format) -> String {
+        let rows = self.tables.iter().map(|table| vec![
+            self.table_link(&table.name, format),
+            format.text(kind_name(table.kind)),
+            table.columns.len().to_string(),
+            table.comment.as_deref().map(|comment| format.text(comment)).unwrap_or_default(),
+        ]).collect();
+        let mut body = format.heading(1, "Schema");
+        body.push_str(&format.table(&["Table", "Kind", "Columns", "Comment"], rows));
+        format.document("Schema", &body)
+    }
+
+    /// Page of a table: its columns, keys, indexes and foreign keys
+    fn table_page(&self, table: &Table, format: Format) -> String {
+        let mut body = format.paragraph(&format.link("Schema", &format.file(INDEX_PAGE)));
+        body.push_str(&format.heading(1, &format.text(&table.name)));
+        if let Some(comment) = &table.comment {
+            body.push_str(&format.paragraph(&format.text(comment)));
+        }
+        let kind = capitalize_first_letter(kind_name(table.kind));
+        let about = match &table.source {
+            Some(source) => format!("{} {}.", format.text(&format!("{} defined in", kind)), format.code(source)),
+            None => format.text(&format!("{}.", kind)),
+        };
+        body.push_str(&format.paragraph(&about));
+
+        let columns = table.columns.iter().map(|column| {
+            let data_type = column.data_type.to_string();
+            let default = match (&column.default, column.generated) {
+                (Some(default), _) => format.code(&default.to_string()),
+                (None, true) => format.text("generated"),
+                (None, false) => String::new(),
+            };
+            vec![
+                format.code(&column.name),
+                if data_type.is_empty() { String::new() } else { format.code(&data_type) },
+                format.text(if column.nullable { "yes" } else { "no" }),
+                default,
+                column_keys(table, column).join(", "),
+                column.comment.as_deref().map(|comment| format.text(comment)).unwrap_or_default(),
+            ]
+        }).collect();
+        body.push_str(&format.heading(2, "Columns"));
+        body.push_str(&format.table(&["Column", "Type", "Nullable", "Default", "Key", "Comment"], columns));
+
+        let mut indexes = Vec::new();
+        if !table.primary_key.is_empty() {
+            indexes.push(vec![format.text("primary key"), format.code(&table.primary_key.join(", ")), format.text("yes"), String::new()]);
+        }
+        for key in &table.unique_keys {
+            indexes.push(vec![format.text("unique key"), format.code(&key.join(", ")), format.text("yes"), String::new()]);
+        }
+        for index in &table.indexes {
+            indexes.push(vec![
+                index.name.as_deref().map(|name| format.code(name)).unwrap_or_default(),
+                format.code(&index.columns.join(", ")),
+                format.text(if index.unique { "yes" } else { "no" }),
+                index.predicate.as_ref().map(|predicate| format.code(&predicate.to_string())).unwrap_or_default(),
+            ]);
+        }
+        if !indexes.is_empty() {
+            body.push_str(&format.heading(2, "Indexes"));
+            body.push_str(&format.table(&["Index", "Columns", "Unique", "Where"], indexes));
+        }
+
+        let outgoing: Vec<_> = table.foreign_keys.iter().map(|foreign_key| vec![
+            format.code(&foreign_key.columns.join(", ")),
+            self.table_link(&foreign_key.foreign_table, format),
+            format.code(&foreign_key.referred_columns.join(", ")),
+            foreign_key.on_delete.map(|action| format.text(&action.to_string())).unwrap_or_default(),
+            foreign_key.on_update.map(|action| format.text(&action.to_string())).unwrap_or_default(),
+        ]).collect();
+        if !outgoing.is_empty() {
+            body.push_str(&format.heading(2, "Foreign keys"));
+            body.push_str(&format.table(&["Columns", "References", "Referred columns", "On delete", "On update"], outgoing));
+        }
+
+        let incoming: Vec<_> = self.tables.iter()
+            .flat_map(|other| other.foreign_keys.iter().map(move |foreign_key| (other, foreign_key)))
+            .filter(|(_, foreign_key)| foreign_key.foreign_table.eq_ignore_ascii_case(&table.name))
+            .map(|(other, foreign_key)| vec![
+                self.table_link(&other.name, format),
+                format.code(&foreign_key.columns.join(", ")),
+                format.code(&foreign_key.referred_columns.join(", ")),
+            ])
+            .collect();
+        if !incoming.is_empty() {
+            body.push_str(&format.heading(2, "Referenced by"));
+            body.push_str(&format.table(&["Table", "Columns", "Referred columns"], incoming));
+        }
+
+        if let Some(definition) = &table.definition {
+            body.push_str(&format.heading(2, "Definition"));
+            body.push_str(&format.sql(definition));
+        }
+        format.document(&table.name, &body)
+    }
+
+    /// Link to the page of a table, or its name when it has no page
+    fn table_link(&self, name: &str, format: Format) -> String {
+        match self.table(name) {
+            Some(table) => format.link(&table.name, &format.file(&page_name(&table.name))),
+            None => format.text(name),
+        }
+    }
+}
+
+impl Format {
+    /// File name of a page
+    fn file(self, page: &str) -> String {
+        match self {
+            Format::Markdown => format!("{}.md", page),
+            Format::Html => format!("{}.html", page),
+        }
+    }
+
+    /// Complete page, from its title and body
+    fn document(self, title: &str, body: &str) -> String {
+        match self {
+            Format::Markdown => body.to_string(),
+            Format::Html => format!(
+                "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>\n{}\n</style>\n</head>\n<body>\n{}</body>\n</html>\n",
+                html(title), HTML_STYLE, body,
+            ),
+        }
+    }
+
+    /// Heading of a page or section, from formatted text
+    fn heading(self, level: usize, text: &str) -> String {
+        match self {
+            Format::Markdown => format!("{} {}\n\n", "#".repeat(level), text),
+            Format::Html => format!("<h{level}>{}</h{level}>\n", text),
+        }
+    }
+
+    /// Paragraph, from formatted text
+    fn paragraph(self, text: &str) -> String {
+        match self {
+            Format::Markdown => format!("{}\n\n", text),
+            Format::Html => format!("<p>{}</p>\n", text),
+        }
+    }
+
+    /// Link to a page, from formatted text
+    fn link(self, text: &str, file: &str) -> String {
+        match self {
+            Format::Markdown => format!("[{}]({})", text, file),
+            Format::Html => format!("<a href=\"{}\">{}</a>", html(file), text),
+        }
+    }
+
+    /// Plain text, on a single line
+    fn text(self, text: &str) -> String {
+        let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        match self {
+            Format::Markdown => text.chars().fold(String::new(), |mut escaped, c| {
+                if matches!(c, '\\' | '`' | '*' | '[' | ']' | '<' | '|') {
+                    escaped.push('\\');
+                }
+                escaped.push(c);
+                escaped
+            }),
+            Format::Html => html(&text),
+        }
+    }
+
+    /// Inline code, e.g. a name or a type
+    fn code(self, code: &str) -> String {
+        let code = code.split_whitespace().collect::<Vec<_>>().join(" ");
+        match self {
+            // Pipes are escaped even in code spans inside tables
+            Format::Markdown if code.contains('`') => format!("`` {} ``", code.replace('|', "\\|")),
+            Format::Markdown => format!("`{}`", code.replace('|', "\\|")),
+            Format::Html => format!("<code>{}</code>", html(&code)),
+        }
+    }
+
+    /// Block of SQL
+    fn sql(self, sql: &str) -> String {
+        match self {
+            Format::Markdown => {
+                let fence = if sql.contains("```") { "~~~~" } else { "```" };
+                format!("{fence}sql\n{}\n{fence}\n\n", sql.trim())
+            }
+            Format::Html => format!("<pre><code>{}</code></pre>\n", html(sql.trim())),
+        }
+    }
+
+    /// Table, from its header and rows of formatted cells
+    fn table(self, header: &[&str], rows: Vec<Vec<String>>) -> String {
+        let mut table = String::new();
+        match self {
+            Format::Markdown => {
+                let _ = writeln!(table, "| {} |", header.join(" | "));
+                let _ = writeln!(table, "|{}", " --- |".repeat(header.len()));
+                for row in rows {
+                    let _ = writeln!(table, "| {} |", row.join(" | "));
+                }
+                table.push('\n');
+            }
+            Format::Html => {
+                table.push_str("<table>\n<thead>\n<tr>");
+                for cell in header {
+                    let _ = write!(table, "<th>{}</th>", html(cell));
+                }
+                table.push_str("</tr>\n</thead>\n<tbody>\n");
+                for row in rows {
+                    table.push_str("<tr>");
+                    for cell in row {
+                        let _ = write!(table, "<td>{}</td>", cell);
+                    }
+                    table.push_str("</tr>\n");
+                }
+                table.push_str("</tbody>\n</table>\n");
+            }
+        }
+        table
+    }
+}
+
+/// Name of the page of a table, safe as a file name and apart from the index page
+fn page_name(table: &str) -> String {
+    let name: String = table.chars().map(|c| if c.is_alphanumeric() || c == '_' || c == '-' { c } else { '_' }).collect();
+    if name.eq_ignore_ascii_case(INDEX_PAGE) { format!("{}_table", name) } else { name }
+}
+
+fn kind_name(kind: TableKind) -> &'static str {
+    match kind {
+        TableKind::Table => "table",
+        TableKind::View => "view",
+        TableKind::MaterializedView => "materialized view",
+    }
+}
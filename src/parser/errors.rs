@@ -1,3 +1,4 @@
+use std::path::PathBuf;
 use thiserror::Error;
 
 /// ParserConfig Errors.
@@ -6,6 +7,32 @@ pub enum ParserConfigError {
     /// ParserConfig TOML import error
     #[error("ParserConfig TOML import error")]
     TomlImport(#[from] toml::de::Error),
+    /// Error reading a configuration file
+    #[error("Cannot read {}: {source}", .path.display())]
+    Read {
+        /// Path of the configuration file
+        path: PathBuf,
+        /// Underlying error
+        source: std::io::Error,
+    },
+    /// Invalid configuration, at a line and column of the file
+    #[error("{}:{line}:{column}: {message}", .path.display())]
+    Invalid {
+        /// Path of the configuration file
+        path: PathBuf,
+        /// Line of the error, starting from 1
+        line: usize,
+        /// Column of the error, starting from 1
+        column: usize,
+        /// Description of the error
+        message: String,
+    },
+    /// `Cargo.toml` without a `[package.metadata.noorm]` table
+    #[error("No [package.metadata.noorm] table in {}", .0.display())]
+    MissingSection(PathBuf),
+    /// No configuration file found
+    #[error("No noorm.toml or [package.metadata.noorm] found in {} or its parents", .0.display())]
+    NotFound(PathBuf),
     /// Unknown ParserConfig error
     #[error("Unknown ParserConfig error")]
     Unknown,
@@ -17,6 +44,78 @@ pub enum ParserError {
     /// Parser statement error
     #[error("Parser statement error")]
     Statement,
+    /// SQL parsing error
+    #[error("SQL parsing error: {0}")]
+    Sql(#[from] sqlparser::parser::ParserError),
+    /// Error reading an input file or directory
+    #[error("Cannot read {}: {source}", .path.display())]
+    Read {
+        /// Path of the file or directory
+        path: PathBuf,
+        /// Underlying error
+        source: std::io::Error,
+    },
+    /// SQL parsing error in an input file
+    #[error("SQL parsing error in {}: {source}", .path.display())]
+    SqlFile {
+        /// Path of the SQL file
+        path: PathBuf,
+        /// Underlying error
+        source: sqlparser::parser::ParserError,
+    },
+    /// Rust parsing error, or invalid `#[noorm]` attribute
+    #[error("Rust parsing error at {location}: {message}")]
+    Rust {
+        /// Source and line of the error
+        location: String,
+        /// Description of the error
+        message: String,
+    },
+    /// Table or view created twice
+    #[error(
+        "Table {name} is created twice, in {} and in {}",
+        .first.as_deref().unwrap_or("the statement"),
+        .second.as_deref().unwrap_or("the statement")
+    )]
+    DuplicateTable {
+        /// Name of the table
+        name: String,
+        /// Source of the first definition
+        first: Option<String>,
+        /// Source of the second definition
+        second: Option<String>,
+    },
+    /// Error reading an SQLite database
+    #[cfg(feature = "sqlite")]
+    #[error("Cannot read the SQLite database {}: {source}", .path.display())]
+    Sqlite {
+        /// Path of the database file
+        path: PathBuf,
+        /// Underlying error
+        source: rusqlite::Error,
+    },
+    /// Invalid JSON or YAML schema
+    #[error("Schema format error: {0}")]
+    SchemaFormat(String),
+    /// No SQL file found in an input directory
+    #[error("No SQL files found in {}", .0.display())]
+    NoSqlFiles(PathBuf),
+    /// Column of a type the selected Rust backend cannot read
+    #[error("Column {table}.{column} has no Rust type for {data_type}: select its crate in the `types` options, or change its type")]
+    ColumnType {
+        /// Name of the table
+        table: String,
+        /// Name of the column
+        column: String,
+        /// SQL type of the column
+        data_type: String,
+    },
+    /// Missing output directory
+    #[error("No output directory configured")]
+    OutputDir,
+    /// Error writing the generated files
+    #[error("Output writing error: {0}")]
+    Io(#[from] std::io::Error),
     /// Unknown Parser error
     #[error("Unknown Parser error")]
     Unknown,
@@ -1,15 +1,37 @@
+use std::fmt;
+use std::io;
+use serde::de::{self, Deserializer, MapAccess, Visitor};
 use serde::Deserialize;
 
 use super::{
-    RustAst, rust_ast_to_string, rust_parse_create_table,
-    JavaScriptAst, javascript_parse_create_table, javascript_ast_to_string,
-    PythonAst, python_parse_create_table, python_ast_to_string,
-    DummyAst, dummy_parse_create_table, dummy_ast_to_string, 
+    errors::ParserError, Dialect, GeneratedFiles, Schema,
+    RustAst, rust_ast_to_string, rust_schema_to_ast, rust_schema_to_files, write_rust_ast,
+    JavaScriptAst, javascript_schema_to_ast, javascript_ast_to_string, javascript_schema_to_files,
+    PythonAst, python_schema_to_ast, python_ast_to_string, python_schema_to_files,
+    DummyAst, dummy_parse_create_table, dummy_ast_to_string,
+    langgen_rust::check_column_types,
 };
 
 
 /// Language selection enum
-#[derive(Debug, Clone, PartialEq, Deserialize)]
+///
+/// In the configuration, a language is selected by name, or by a table of generator options
+/// named after it:
+///
+/// ```toml
+/// language = "python"
+///
+/// [language.rust]
+/// backend = "sqlx"
+/// derives = ["Debug", "Clone"]
+/// types = { datetime = "chrono", uuid = true }
+/// naming = "pascal-case"
+/// ```
+///
+/// Only Rust has options: with a `backend`, the queries of the tables are generated too, e.g.
+/// the lookups of their unique keys and indexes and the helpers of their foreign keys.
+/// The other languages reject any option.
+#[derive(Debug, Clone, PartialEq)]
 pub enum Language {
     /// Rust language
     Rust(RustAst),
@@ -55,23 +77,140 @@ impl Language {
         }
     }
 
-    /// Implement conversion to string for the language
-    pub fn parse_create_table(&mut self, sql: &str) {
+    /// Name of the language, as accepted by `Language::new`
+    pub fn name(&self) -> &'static str {
         match self {
-            Language::Rust(ast) => *ast = rust_parse_create_table(sql),
-            Language::Python(ast) => *ast = python_parse_create_table(sql),
-            Language::JavaScript(ast) => *ast = javascript_parse_create_table(sql),
-            Language::Dummy(ast) => *ast = dummy_parse_create_table(sql),
+            Language::Rust(_) => "rust",
+            Language::Python(_) => "python",
+            Language::JavaScript(_) => "javascript",
+            Language::Dummy(_) => "dummy",
         }
     }
 
-    /// Implement conversion to string for the language
-    pub fn to_string(&self) -> String {
+    /// Parse the `CREATE TABLE` statements and generate the language AST,
+    /// returning the schema parsed (empty for the Dummy language, which echoes the SQL)
+    ///
+    /// Parameters
+    /// * sql: &str the SQL statements to parse
+    /// * dialect: &Dialect the Dialect used to parse the statements
+    pub fn parse_create_table(&mut self, sql: &str, dialect: &Dialect) -> Result<Schema, ParserError> {
+        if let Language::Dummy(ast) = self {
+            *ast = dummy_parse_create_table(sql);
+            return Ok(Schema::default());
+        }
+        let schema = Schema::from_sql(sql, dialect)?;
+        self.generate(&schema, dialect)?;
+        Ok(schema)
+    }
+
+    /// Generate the language AST from a schema, failing on the Rust columns the backend
+    /// of the options cannot read
+    ///
+    /// Parameters
+    /// * schema: &Schema the schema to generate the code for
+    /// * dialect: &Dialect the Dialect of the queries in the generated code
+    pub fn generate(&mut self, schema: &Schema, dialect: &Dialect) -> Result<(), ParserError> {
         match self {
+            Language::Rust(ast) => {
+                let options = ast.options().clone();
+                check_column_types(schema, dialect, &options)?;
+                *ast = rust_schema_to_ast(schema, dialect, &options)
+            }
+            Language::Python(ast) => *ast = python_schema_to_ast(schema),
+            Language::JavaScript(ast) => *ast = javascript_schema_to_ast(schema),
+            Language::Dummy(_) => {}
+        }
+        Ok(())
+    }
+
+    /// Generate the code as a set of files: a module per table and the module index
+    ///
+    /// Parameters
+    /// * schema: &Schema the schema to generate the code for
+    /// * dialect: &Dialect the Dialect of the queries in the generated code
+    pub fn to_files(&self, schema: &Schema, dialect: &Dialect) -> GeneratedFiles {
+        match self {
+            Language::Rust(ast) => rust_schema_to_files(schema, dialect, ast.options()),
+            Language::Python(_) => python_schema_to_files(schema),
+            Language::JavaScript(_) => javascript_schema_to_files(schema),
+            Language::Dummy(ast) => {
+                let mut files = GeneratedFiles::default();
+                files.push("schema.sql", dummy_ast_to_string(ast));
+                files
+            }
+        }
+    }
+
+    /// Write the generated code to a writer
+    ///
+    /// Parameters
+    /// * writer: the destination of the code, e.g., a file or stdout
+    pub fn write<W: io::Write>(&self, writer: &mut W) -> io::Result<()> {
+        match self {
+            Language::Rust(fn_ast) => write_rust_ast(fn_ast, writer),
+            _ => writer.write_all(self.to_string().as_bytes()),
+        }
+    }
+}
+
+/// Names of the supported languages
+const LANGUAGES: &[&str] = &["rust", "python", "javascript", "dummy"];
+
+/// Generator options of the languages without any
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct NoOptions {}
+
+impl<'de> Deserialize<'de> for Language {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(LanguageVisitor)
+    }
+}
+
+/// Visitor of a language name, or of a table with a single language and its options
+struct LanguageVisitor;
+
+impl<'de> Visitor<'de> for LanguageVisitor {
+    type Value = Language;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a language name, or a table of options named after the language")
+    }
+
+    fn visit_str<E: de::Error>(self, name: &str) -> Result<Language, E> {
+        Language::new(name).ok_or_else(|| E::unknown_variant(name, LANGUAGES))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Language, A::Error> {
+        let Some(name) = map.next_key::<String>()? else {
+            return Err(de::Error::invalid_length(0, &self));
+        };
+        let language = match name.to_lowercase().as_str() {
+            "rust" => Language::Rust(RustAst::with_options(map.next_value()?)),
+            other => {
+                map.next_value::<NoOptions>()?;
+                self.visit_str(other)?
+            }
+        };
+        if map.next_key::<String>()?.is_some() {
+            return Err(de::Error::custom("only one language can be selected"));
+        }
+        Ok(language)
+    }
+}
+
+/// Implement conversion to string for the language
+impl fmt::Display for Language {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
             Language::Rust(fn_ast) => rust_ast_to_string(fn_ast),
             Language::Python(fn_ast) => python_ast_to_string(fn_ast),
             Language::JavaScript(fn_ast) => javascript_ast_to_string(fn_ast),
             Language::Dummy(fn_ast) => dummy_ast_to_string(fn_ast),
-        }
+        };
+        f.write_str(&s)
     }
 }
@@ -3,15 +3,9 @@ use std::ops::Deref;
 use serde::Deserialize;
 
 /// AST type for Language::Echo(EchoAst)
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Default, Clone, Deserialize)]
 pub struct DummyAst(String);
 
-impl Default for DummyAst {
-    fn default() -> Self {
-        DummyAst(String::new())
-    }
-}
-
 impl Deref for DummyAst {
     type Target = String;
 
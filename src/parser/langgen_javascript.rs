@@ -1,22 +1,27 @@
 use std::ops::Deref;
 use std::fmt::Write;
 use serde::Deserialize;
-use sqlparser::dialect::GenericDialect;
-use sqlparser::parser::Parser;
-use sqlparser::ast::{Statement, ColumnOption};
 use swc_ecma_ast::*;
-use swc_common::{SourceMap, SyntaxContext, DUMMY_SP};
+use swc_common::{BytePos, SourceMap, Span, SyntaxContext, DUMMY_SP};
+use swc_common::comments::{Comment, CommentKind, Comments, SingleThreadedComments};
 use swc_common::sync::Lrc;
 use swc_ecma_codegen::{text_writer::JsWriter, Emitter};
 
+use super::{is_serial_type, Column, DefaultValue, Dialect, GeneratedFiles, ParserError, Schema, Table};
+use super::langgen_rust::capitalize_first_letter;
+
 /// AST type for Language::JavaScript(JavaScriptAst)
 #[derive(Debug, Clone, Deserialize)]
-pub struct JavaScriptAst(Vec<VarDecl>);
+pub struct JavaScriptAst(Vec<VarDecl>, #[serde(skip)] JsDocs);
+
+/// JSDoc comments of the AST, attached to the nodes through the position of their span
+#[derive(Debug, Default, Clone)]
+struct JsDocs(Vec<String>);
 
 impl Default for JavaScriptAst {
     fn default() -> Self {
         let v: Vec<VarDecl> = Vec::new();
-        JavaScriptAst(v)
+        JavaScriptAst(v, JsDocs::default())
     }
 }
 
@@ -42,82 +47,249 @@ impl PartialEq for JavaScriptAst {
 }
 
 /// parse_create_table JavaScript implementation
-pub fn javascript_parse_create_table(sql: &str) -> JavaScriptAst {
-    let dialect = GenericDialect {}; // or MySqlDialect, PostgreSqlDialect, etc.
-    let ast = Parser::parse_sql(&dialect, sql).expect("Failed to parse SQL");
+///
+/// # Errors
+///
+/// * The errors of `Schema::from_sql` if the SQL is not valid
+pub fn javascript_parse_create_table(sql: &str) -> Result<JavaScriptAst, ParserError> {
+    let schema = Schema::from_sql(sql, &Dialect::Generic)?;
+    Ok(javascript_schema_to_ast(&schema))
+}
+
+/// schema_to_ast JavaScript implementation: the objects describing the rows, without the lookups and relation helpers
+/// of the Rust backends
+pub fn javascript_schema_to_ast(schema: &Schema) -> JavaScriptAst {
     let mut js_objects = Vec::new();
+    let mut docs = JsDocs::default();
+    for table in &schema.tables {
+        table_decls(table, &mut js_objects, &mut docs);
+    }
+    JavaScriptAst(js_objects, docs)
+}
 
-    for stmt in ast {
-        if let Statement::CreateTable(create_table) = stmt {
-            let table_name = &create_table.name.0[0].value;
-            //let struct_name = Ident::new(&capitalize_first_letter(table_name), proc_macro2::Span::call_site());
+/// schema_to_files JavaScript implementation: an ES module per table, re-exported by `index.js`
+pub fn javascript_schema_to_files(schema: &Schema) -> GeneratedFiles {
+    let mut files = GeneratedFiles::default();
+    let mut index = String::new();
+    for table in &schema.tables {
+        let mut ast = JavaScriptAst::default();
+        table_decls(table, &mut ast.0, &mut ast.1);
+        let module = table.name.to_lowercase();
+        files.push(format!("{}.js", module), emit(&ast, true));
+        writeln!(index, "export * from \"./{}.js\";", module).unwrap();
+    }
+    files.push("index.js", index);
+    files
+}
 
-            // Prepare properties for the JavaScript object
-            let mut props = Vec::new();
-            
-            for column in create_table.columns {
-                let col_name = &column.name.value;
-                let col_type = &column.data_type;
-                let is_nullable = column.options.iter().any(|opt| opt.option == ColumnOption::Null);
-
-                // Convert SQL type to JavaScript type
-                let js_type = sql_to_type(col_type, is_nullable);
-
-                // Generate a property for the JavaScript object
-                let key = PropName::Ident(IdentName {
-                    sym: col_name.to_string().into(),
-                    span: DUMMY_SP,
-                });
-
-                props.push(PropOrSpread::Prop(Box::new(Prop::KeyValue(KeyValueProp {
-                    key,
-                    value: js_type,
-                }))));
-            }
-
-            // Create the full JavaScript object for the table
-            let obj_lit = ObjectLit {
-                span: DUMMY_SP,
-                props,
-            };
+/// Declarations of the objects describing a table
+fn table_decls(table: &Table, js_objects: &mut Vec<VarDecl>, docs: &mut JsDocs) {
+    let table_name = &js_name(&table.name);
+    let new_name = format!("new{}", capitalize_first_letter(table_name));
+
+    // Prepare properties for the JavaScript object
+    let mut props = Vec::new();
+    for column in &table.columns {
+        // Convert SQL type to JavaScript type
+        let js_type = sql_to_type(&column.data_type, column.nullable, false);
+        props.push(key_value(&column.name, js_type, docs.span(column.comment.as_deref())));
+    }
+    let mut row_decl = var_decl(table_name, object_lit(props));
+    row_decl.span = docs.span(table.comment.as_deref());
+    js_objects.push(row_decl);
+
+    // Views are read-only: no insert object
+    if table.is_view() {
+        return;
+    }
 
-            // Create a variable declaration for the table object
-            let var_decl = VarDecl {
+    // Prepare properties for the insert object: generated columns are left out,
+    // and columns with a DEFAULT become optional
+    let mut new_props = Vec::new();
+    for column in table.insertable_columns() {
+        let js_type = sql_to_type(&column.data_type, column.nullable, column.has_default());
+        new_props.push(key_value(&column.name, js_type, docs.span(column.comment.as_deref())));
+    }
+    js_objects.push(var_decl(&new_name, object_lit(new_props)));
+
+    // Conversion from the insert object to the full row, using the literal defaults
+    // and the values assigned by the database
+    let mut row_props = Vec::new();
+    for column in &table.columns {
+        let value = if column.generated {
+            member("generated", &column.name)
+        } else if column.has_default() {
+            Box::new(Expr::Bin(BinExpr {
                 span: DUMMY_SP,
-                decls: vec![VarDeclarator {
-                    span: DUMMY_SP,
-                    name: Pat::Ident(BindingIdent {
-                        id: Ident {sym:table_name.to_string().into(),span:DUMMY_SP,optional:false, ctxt: SyntaxContext::default() },
-                        type_ann: None,
-                    }),
-                    init: Some(Box::new(Expr::Object(obj_lit))),
-                    definite: false,
-                }],
-                kind: VarDeclKind::Var,  // This uses 'var', you could also use 'let' or 'const'
-                declare: false,
-                ctxt: SyntaxContext::default(),
-            };
+                op: BinaryOp::NullishCoalescing,
+                left: member(&new_name, &column.name),
+                right: default_literal(column).unwrap_or_else(|| member("generated", &column.name)),
+            }))
+        } else {
+            member(&new_name, &column.name)
+        };
+        row_props.push(key_value(&column.name, value, DUMMY_SP));
+    }
+    let conversion = Expr::Arrow(ArrowExpr {
+        span: DUMMY_SP,
+        ctxt: SyntaxContext::default(),
+        params: vec![binding(&new_name), binding("generated")],
+        body: Box::new(BlockStmtOrExpr::Expr(Box::new(Expr::Paren(ParenExpr {
+            span: DUMMY_SP,
+            expr: object_lit(row_props),
+        })))),
+        is_async: false,
+        is_generator: false,
+        type_params: None,
+        return_type: None,
+    });
+    js_objects.push(var_decl(&format!("{}To{}", new_name, capitalize_first_letter(table_name)), Box::new(conversion)));
+}
+
+impl JsDocs {
+    /// Span to attach a JSDoc comment to, or a dummy span if there is no comment
+    fn span(&mut self, comment: Option<&str>) -> Span {
+        let Some(comment) = comment else { return DUMMY_SP };
+        self.0.push(comment.to_string());
+        let pos = BytePos(self.0.len() as u32);
+        Span::new(pos, pos)
+    }
 
-            js_objects.push(var_decl);
+    /// Leading comments for the emitter, as `/** ... */` blocks
+    fn comments(&self) -> SingleThreadedComments {
+        let comments = SingleThreadedComments::default();
+        for (i, doc) in self.0.iter().enumerate() {
+            let text = match doc.lines().collect::<Vec<_>>().as_slice() {
+                [line] => format!("* {} ", line),
+                lines => format!("*\n{} ", lines.iter().map(|line| format!(" * {}\n", line)).collect::<String>()),
+            };
+            comments.add_leading(BytePos(i as u32 + 1), Comment { kind: CommentKind::Block, span: DUMMY_SP, text: text.into() });
         }
-    } // End For statements
-    JavaScriptAst(js_objects)
+        comments
+    }
+}
+
+/// Create a variable declaration
+fn var_decl(name: &str, init: Box<Expr>) -> VarDecl {
+    VarDecl {
+        span: DUMMY_SP,
+        decls: vec![VarDeclarator {
+            span: DUMMY_SP,
+            name: binding(name),
+            init: Some(init),
+            definite: false,
+        }],
+        kind: VarDeclKind::Var,  // This uses 'var', you could also use 'let' or 'const'
+        declare: false,
+        ctxt: SyntaxContext::default(),
+    }
 }
 
-fn sql_to_type(sql_type: &sqlparser::ast::DataType, is_nullable: bool) -> Box<Expr> {
+/// Create a binding pattern for a name
+fn binding(name: &str) -> Pat {
+    Pat::Ident(BindingIdent {
+        id: Ident {sym:name.to_string().into(),span:DUMMY_SP,optional:false, ctxt: SyntaxContext::default() },
+        type_ann: None,
+    })
+}
+
+/// Create an object literal
+fn object_lit(props: Vec<PropOrSpread>) -> Box<Expr> {
+    Box::new(Expr::Object(ObjectLit {
+        span: DUMMY_SP,
+        props,
+    }))
+}
+
+/// JavaScript identifier for an SQL name: the characters not allowed in identifiers become `_`,
+/// and the reserved words get a trailing `_`, e.g. `user-data` becomes `user_data` and `class` becomes `class_`
+fn js_name(name: &str) -> String {
+    let mut name: String = name.chars().map(|c| if c.is_alphanumeric() || c == '_' || c == '$' { c } else { '_' }).collect();
+    if !name.starts_with(|c: char| c.is_alphabetic() || c == '_' || c == '$') {
+        name.insert(0, '_');
+    }
+    if RESERVED_WORDS.contains(&name.as_str()) {
+        name.push('_');
+    }
+    name
+}
+
+/// Reserved words of JavaScript, not allowed as variable names
+const RESERVED_WORDS: &[&str] = &[
+    "arguments", "await", "break", "case", "catch", "class", "const", "continue", "debugger", "default",
+    "delete", "do", "else", "enum", "eval", "export", "extends", "false", "finally", "for", "function",
+    "if", "implements", "import", "in", "instanceof", "interface", "let", "new", "null", "package",
+    "private", "protected", "public", "return", "static", "super", "switch", "this", "throw", "true",
+    "try", "typeof", "var", "void", "while", "with", "yield",
+];
+
+/// A property name can be written as is, e.g. `name` but not `my col`; reserved words are allowed
+fn is_property_name(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_alphabetic() || c == '_' || c == '$')
+        && name.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '$')
+}
+
+/// Create a `key: value` object property, with a string key if the name is not an identifier
+fn key_value(key: &str, value: Box<Expr>, span: Span) -> PropOrSpread {
+    let key = if is_property_name(key) {
+        PropName::Ident(IdentName { sym: key.to_string().into(), span })
+    } else {
+        PropName::Str(Str { span, value: key.into(), raw: None })
+    };
+    PropOrSpread::Prop(Box::new(Prop::KeyValue(KeyValueProp {
+        key,
+        value,
+    })))
+}
+
+/// Create a `obj.prop` member expression, or `obj["prop"]` if the name is not an identifier
+fn member(obj: &str, prop: &str) -> Box<Expr> {
+    let prop = if is_property_name(prop) {
+        MemberProp::Ident(IdentName { sym: prop.to_string().into(), span: DUMMY_SP })
+    } else {
+        MemberProp::Computed(ComputedPropName {
+            span: DUMMY_SP,
+            expr: Box::new(Expr::Lit(Lit::Str(Str { span: DUMMY_SP, value: prop.into(), raw: None }))),
+        })
+    };
+    Box::new(Expr::Member(MemberExpr {
+        span: DUMMY_SP,
+        obj: Box::new(Expr::Ident(Ident {sym:obj.to_string().into(),span:DUMMY_SP,optional:false, ctxt: SyntaxContext::default() })),
+        prop,
+    }))
+}
+
+/// JavaScript literal for the `DEFAULT` of a column
+fn default_literal(column: &Column) -> Option<Box<Expr>> {
+    let is_boolean = matches!(column.data_type, sqlparser::ast::DataType::Boolean);
+    let lit = match column.default_value()? {
+        DefaultValue::Null => Lit::Null(Null { span: DUMMY_SP }),
+        DefaultValue::Boolean(value) => Lit::Bool(Bool { span: DUMMY_SP, value }),
+        DefaultValue::Number(number) if is_boolean => Lit::Bool(Bool { span: DUMMY_SP, value: number != "0" }),
+        DefaultValue::Number(number) => Lit::Num(Number { span: DUMMY_SP, value: number.parse().ok()?, raw: None }),
+        DefaultValue::String(text) => Lit::Str(Str { span: DUMMY_SP, value: text.into(), raw: None }),
+    };
+    Some(Box::new(Expr::Lit(lit)))
+}
+
+fn sql_to_type(sql_type: &sqlparser::ast::DataType, is_nullable: bool, is_optional: bool) -> Box<Expr> {
     // Example mapping logic - this will depend on your specific mappings
     let js_type = match sql_type {
         sqlparser::ast::DataType::Varchar(_) | sqlparser::ast::DataType::Text => "string",
-        sqlparser::ast::DataType::Int(_) => "number",
+        sqlparser::ast::DataType::Int(_) | sqlparser::ast::DataType::BigInt(_) => "number",
+        sqlparser::ast::DataType::Custom(..) if is_serial_type(sql_type) => "number",
         sqlparser::ast::DataType::Boolean => "boolean",
         _ => "any",  // Fallback to `any` for unsupported types
     };
 
-    let type_expr = if is_nullable {
+    let mut type_expr = if is_nullable {
         format!("{} | null", js_type)
     } else {
         js_type.to_string()
     };
+    if is_optional {
+        type_expr.push_str(" | undefined");
+    }
 
     // Return JavaScript expression for the type
     Box::new(Expr::Lit(Lit::Str(Str {
@@ -130,10 +302,16 @@ fn sql_to_type(sql_type: &sqlparser::ast::DataType, is_nullable: bool) -> Box<Ex
 
 /// ast_to_string JavaScript implementation
 pub fn javascript_ast_to_string(ast: &JavaScriptAst) -> String {
+    emit(ast, false)
+}
+
+/// Emit the declarations, as exports of an ES module if `export` is set
+fn emit(ast: &JavaScriptAst, export: bool) -> String {
     let mut js_code = String::new();
     
     // Create a SourceMap to manage code generation
     let cm = Lrc::new(SourceMap::default());
+    let comments = ast.1.comments();
 
     // Iterate over the variable declarations and generate code for each
     for var_decl in &ast.0 {
@@ -149,16 +327,21 @@ pub fn javascript_ast_to_string(ast: &JavaScriptAst) -> String {
             let codegen_config = config;
             let mut emitter = Emitter {
                 cfg: codegen_config,
-                comments: None,
+                comments: Some(&comments),
                 cm: cm.clone(),
                 wr: writer,
             };
         
     
-            let boxed_decl = Box::new(var_decl.clone());
-            let decl_var = Decl::Var(boxed_decl);
+            let item = if export {
+                // The JSDoc goes before `export`
+                let decl_var = Decl::Var(Box::new(VarDecl { span: DUMMY_SP, ..var_decl.clone() }));
+                ModuleItem::ModuleDecl(ModuleDecl::ExportDecl(ExportDecl { span: var_decl.span, decl: decl_var }))
+            } else {
+                ModuleItem::from(Decl::Var(Box::new(var_decl.clone())))
+            };
             emitter
-                .emit_module_item(&ModuleItem::from(decl_var))
+                .emit_module_item(&item)
                 .expect("Failed to emit JavaScript code");
         }
         let emitted_code = String::from_utf8_lossy(&buf);
@@ -1,17 +1,16 @@
 use std::ops::Deref;
+use std::fmt::Write;
 
 use serde::Deserialize;
+use sqlparser::ast::DataType;
+
+use super::{is_serial_type, Column, DefaultValue, Dialect, GeneratedFiles, ParserError, Schema, Table};
+use super::langgen_rust::capitalize_first_letter;
 
 /// AST type for Language::Python(PythonAst)
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Default, Clone, Deserialize)]
 pub struct PythonAst(String);
 
-impl Default for PythonAst {
-    fn default() -> Self {
-        PythonAst(String::new())
-    }
-}
-
 impl Deref for PythonAst {
     type Target = String;
 
@@ -34,11 +33,204 @@ impl PartialEq for PythonAst {
 }
 
 /// parse_create_table Python implementation
-pub fn python_parse_create_table(sql: &str) -> PythonAst {
-    PythonAst(sql.to_string())
+///
+/// # Errors
+///
+/// * The errors of `Schema::from_sql` if the SQL is not valid
+pub fn python_parse_create_table(sql: &str) -> Result<PythonAst, ParserError> {
+    let schema = Schema::from_sql(sql, &Dialect::Generic)?;
+    Ok(python_schema_to_ast(&schema))
+}
+
+/// schema_to_ast Python implementation: the dataclasses of the rows, without the lookups and relation helpers
+/// of the Rust backends
+pub fn python_schema_to_ast(schema: &Schema) -> PythonAst {
+    let mut py_code = String::from(IMPORTS);
+    for table in &schema.tables {
+        write_table(&mut py_code, table);
+    }
+    PythonAst(py_code)
+}
+
+/// schema_to_files Python implementation: a module per table, re-exported by `__init__.py`
+pub fn python_schema_to_files(schema: &Schema) -> GeneratedFiles {
+    let mut files = GeneratedFiles::default();
+    let mut index = String::new();
+    for table in &schema.tables {
+        let mut py_code = String::from(IMPORTS);
+        write_table(&mut py_code, table);
+        let module = python_name(&table.name);
+        files.push(format!("{}.py", module), py_code);
+
+        let class_name = class_name(table);
+        if table.is_view() {
+            writeln!(index, "from .{} import {}", module, class_name).unwrap();
+        } else {
+            writeln!(index, "from .{} import {}, New{}", module, class_name, class_name).unwrap();
+        }
+    }
+    files.push("__init__.py", index);
+    files
+}
+
+/// Imports of the generated dataclasses
+const IMPORTS: &str = "from dataclasses import dataclass\nfrom typing import Optional\n";
+
+/// Write the dataclasses of a table
+fn write_table(py_code: &mut String, table: &Table) {
+    let class_name = class_name(table);
+    let new_class_name = format!("New{}", class_name);
+
+    // Dataclass with all the columns
+    writeln!(py_code, "\n\n@dataclass\nclass {}:", class_name).unwrap();
+    if let Some(comment) = &table.comment {
+        write_docstring(py_code, comment);
+    }
+    for column in &table.columns {
+        writeln!(py_code, "    {}: {}", field_name(column), column_type(column)).unwrap();
+        if let Some(comment) = &column.comment {
+            write_docstring(py_code, comment);
+        }
+    }
+
+    // Views are read-only: no insert dataclass
+    if table.is_view() {
+        return;
+    }
+
+    // Insert dataclass: generated columns are left out, and columns with a DEFAULT
+    // get it as default value, after the required ones as dataclasses demand
+    writeln!(py_code, "\n\n@dataclass\nclass {}:", new_class_name).unwrap();
+    let (defaulted, required): (Vec<&Column>, Vec<&Column>) = table.insertable_columns().partition(|c| c.has_default());
+    for column in &required {
+        writeln!(py_code, "    {}: {}", field_name(column), column_type(column)).unwrap();
+        if let Some(comment) = &column.comment {
+            write_docstring(py_code, comment);
+        }
+    }
+    for column in &defaulted {
+        match default_literal(column) {
+            Some(value) => writeln!(py_code, "    {}: {} = {}", field_name(column), column_type(column), value).unwrap(),
+            None => writeln!(py_code, "    {}: Optional[{}] = None", field_name(column), column_type(column)).unwrap(),
+        }
+        if let Some(comment) = &column.comment {
+            write_docstring(py_code, comment);
+        }
+    }
+
+    // Conversion from the insert dataclass to the full row, using the values assigned by the database
+    let params: String = table.columns.iter()
+        .filter(|c| c.generated || (c.has_default() && default_literal(c).is_none()))
+        .map(|c| format!(", {}: {}", field_name(c), column_type(c)))
+        .collect();
+    writeln!(py_code, "\n    def into_{}(self{}) -> \"{}\":", python_name(&table.name), params, class_name).unwrap();
+    writeln!(py_code, "        return {}(", class_name).unwrap();
+    for column in &table.columns {
+        let name = field_name(column);
+        if column.generated {
+            writeln!(py_code, "            {}={},", name, name).unwrap();
+        } else if column.has_default() && default_literal(column).is_none() {
+            writeln!(py_code, "            {}=self.{} if self.{} is not None else {},", name, name, name, name).unwrap();
+        } else {
+            writeln!(py_code, "            {}=self.{},", name, name).unwrap();
+        }
+    }
+    writeln!(py_code, "        )").unwrap();
+}
+
+/// Python literal for the `DEFAULT` of a column, if it fits the field type
+fn default_literal(column: &Column) -> Option<String> {
+    match (column.default_value()?, base_type(column)) {
+        (DefaultValue::Null, _) => column.nullable.then(|| "None".to_string()),
+        (DefaultValue::Number(number), "int") => number.parse::<i64>().ok().map(|n| n.to_string()),
+        (DefaultValue::Number(number), "float") => number.parse::<f64>().ok().filter(|n| n.is_finite()).map(|n| format!("{:?}", n)),
+        (DefaultValue::Number(number), "bool") => match number.as_str() {
+            "0" => Some("False".to_string()),
+            "1" => Some("True".to_string()),
+            _ => None,
+        },
+        (DefaultValue::Boolean(value), "bool") => Some(if value { "True" } else { "False" }.to_string()),
+        (DefaultValue::String(text) | DefaultValue::Number(text), "str") => {
+            Some(format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")))
+        }
+        _ => None,
+    }
+}
+
+/// Python type annotation for a column
+fn column_type(column: &Column) -> String {
+    let base_type = base_type(column);
+    if column.nullable {
+        format!("Optional[{}]", base_type)
+    } else {
+        base_type.to_string()
+    }
+}
+
+/// Python type of a column, without nullability
+fn base_type(column: &Column) -> &'static str {
+    match &column.data_type {
+        DataType::Int(_) | DataType::Integer(_) | DataType::BigInt(_) => "int",
+        DataType::Custom(..) if is_serial_type(&column.data_type) => "int",
+        DataType::Varchar(_) | DataType::Text => "str",
+        DataType::Boolean => "bool",
+        DataType::Float(_) | DataType::Double => "float",
+        _ => "str", // Default to str for unhandled types
+    }
+}
+
+/// Write a docstring in the class body, for the class or the attribute before it
+fn write_docstring(py_code: &mut String, comment: &str) {
+    let comment = comment.replace('\\', "\\\\").replace("\"\"\"", "\\\"\"\"");
+    match comment.lines().collect::<Vec<_>>().as_slice() {
+        [line] => writeln!(py_code, "    \"\"\"{}\"\"\"", line).unwrap(),
+        lines => {
+            writeln!(py_code, "    \"\"\"").unwrap();
+            for line in lines {
+                writeln!(py_code, "    {}", line).unwrap();
+            }
+            writeln!(py_code, "    \"\"\"").unwrap();
+        }
+    }
+}
+
+/// Python keywords, escaped with a trailing `_` in the generated names
+const KEYWORDS: &[&str] = &[
+    "and", "as", "assert", "async", "await", "break", "class", "continue", "def", "del", "elif", "else", "except",
+    "finally", "for", "from", "global", "if", "import", "in", "is", "lambda", "nonlocal", "not", "or", "pass",
+    "raise", "return", "try", "while", "with", "yield",
+];
+
+/// Field name for a column
+fn field_name(column: &Column) -> String {
+    python_name(&column.name)
+}
+
+/// Lowercase Python name for an SQL name: the characters not allowed in names become `_`,
+/// and the keywords get a trailing `_`, e.g. `class` becomes `class_`
+fn python_name(name: &str) -> String {
+    python_identifier(&name.to_lowercase())
+}
+
+/// Class name for a table, e.g. `user-data` becomes `User_data` and `2fa_codes` becomes `_2fa_codes`
+fn class_name(table: &Table) -> String {
+    python_identifier(&capitalize_first_letter(&table.name))
+}
+
+/// Python identifier for a name: the characters not allowed in names become `_`,
+/// and the keywords get a trailing `_`
+fn python_identifier(name: &str) -> String {
+    let mut name: String = name.chars().map(|c| if c.is_alphanumeric() || c == '_' { c } else { '_' }).collect();
+    if !name.starts_with(|c: char| c.is_alphabetic() || c == '_') {
+        name.insert(0, '_');
+    }
+    if KEYWORDS.contains(&name.as_str()) {
+        name.push('_');
+    }
+    name
 }
 
-/// ast_to_string Rust implementation
+/// ast_to_string Python implementation
 pub fn python_ast_to_string(ast: &PythonAst) -> String {
     ast.to_string()
 }
@@ -1,23 +1,137 @@
 use serde::de::{self, Deserializer, Visitor, SeqAccess};
 use serde::Deserialize;
-use sqlparser::dialect::GenericDialect;
-use sqlparser::parser::Parser;
-use sqlparser::ast::{Statement, DataType, ColumnOption};
+use sqlparser::ast::{DataType, TimezoneInfo};
 use syn::token::Pub;
-use syn::{parse_str, Field, Ident, ItemStruct, Type, Visibility};
-use proc_macro2::{self, Span};
-use quote::ToTokens;
+use syn::ext::IdentExt;
+use syn::{parse_str, Attribute, Field, Ident, Item, ItemStruct, Type, Visibility};
+use proc_macro2::{self, Literal, Span, TokenStream, TokenTree};
+use quote::{format_ident, quote, ToTokens};
 use std::ops::Deref;
 use std::fmt;
+use std::io;
+
+use super::{is_serial_type, Column, DefaultValue, Dialect, GeneratedFiles, ParserError, Schema, Table};
+use super::langgen_rust_crud::{crud_items, RustBackend, TableTypes};
 
 /// AST type for Language::Rust(RustAst)
 #[derive(Clone)]
-pub struct RustAst(Vec<ItemStruct>);
+pub struct RustAst(Vec<Item>, RustOptions);
+
+/// Code generation options for Language::Rust(RustAst)
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RustOptions {
+    /// Backend of the generated CRUD functions, none are generated if missing
+    #[serde(default)]
+    pub backend: Option<RustBackend>,
+    /// Extra derives of the row, insert and patch structs, e.g. `["Debug", "Clone"]`
+    #[serde(default, deserialize_with = "deserialize_derives")]
+    pub derives: Vec<String>,
+    /// Crates of the types of the columns without a `std` type
+    #[serde(default)]
+    pub types: RustTypeCrates,
+    /// Naming style of the generated types
+    #[serde(default)]
+    pub naming: RustNaming,
+}
+
+/// Crates of the Rust types of the SQL types without a `std` equivalent;
+/// the columns of these types are `String`s when no crate is selected, and an error with a `backend`
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RustTypeCrates {
+    /// Crate of the DATE, TIME and TIMESTAMP types
+    #[serde(default)]
+    pub datetime: Option<DateTimeCrate>,
+    /// `uuid::Uuid` for UUID columns
+    #[serde(default)]
+    pub uuid: bool,
+    /// `rust_decimal::Decimal` for DECIMAL and NUMERIC columns
+    #[serde(default)]
+    pub decimal: bool,
+    /// `serde_json::Value` for JSON columns
+    #[serde(default)]
+    pub json: bool,
+}
+
+/// Crates for the date and time types
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DateTimeCrate {
+    /// `chrono::NaiveDate`, `chrono::NaiveDateTime`, `chrono::DateTime<chrono::Utc>`...
+    Chrono,
+    /// `time::Date`, `time::PrimitiveDateTime`, `time::OffsetDateTime`...
+    Time,
+}
+
+/// Naming styles of the generated types
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RustNaming {
+    /// `person_office` becomes `PersonOffice`
+    PascalCase,
+    /// Only the first letter is capitalized, as the types were always named: `person_office` becomes `Person_office`
+    #[default]
+    Capitalize,
+}
+
+impl RustNaming {
+    /// Type name for a table or relation name
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use noorm::parser::RustNaming;
+    ///
+    /// assert_eq!(RustNaming::PascalCase.type_name("person_office").to_string(), "PersonOffice");
+    /// assert_eq!(RustNaming::Capitalize.type_name("person_office").to_string(), "Person_office");
+    /// ```
+    pub fn type_name(&self, name: &str) -> Ident {
+        let name = rust_ident(name).unraw().to_string();
+        let name = match self {
+            RustNaming::PascalCase => name.split('_').map(capitalize_first_letter).collect(),
+            RustNaming::Capitalize => capitalize_first_letter(&name),
+        };
+        rust_ident(&name)
+    }
+}
+
+/// Derives must be paths, e.g. `Debug` or `serde::Serialize`
+fn deserialize_derives<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    let derives = Vec::<String>::deserialize(deserializer)?;
+    for derive in &derives {
+        if parse_str::<syn::Path>(derive).is_err() {
+            return Err(de::Error::custom(format!("invalid derive `{}`, expected a trait path", derive)));
+        }
+    }
+    Ok(derives)
+}
+
+impl RustAst {
+    /// Create an empty RustAst with the given generation options.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use noorm::parser::{Language, RustAst, RustBackend, RustOptions};
+    ///
+    /// let options = RustOptions { backend: Some(RustBackend::Sqlx), ..Default::default() };
+    /// let language = Language::Rust(RustAst::with_options(options));
+    /// ```
+    pub fn with_options(options: RustOptions) -> Self {
+        RustAst(Vec::new(), options)
+    }
+
+    /// Get the generation options.
+    pub fn options(&self) -> &RustOptions {
+        &self.1
+    }
+}
 
 impl fmt::Debug for RustAst {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("RustAst")
-            .field("inner", &"Vec<ItemStruct>")  // Placeholder for field2
+            .field("inner", &"Vec<Item>")  // Placeholder for field2
             .finish()
     }
 }
@@ -26,24 +140,16 @@ impl Default for RustAst {
     fn default() -> Self {
 
         let v = Vec::new();
-        let mut ast = RustAst(v);
-        ast.0.push(ItemStruct {
-            attrs: Vec::new(),
-            vis: Visibility::Public(Pub::default()),
-            struct_token: Default::default(),
-            ident: Ident::new("emptyIdent", Span::call_site()),
-            generics: Default::default(),
-            fields: syn::Fields::Named(syn::FieldsNamed {
-                brace_token: Default::default(),
-                named: Default::default(),
-            }),
-            semi_token: None,
-        });
+        let mut ast = RustAst(v, RustOptions::default());
+        ast.0.push(Item::Struct(public_struct(
+            Ident::new("emptyIdent", Span::call_site()),
+            Vec::new(),
+        )));
         ast
     }
 }
 impl Deref for RustAst {
-    type Target = Vec<ItemStruct>;
+    type Target = Vec<Item>;
 
     fn deref(&self) -> &Self::Target {
         &self.0
@@ -52,11 +158,11 @@ impl Deref for RustAst {
 
 impl PartialEq for RustAst {
     fn eq(&self, other: &Self) -> bool {
-        if self.0.len() != other.0.len() {
+        if self.0.len() != other.0.len() || self.1 != other.1 {
             return false;
         }
 
-        // Compare each `ItemStruct` by converting them to a `String`
+        // Compare each `Item` by converting them to a `String`
         for (item1, item2) in self.0.iter().zip(&other.0) {
             let item1_str = item1.to_token_stream().to_string();
             let item2_str = item2.to_token_stream().to_string();
@@ -87,10 +193,10 @@ impl<'de> Visitor<'de> for RustAstVisitor {
     type Value = RustAst;
 
     fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-        formatter.write_str("a sequence of Rust struct definitions as strings")
+        formatter.write_str("a sequence of Rust item definitions as strings")
     }
 
-    // Visit the sequence and parse each item as a Rust struct using syn::parse_str
+    // Visit the sequence and parse each item as a Rust item using syn::parse_str
     fn visit_seq<A>(self, mut seq: A) -> Result<RustAst, A::Error>
     where
         A: SeqAccess<'de>,
@@ -98,85 +204,475 @@ impl<'de> Visitor<'de> for RustAstVisitor {
         let mut items = Vec::new();
 
         while let Some(value) = seq.next_element::<String>()? {
-            match parse_str::<ItemStruct>(&value) {
-                Ok(item_struct) => items.push(item_struct),
-                Err(_) => return Err(de::Error::custom("Failed to parse Rust struct")),
+            match parse_str::<Item>(&value) {
+                Ok(item) => items.push(item),
+                Err(_) => return Err(de::Error::custom("Failed to parse Rust item")),
             }
         }
 
-        Ok(RustAst(items))
+        Ok(RustAst(items, RustOptions::default()))
     }
 }
 
 
 
 /// parse_create_table Rust implementation
-pub fn rust_parse_create_table(sql: &str) -> RustAst {
-    let dialect = GenericDialect {}; // or MySqlDialect, PostgreSqlDialect, etc.
-    let ast = Parser::parse_sql(&dialect, sql).expect("Failed to parse SQL");
-    let mut table_vec = Vec::new();
-    for stmt in ast {
-        if let Statement::CreateTable(create_table) = stmt {
-            let table_name = &create_table.name.0[0].value;
-            let struct_name = Ident::new(&capitalize_first_letter(table_name), proc_macro2::Span::call_site());
-
-            // Create the Rust struct fields
-            let mut struct_fields = Vec::new();
-            
-            for column in create_table.columns {
-                let col_name = &column.name.value;
-                let col_type = &column.data_type;
-                let is_nullable = column.options.iter().any(|opt| opt.option == ColumnOption::Null);
-
-                // Map SQL type to Rust type
-                let rust_type = sql_to_type(col_type, is_nullable);
-
-                // Generate the field
-                let field_name = Ident::new(&col_name.to_lowercase(), proc_macro2::Span::call_site());
-                let field: Field = syn::parse_quote! {
-                    pub #field_name: #rust_type
-                };
-                struct_fields.push(field);
+///
+/// # Errors
+///
+/// * The errors of `Schema::from_sql` if the SQL is not valid
+pub fn rust_parse_create_table(sql: &str) -> Result<RustAst, ParserError> {
+    let schema = Schema::from_sql(sql, &Dialect::Generic)?;
+    Ok(rust_schema_to_ast(&schema, &Dialect::Generic, &RustOptions::default()))
+}
+
+/// schema_to_ast Rust implementation
+pub fn rust_schema_to_ast(schema: &Schema, dialect: &Dialect, options: &RustOptions) -> RustAst {
+    let items: Vec<Item> = schema.tables.iter()
+        .flat_map(|table| table_items(schema, table, dialect, options))
+        .collect();
+    let mut file_items = use_items(&items);
+    file_items.extend(items);
+    RustAst(file_items, options.clone())
+}
+
+/// schema_to_files Rust implementation: a module per table, re-exported by `mod.rs`
+pub fn rust_schema_to_files(schema: &Schema, dialect: &Dialect, options: &RustOptions) -> GeneratedFiles {
+    let modules: Vec<(Ident, Vec<Item>)> = schema.tables.iter()
+        .map(|table| (rust_ident(&table.name.to_lowercase()), table_items(schema, table, dialect, options)))
+        .collect();
+    let defined: Vec<Vec<Ident>> = modules.iter().map(|(_, items)| defined_types(items)).collect();
+
+    let mut files = GeneratedFiles::default();
+    for (i, (module, items)) in modules.iter().enumerate() {
+        // Types of the other modules, e.g. the related rows, come through the index
+        let imported: Vec<&Ident> = defined.iter()
+            .enumerate()
+            .filter(|(j, _)| *j != i)
+            .flat_map(|(_, names)| names)
+            .filter(|name| items.iter().any(|item| mentions_ident(item.to_token_stream(), &name.to_string())))
+            .collect();
+        let mut file_items = use_items(items);
+        if !imported.is_empty() {
+            file_items.push(syn::parse_quote!(use super::{#(#imported),*};));
+        }
+        file_items.extend(items.iter().cloned());
+        files.push(format!("{}.rs", module.unraw()), rust_ast_to_string(&file_items));
+    }
+
+    let names: Vec<&Ident> = modules.iter().map(|(module, _)| module).collect();
+    let index: syn::File = syn::parse_quote! {
+        #(
+            pub mod #names;
+            pub use #names::*;
+        )*
+    };
+    files.push("mod.rs", prettyplease::unparse(&index));
+    files
+}
+
+/// Items generated for a table: the row struct, its insert and patch companions, and the CRUD functions
+fn table_items(schema: &Schema, table: &Table, dialect: &Dialect, options: &RustOptions) -> Vec<Item> {
+    let mut items = Vec::new();
+    let struct_name = options.naming.type_name(&table.name);
+    let new_struct_name = format_ident!("New{}", struct_name);
+    // Without a backend reading the rows, the fields keep the widest types
+    let database = options.backend.map_or(Dialect::Generic, |backend| backend.sql_dialect(dialect));
+
+    // Create the Rust struct fields
+    let mut struct_fields = Vec::new();
+    for column in &table.columns {
+        let field_name = field_ident(column);
+        let rust_type = column_type(column, &options.types, &database);
+        let mut docs = doc_attrs(column.comment.as_deref());
+        if options.backend == Some(RustBackend::Sqlx) && field_name.unraw() != column.name {
+            let name = &column.name;
+            docs.push(syn::parse_quote!(#[sqlx(rename = #name)]));
+        }
+        let field: Field = syn::parse_quote! {
+            #(#docs)*
+            pub #field_name: #rust_type
+        };
+        struct_fields.push(field);
+    }
+    let mut row_struct = public_struct(struct_name.clone(), struct_fields);
+    row_struct.attrs.extend(doc_attrs(table.comment.as_deref()));
+    row_struct.attrs.extend(derive_attr(options, &[]));
+    if let Some(backend) = options.backend {
+        row_struct.attrs.extend(backend.row_attrs());
+    }
+    items.push(Item::Struct(row_struct));
+
+    // Views are read-only: only the row struct and the queries are generated
+    if table.is_view() {
+        if let Some(backend) = options.backend {
+            let types = TableTypes { row: &struct_name, new: &new_struct_name, options };
+            items.extend(crud_items(schema, table, &types, dialect, backend));
+        }
+        return items;
+    }
+
+    // Create the insert struct fields: generated columns are left out,
+    // and columns with a DEFAULT become optional
+    let mut new_fields = Vec::new();
+    for column in table.insertable_columns() {
+        let field_name = field_ident(column);
+        let rust_type = column_type(column, &options.types, &database);
+        let docs = doc_attrs(column.comment.as_deref());
+        let field: Field = if column.has_default() {
+            syn::parse_quote! { #(#docs)* pub #field_name: Option<#rust_type> }
+        } else {
+            syn::parse_quote! { #(#docs)* pub #field_name: #rust_type }
+        };
+        new_fields.push(field);
+    }
+    let mut new_struct = public_struct(new_struct_name.clone(), new_fields);
+    let defaultable = table.insertable_columns().all(|c| c.nullable || c.has_default());
+    new_struct.attrs.extend(derive_attr(options, if defaultable { &["Default"] } else { &[] }));
+    items.push(Item::Struct(new_struct));
+    items.extend(default_fns(table, &struct_name, &options.types, &database));
+    items.push(new_to_row_conversion(table, &struct_name, &new_struct_name, &options.types, &database));
+
+    // Create the patch struct, only for tables with a primary key to target the row
+    // and other columns to update
+    if !table.primary_key.is_empty() && table.non_key_columns().next().is_some() {
+        let patch_struct_name = format_ident!("{}Patch", struct_name);
+        let mut patch_fields = Vec::new();
+        for column in table.non_key_columns() {
+            let field_name = field_ident(column);
+            let rust_type = column_type(column, &options.types, &database);
+            let docs = doc_attrs(column.comment.as_deref());
+            let field: Field = syn::parse_quote! {
+                #(#docs)*
+                pub #field_name: Option<#rust_type>
+            };
+            patch_fields.push(field);
+        }
+        let mut patch_struct = public_struct(patch_struct_name.clone(), patch_fields);
+        patch_struct.attrs.extend(derive_attr(options, &["Default"]));
+        items.push(Item::Struct(patch_struct));
+        items.push(patch_update_sql(table, &patch_struct_name, dialect));
+    }
+
+    // Create the CRUD functions for the selected backend
+    if let Some(backend) = options.backend {
+        let types = TableTypes { row: &struct_name, new: &new_struct_name, options };
+        items.extend(crud_items(schema, table, &types, dialect, backend));
+    }
+    items
+}
+
+/// Names of the types defined by the items
+fn defined_types(items: &[Item]) -> Vec<Ident> {
+    items.iter()
+        .filter_map(|item| match item {
+            Item::Struct(item) => Some(item.ident.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// `use` items for the traits the generated items refer to by name
+fn use_items(items: &[Item]) -> Vec<Item> {
+    let imports: [(&str, Item); 1] = [
+        ("OptionalExtension", syn::parse_quote!(use rusqlite::OptionalExtension;)),
+    ];
+    imports.into_iter()
+        .filter(|(name, _)| items.iter().any(|item| mentions_ident(item.to_token_stream(), name)))
+        .map(|(_, item)| item)
+        .collect()
+}
+
+/// Check if an identifier appears in a token stream
+fn mentions_ident(tokens: TokenStream, name: &str) -> bool {
+    tokens.into_iter().any(|token| match token {
+        TokenTree::Ident(ident) => ident == name,
+        TokenTree::Group(group) => mentions_ident(group.stream(), name),
+        _ => false,
+    })
+}
+
+/// Conversion from the insert struct to the full row struct
+fn new_to_row_conversion(table: &Table, struct_name: &Ident, new_struct_name: &Ident, crates: &RustTypeCrates, dialect: &Dialect) -> Item {
+    let value = |column: &Column, new: TokenStream| {
+        let field_name = field_ident(column);
+        if column.has_default() {
+            let default_fn = format_ident!("default_{}", field_name);
+            quote! { #new.#field_name.unwrap_or_else(#struct_name::#default_fn) }
+        } else {
+            quote! { #new.#field_name }
+        }
+    };
+
+    let assigned: Vec<&Column> = table.columns.iter().filter(|c| is_assigned(c, crates, dialect)).collect();
+    if assigned.is_empty() {
+        let fields: Vec<Ident> = table.columns.iter().map(field_ident).collect();
+        let values = table.columns.iter().map(|column| value(column, quote!(new)));
+        return syn::parse_quote! {
+            impl From<#new_struct_name> for #struct_name {
+                fn from(new: #new_struct_name) -> Self {
+                    Self { #(#fields: #values),* }
+                }
             }
-            // Generate the full struct definition using syn
-            table_vec.push(ItemStruct {
-                attrs: Vec::new(),
-                vis: Visibility::Public(Pub::default()),
-                struct_token: Default::default(),
-                ident: struct_name,
-                generics: Default::default(),
-                fields: syn::Fields::Named(syn::FieldsNamed {
-                    brace_token: Default::default(),
-                    named: struct_fields.into_iter().collect(),
-                }),
-                semi_token: None,
-            });
-        }
-    } // End For statements
-    RustAst(table_vec)
-}
-
-
-/// ast_to_string Rust implementation
-pub fn rust_ast_to_string(ast: &Vec<ItemStruct>) -> String {
-    let mut s = String::new();
-    for item in ast {
-        s = format!("{}{}\n", s, item.to_token_stream().to_string());
-    }
-    s
-}
-
-/// sql_to_type Rust implementation
-fn sql_to_type(data_type: &DataType, is_nullable: bool) -> syn::Type {
-    let base_type: Type = match data_type {
-        DataType::Int(_) => syn::parse_quote!(i64),
-        DataType::Integer(_) => syn::parse_quote!(i64),
-        DataType::Varchar(_) | DataType::Text => syn::parse_quote!(String),
-        DataType::Boolean => syn::parse_quote!(bool),
-        DataType::Float(_) | DataType::Double => syn::parse_quote!(f64),
-        _ => syn::parse_quote!(String), // Default to String for unhandled types
+        };
+    }
+
+    let method_name = format_ident!("into_{}", rust_ident(&table.name.to_lowercase()));
+    let params = assigned.iter().map(|column| {
+        let field_name = field_ident(column);
+        let rust_type = column_type(column, crates, dialect);
+        quote! { #field_name: #rust_type }
+    });
+    let inits = table.columns.iter().map(|column| {
+        let field_name = field_ident(column);
+        if column.generated {
+            quote! { #field_name }
+        } else if is_assigned(column, crates, dialect) {
+            quote! { #field_name: self.#field_name.unwrap_or(#field_name) }
+        } else {
+            let value = value(column, quote!(self));
+            quote! { #field_name: #value }
+        }
+    });
+    syn::parse_quote! {
+        impl #new_struct_name {
+            /// Build the full row from the values assigned by the database.
+            pub fn #method_name(self, #(#params),*) -> #struct_name {
+                #struct_name { #(#inits),* }
+            }
+        }
+    }
+}
+
+/// The value of the column is only known once assigned by the database:
+/// it is generated, or its `DEFAULT` has no Rust equivalent
+fn is_assigned(column: &Column, crates: &RustTypeCrates, dialect: &Dialect) -> bool {
+    column.generated || (column.has_default() && default_expr(column, crates, dialect).is_none())
+}
+
+/// `default_<column>()` functions returning the literal `DEFAULT`s of the table
+fn default_fns(table: &Table, struct_name: &Ident, crates: &RustTypeCrates, dialect: &Dialect) -> Option<Item> {
+    let fns: Vec<TokenStream> = table.columns.iter()
+        .filter(|column| column.has_default())
+        .filter_map(|column| {
+            let value = default_expr(column, crates, dialect)?;
+            let fn_name = format_ident!("default_{}", field_ident(column));
+            let rust_type = column_type(column, crates, dialect);
+            let doc = format!(" `DEFAULT {}` of the `{}` column.", column.default.as_ref()?, column.name);
+            Some(quote! {
+                #[doc = #doc]
+                pub fn #fn_name() -> #rust_type {
+                    #value
+                }
+            })
+        })
+        .collect();
+    if fns.is_empty() {
+        return None;
+    }
+    Some(syn::parse_quote! {
+        impl #struct_name {
+            #(#fns)*
+        }
+    })
+}
+
+/// Rust expression for the literal `DEFAULT` of a column, if it fits the field type
+fn default_expr(column: &Column, crates: &RustTypeCrates, dialect: &Dialect) -> Option<TokenStream> {
+    let base_type = sql_to_type(&column.data_type, false, crates, dialect).to_token_stream().to_string();
+    let value = match (column.default_value()?, base_type.as_str()) {
+        (DefaultValue::Null, _) => return column.nullable.then(|| quote!(None)),
+        // A value out of the range of the field is left to the database
+        (DefaultValue::Number(number), "i16") => {
+            let literal = Literal::i16_unsuffixed(number.parse().ok()?);
+            quote!(#literal)
+        }
+        (DefaultValue::Number(number), "i32") => {
+            let literal = Literal::i32_unsuffixed(number.parse().ok()?);
+            quote!(#literal)
+        }
+        (DefaultValue::Number(number), "i64") => {
+            let literal = Literal::i64_unsuffixed(number.parse().ok()?);
+            quote!(#literal)
+        }
+        (DefaultValue::Number(number), "u64") => {
+            let literal = Literal::u64_unsuffixed(number.parse().ok()?);
+            quote!(#literal)
+        }
+        (DefaultValue::Number(number), "f32") => {
+            let value: f32 = number.parse().ok()?;
+            let literal = value.is_finite().then(|| Literal::f32_unsuffixed(value))?;
+            quote!(#literal)
+        }
+        (DefaultValue::Number(number), "f64") => {
+            let value: f64 = number.parse().ok()?;
+            let literal = value.is_finite().then(|| Literal::f64_unsuffixed(value))?;
+            quote!(#literal)
+        }
+        (DefaultValue::Number(number), "bool") => match number.as_str() {
+            "0" => quote!(false),
+            "1" => quote!(true),
+            _ => return None,
+        },
+        (DefaultValue::Boolean(value), "bool") => quote!(#value),
+        (DefaultValue::String(text) | DefaultValue::Number(text), "String") => quote!(#text.to_string()),
+        _ => return None,
+    };
+    Some(if column.nullable { quote!(Some(#value)) } else { value })
+}
+
+/// Dynamic `UPDATE` statement for the patch struct, with the placeholders of the dialect
+fn patch_update_sql(table: &Table, patch_struct_name: &Ident, dialect: &Dialect) -> Item {
+    let first_set_index = proc_macro2::Literal::usize_unsuffixed(table.primary_key.len() + 1);
+    let where_clause = table.primary_key_columns()
+        .enumerate()
+        .map(|(i, column)| format!("{} = {}", dialect.quote_identifier(&column.name), dialect.placeholder(i + 1)))
+        .collect::<Vec<_>>()
+        .join(" AND ");
+    let update_format = format!("UPDATE {} SET {{}} WHERE {}", dialect.quote_identifier(&table.name), where_clause);
+
+    let (fields, names): (Vec<Ident>, Vec<String>) = table.non_key_columns()
+        .map(|column| (field_ident(column), dialect.quote_identifier(&column.name)))
+        .unzip();
+    let (assignments, doc) = match dialect.placeholder_prefix() {
+        Some(prefix) => {
+            let assignment_format = format!("{{}} = {}{{}}", prefix);
+            (
+                quote! { columns.iter().enumerate().map(|(i, column)| format!(#assignment_format, column, i + #first_set_index)) },
+                " Parameters are numbered with the primary key first, followed by the set fields in declaration order.",
+            )
+        }
+        None => (
+            quote! { columns.iter().map(|column| format!("{} = ?", column)) },
+            " Parameters are the set fields in declaration order, followed by the primary key.",
+        ),
     };
-    
+    syn::parse_quote! {
+        impl #patch_struct_name {
+            /// Build the parameterized `UPDATE` statement for the fields that are set,
+            /// or `None` if there is nothing to update.
+            ///
+            #[doc = #doc]
+            pub fn update_sql(&self) -> Option<String> {
+                let mut columns: Vec<&str> = Vec::new();
+                #(
+                    if self.#fields.is_some() {
+                        columns.push(#names);
+                    }
+                )*
+                if columns.is_empty() {
+                    return None;
+                }
+                let assignments: Vec<String> = #assignments.collect();
+                Some(format!(#update_format, assignments.join(", ")))
+            }
+        }
+    }
+}
+
+/// Create a public struct with named fields
+fn public_struct(ident: Ident, fields: Vec<Field>) -> ItemStruct {
+    ItemStruct {
+        attrs: Vec::new(),
+        vis: Visibility::Public(Pub::default()),
+        struct_token: Default::default(),
+        ident,
+        generics: Default::default(),
+        fields: syn::Fields::Named(syn::FieldsNamed {
+            brace_token: Default::default(),
+            named: fields.into_iter().collect(),
+        }),
+        semi_token: None,
+    }
+}
+
+/// `///` doc attributes for a table or column comment, one per line
+pub(super) fn doc_attrs(comment: Option<&str>) -> Vec<Attribute> {
+    comment.into_iter()
+        .flat_map(str::lines)
+        .map(|line| {
+            let line = format!(" {}", line);
+            syn::parse_quote!(#[doc = #line])
+        })
+        .collect()
+}
+
+/// Field name for a column
+pub(super) fn field_ident(column: &Column) -> Ident {
+    rust_ident(&column.name.to_lowercase())
+}
+
+/// Identifier for an SQL name: the characters not allowed in identifiers become `_`,
+/// and the keywords are raw identifiers, e.g. `my col` becomes `my_col` and `type` becomes `r#type`
+pub(super) fn rust_ident(name: &str) -> Ident {
+    let mut ident: String = name.chars().map(|c| if c.is_alphanumeric() || c == '_' { c } else { '_' }).collect();
+    if !ident.starts_with(|c: char| c.is_alphabetic() || c == '_') {
+        ident.insert(0, '_');
+    }
+    // Keywords that cannot be raw identifiers
+    if ["_", "self", "Self", "super", "crate"].contains(&ident.as_str()) {
+        ident.push('_');
+    }
+    parse_str::<Ident>(&ident)
+        .or_else(|_| parse_str::<Ident>(&format!("r#{}", ident)))
+        .unwrap_or_else(|_| {
+            let ascii: String = ident.chars().map(|c| if c.is_ascii_alphanumeric() { c } else { '_' }).collect();
+            format_ident!("_{}", ascii)
+        })
+}
+
+/// Field type for a column, read from a database of the dialect
+pub(super) fn column_type(column: &Column, crates: &RustTypeCrates, dialect: &Dialect) -> Type {
+    sql_to_type(&column.data_type, column.nullable, crates, dialect)
+}
+
+/// `#[derive]` attribute with the derives of the options, followed by the ones
+/// the generator needs and not already there
+fn derive_attr(options: &RustOptions, required: &[&str]) -> Option<Attribute> {
+    let mut derives: Vec<&str> = options.derives.iter().map(String::as_str).collect();
+    derives.extend(required.iter().filter(|name| !options.derives.iter().any(|d| d == *name)));
+    if derives.is_empty() {
+        return None;
+    }
+    let paths: Vec<syn::Path> = derives.iter().filter_map(|derive| parse_str(derive).ok()).collect();
+    Some(syn::parse_quote!(#[derive(#(#paths),*)]))
+}
+
+/// ast_to_file Rust implementation: the items as a complete source file
+pub fn rust_ast_to_file(ast: &[Item]) -> syn::File {
+    syn::File {
+        shebang: None,
+        attrs: Vec::new(),
+        items: ast.to_vec(),
+    }
+}
+
+/// ast_to_string Rust implementation, formatted with prettyplease
+pub fn rust_ast_to_string(ast: &[Item]) -> String {
+    prettyplease::unparse(&rust_ast_to_file(ast))
+}
+
+/// Write the formatted Rust source file to a writer.
+///
+/// # Examples
+///
+/// ```
+/// use noorm::prelude::*;
+/// use noorm::parser::{rust_schema_to_ast, write_rust_ast, RustOptions, Schema};
+///
+/// let schema = Schema::from_sql("CREATE TABLE person ( id INT, name TEXT )", &Dialect::Generic).unwrap();
+/// let ast = rust_schema_to_ast(&schema, &Dialect::Generic, &RustOptions::default());
+/// let mut out = Vec::new();
+/// write_rust_ast(&ast, &mut out).unwrap();
+/// assert!(String::from_utf8(out).unwrap().starts_with("pub struct Person {\n    pub id: i64,\n"));
+/// ```
+pub fn write_rust_ast<W: io::Write>(ast: &[Item], writer: &mut W) -> io::Result<()> {
+    writer.write_all(rust_ast_to_string(ast).as_bytes())
+}
+
+/// sql_to_type Rust implementation: the types without a Rust equivalent are `String`s
+fn sql_to_type(data_type: &DataType, is_nullable: bool, crates: &RustTypeCrates, dialect: &Dialect) -> syn::Type {
+    let base_type = rust_base_type(data_type, crates, dialect).unwrap_or_else(|| syn::parse_quote!(String));
     if is_nullable {
         syn::parse_quote!(Option<#base_type>)
     } else {
@@ -184,8 +680,89 @@ fn sql_to_type(data_type: &DataType, is_nullable: bool) -> syn::Type {
     }
 }
 
+/// Rust type read from a column of an SQL type, if any
+///
+/// The integers are `i64`, and the floats `f64`, except for PostgreSQL whose drivers only read each
+/// numeric type into the Rust type of the same width; MySQL's unsigned integers are `u64`.
+fn rust_base_type(data_type: &DataType, crates: &RustTypeCrates, dialect: &Dialect) -> Option<syn::Type> {
+    let with_time_zone = |tz: &TimezoneInfo| matches!(tz, TimezoneInfo::WithTimeZone | TimezoneInfo::Tz);
+    let postgres = dialect == &Dialect::PostgreSQL;
+    Some(match (data_type, crates.datetime) {
+        (DataType::SmallInt(_) | DataType::Int2(_), _) if postgres => syn::parse_quote!(i16),
+        (DataType::Int(_) | DataType::Integer(_) | DataType::Int4(_), _) if postgres => syn::parse_quote!(i32),
+        (DataType::Custom(name, _), _) if postgres && is_serial_type(data_type) => match name.to_string().to_uppercase().as_str() {
+            "SMALLSERIAL" | "SERIAL2" => syn::parse_quote!(i16),
+            "SERIAL" | "SERIAL4" => syn::parse_quote!(i32),
+            _ => syn::parse_quote!(i64),
+        },
+        (DataType::Real | DataType::Float4, _) if postgres => syn::parse_quote!(f32),
+        (DataType::Float(Some(precision)), _) if postgres && *precision <= 24 => syn::parse_quote!(f32),
+        (
+            DataType::TinyInt(_) | DataType::SmallInt(_) | DataType::Int2(_) | DataType::MediumInt(_) | DataType::Int(_)
+            | DataType::Integer(_) | DataType::Int4(_) | DataType::BigInt(_) | DataType::Int8(_),
+            _,
+        ) => syn::parse_quote!(i64),
+        (
+            DataType::UnsignedTinyInt(_) | DataType::UnsignedSmallInt(_) | DataType::UnsignedInt2(_) | DataType::UnsignedMediumInt(_)
+            | DataType::UnsignedInt(_) | DataType::UnsignedInteger(_) | DataType::UnsignedInt4(_) | DataType::UnsignedBigInt(_)
+            | DataType::UnsignedInt8(_),
+            _,
+        ) => syn::parse_quote!(u64),
+        (DataType::Custom(..), _) if is_serial_type(data_type) => syn::parse_quote!(i64),
+        (
+            DataType::Float(_) | DataType::Real | DataType::Float4 | DataType::Float8 | DataType::Double | DataType::DoublePrecision,
+            _,
+        ) => syn::parse_quote!(f64),
+        (DataType::Boolean | DataType::Bool, _) => syn::parse_quote!(bool),
+        (
+            DataType::Varchar(_) | DataType::Text | DataType::Char(_) | DataType::Character(_) | DataType::CharVarying(_)
+            | DataType::CharacterVarying(_) | DataType::Nvarchar(_) | DataType::String(_) | DataType::Clob(_)
+            | DataType::CharacterLargeObject(_) | DataType::CharLargeObject(_) | DataType::Enum(_),
+            _,
+        ) => syn::parse_quote!(String),
+        (DataType::Bytea | DataType::Blob(_) | DataType::Binary(_) | DataType::Varbinary(_) | DataType::Bytes(_), _) => syn::parse_quote!(Vec<u8>),
+        // Types defined in the database, e.g. through `CREATE DOMAIN`, are taken for text
+        (DataType::Custom(..), _) => syn::parse_quote!(String),
+        (DataType::Date, Some(DateTimeCrate::Chrono)) => syn::parse_quote!(chrono::NaiveDate),
+        (DataType::Time(..), Some(DateTimeCrate::Chrono)) => syn::parse_quote!(chrono::NaiveTime),
+        (DataType::Timestamp(_, tz), Some(DateTimeCrate::Chrono)) if with_time_zone(tz) => syn::parse_quote!(chrono::DateTime<chrono::Utc>),
+        (DataType::Timestamp(..) | DataType::Datetime(_), Some(DateTimeCrate::Chrono)) => syn::parse_quote!(chrono::NaiveDateTime),
+        (DataType::Date, Some(DateTimeCrate::Time)) => syn::parse_quote!(time::Date),
+        (DataType::Time(..), Some(DateTimeCrate::Time)) => syn::parse_quote!(time::Time),
+        (DataType::Timestamp(_, tz), Some(DateTimeCrate::Time)) if with_time_zone(tz) => syn::parse_quote!(time::OffsetDateTime),
+        (DataType::Timestamp(..) | DataType::Datetime(_), Some(DateTimeCrate::Time)) => syn::parse_quote!(time::PrimitiveDateTime),
+        (DataType::Uuid, _) if crates.uuid => syn::parse_quote!(uuid::Uuid),
+        (DataType::Decimal(_) | DataType::Numeric(_) | DataType::Dec(_), _) if crates.decimal => syn::parse_quote!(rust_decimal::Decimal),
+        (DataType::JSON | DataType::JSONB, _) if crates.json => syn::parse_quote!(serde_json::Value),
+        _ => return None,
+    })
+}
+
+/// Check that the backend of the options reads every column into a Rust type: without one,
+/// e.g. for a `DATE` column without a `datetime` crate, the field would be a `String` failing to decode
+///
+/// # Errors
+///
+/// * `ParserError::ColumnType` for the first column without a Rust type
+pub(super) fn check_column_types(schema: &Schema, dialect: &Dialect, options: &RustOptions) -> Result<(), ParserError> {
+    let Some(backend) = options.backend else { return Ok(()) };
+    let database = backend.sql_dialect(dialect);
+    for table in &schema.tables {
+        for column in &table.columns {
+            if rust_base_type(&column.data_type, &options.types, &database).is_none() {
+                return Err(ParserError::ColumnType {
+                    table: table.name.clone(),
+                    column: column.name.clone(),
+                    data_type: column.data_type.to_string(),
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
 /// Return first letter capitalized
-fn capitalize_first_letter(s: &str) -> String {
+pub(super) fn capitalize_first_letter(s: &str) -> String {
     let mut c = s.chars();
     match c.next() {
         None => String::new(),
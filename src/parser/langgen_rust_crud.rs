@@ -0,0 +1,566 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote, ToTokens};
+use serde::Deserialize;
+use syn::{Ident, Item};
+
+use super::{Column, Dialect, Lookup, RustOptions, Schema, Table};
+use super::langgen_rust::rust_ident;
+use super::langgen_rust_relations::relation_items;
+
+/// Database access backends for the generated Rust CRUD functions
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RustBackend {
+    /// Async `sqlx`, with the database driver selected by the Dialect
+    Sqlx,
+    /// Async `tokio-postgres`
+    TokioPostgres,
+    /// Sync `rusqlite`
+    Rusqlite,
+}
+
+impl RustBackend {
+    /// The dialect of the SQL sent through the backend, and of the database it reads
+    pub(super) fn sql_dialect(&self, dialect: &Dialect) -> Dialect {
+        match self {
+            // The generic dialect goes through the Postgres driver of sqlx
+            RustBackend::Sqlx if dialect == &Dialect::Generic => Dialect::PostgreSQL,
+            RustBackend::Sqlx => dialect.clone(),
+            RustBackend::TokioPostgres => Dialect::PostgreSQL,
+            RustBackend::Rusqlite => Dialect::SQLite,
+        }
+    }
+
+    /// Attributes added to the row struct
+    pub(super) fn row_attrs(&self) -> Vec<syn::Attribute> {
+        match self {
+            RustBackend::Sqlx => vec![syn::parse_quote!(#[derive(sqlx::FromRow)])],
+            RustBackend::TokioPostgres | RustBackend::Rusqlite => Vec::new(),
+        }
+    }
+}
+
+/// Names of the generated types for a table, and the options to generate the others
+pub(super) struct TableTypes<'a> {
+    pub row: &'a Ident,
+    pub new: &'a Ident,
+    pub options: &'a RustOptions,
+}
+
+/// How the primary key is passed to the generated functions
+struct KeyParam {
+    /// Function parameters
+    params: TokenStream,
+    /// Expressions of the key values, in key order
+    values: Vec<TokenStream>,
+}
+
+/// CRUD items for a table: the key struct for composite keys, the functions in an `impl` block
+/// of the row struct, and the relation helpers; views only get the queries
+pub(super) fn crud_items(schema: &Schema, table: &Table, types: &TableTypes, dialect: &Dialect, backend: RustBackend) -> Vec<Item> {
+    let mut items = Vec::new();
+    let sql_dialect = backend.sql_dialect(dialect);
+    let row = types.row;
+
+    let mut functions = vec![row_mapping(table, backend)];
+    if !table.is_view() {
+        functions.push(insert_fn(table, types, &sql_dialect, backend));
+    }
+    if !table.primary_key.is_empty() {
+        let key = key_param(table, types, &sql_dialect, &mut items);
+        functions.push(get_by_pk_fn(table, row, &key, &sql_dialect, backend));
+        if table.non_key_columns().next().is_some() {
+            functions.push(update_fn(table, row, &sql_dialect, backend));
+        }
+        functions.push(delete_fn(table, &key, &sql_dialect, backend));
+    }
+    functions.push(list_fn(table, row, &sql_dialect, backend));
+    for lookup in table.lookups() {
+        functions.push(lookup_fn(table, types, &lookup, &sql_dialect, backend));
+    }
+    let (relation_functions, join_items) = relation_items(schema, table, types, &sql_dialect, backend);
+    functions.extend(relation_functions);
+
+    items.push(syn::parse_quote! {
+        impl #row {
+            #(#functions)*
+        }
+    });
+    items.extend(join_items);
+    items
+}
+
+/// Key parameter of the functions; composite keys get a generated key struct
+fn key_param(table: &Table, types: &TableTypes, dialect: &Dialect, items: &mut Vec<Item>) -> KeyParam {
+    let crates = &types.options.types;
+    let keys: Vec<&Column> = table.primary_key_columns().collect();
+    if let [column] = keys.as_slice() {
+        let field_name = super::langgen_rust::field_ident(column);
+        let rust_type = super::langgen_rust::column_type(column, crates, dialect);
+        return KeyParam {
+            params: quote! { #field_name: #rust_type },
+            values: vec![quote! { #field_name }],
+        };
+    }
+
+    let key_name = format_ident!("{}Key", types.row);
+    let fields: Vec<Ident> = keys.iter().map(|c| super::langgen_rust::field_ident(c)).collect();
+    let field_types: Vec<syn::Type> = keys.iter().map(|c| super::langgen_rust::column_type(c, crates, dialect)).collect();
+    items.push(syn::parse_quote! {
+        /// Composite primary key
+        pub struct #key_name {
+            #(pub #fields: #field_types),*
+        }
+    });
+    KeyParam {
+        params: quote! { key: #key_name },
+        values: fields.iter().map(|f| quote! { key.#f }).collect(),
+    }
+}
+
+/// Mapping from a backend row to the row struct
+fn row_mapping(table: &Table, backend: RustBackend) -> TokenStream {
+    let fields: Vec<Ident> = table.columns.iter().map(super::langgen_rust::field_ident).collect();
+    let names: Vec<&str> = table.columns.iter().map(|c| c.name.as_str()).collect();
+    match backend {
+        RustBackend::Sqlx => TokenStream::new(),
+        RustBackend::TokioPostgres => quote! {
+            /// Build the struct from a `tokio_postgres::Row`.
+            pub fn from_row(row: &tokio_postgres::Row) -> Result<Self, tokio_postgres::Error> {
+                Ok(Self { #(#fields: row.try_get(#names)?),* })
+            }
+        },
+        RustBackend::Rusqlite => quote! {
+            /// Build the struct from a `rusqlite::Row`.
+            pub fn from_row(row: &rusqlite::Row) -> rusqlite::Result<Self> {
+                Ok(Self { #(#fields: row.get(#names)?),* })
+            }
+        },
+    }
+}
+
+/// `insert`: columns left to their DEFAULT are only sent when set
+fn insert_fn(table: &Table, types: &TableTypes, dialect: &Dialect, backend: RustBackend) -> TokenStream {
+    let (row, new) = (types.row, types.new);
+    let table_name = dialect.quote_identifier(&table.name);
+    let column_list = column_list(table, dialect);
+    let returning = dialect != &Dialect::MySQL;
+    let insert_format = if returning {
+        format!("INSERT INTO {} ({{}}) VALUES ({{}}) RETURNING {}", table_name, column_list)
+    } else {
+        format!("INSERT INTO {} ({{}}) VALUES ({{}})", table_name)
+    };
+    let placeholders = match dialect.placeholder_prefix() {
+        Some(prefix) => {
+            let placeholder_format = format!("{}{{}}", prefix);
+            quote! { (1..=columns.len()).map(|i| format!(#placeholder_format, i)).collect() }
+        }
+        None => quote! { columns.iter().map(|_| "?".to_string()).collect() },
+    };
+    // Tables with only defaulted columns may have nothing to send
+    let build_sql = if table.insertable_columns().any(|c| !c.has_default()) || !returning {
+        quote! {
+            let placeholders: Vec<String> = #placeholders;
+            let sql = format!(#insert_format, columns.join(", "), placeholders.join(", "));
+        }
+    } else {
+        let default_values = format!("INSERT INTO {} DEFAULT VALUES RETURNING {}", table_name, column_list);
+        quote! {
+            let placeholders: Vec<String> = #placeholders;
+            let sql = if columns.is_empty() {
+                #default_values.to_string()
+            } else {
+                format!(#insert_format, columns.join(", "), placeholders.join(", "))
+            };
+        }
+    };
+
+    // Columns to send, and with their values for the backends taking a parameter slice
+    let mut push_columns = Vec::new();
+    let mut push_values = Vec::new();
+    for column in table.insertable_columns() {
+        let field_name = super::langgen_rust::field_ident(column);
+        let name = dialect.quote_identifier(&column.name);
+        if column.has_default() {
+            push_columns.push(quote! { if new.#field_name.is_some() { columns.push(#name); } });
+            push_values.push(quote! { if let Some(value) = &new.#field_name { columns.push(#name); values.push(value); } });
+        } else {
+            push_columns.push(quote! { columns.push(#name); });
+            push_values.push(quote! { columns.push(#name); values.push(&new.#field_name); });
+        }
+    }
+
+    match backend {
+        RustBackend::Sqlx => {
+            let db = sqlx_database(dialect);
+            let binds = table.insertable_columns().map(|column| {
+                let field_name = super::langgen_rust::field_ident(column);
+                if column.has_default() {
+                    quote! { if let Some(value) = &new.#field_name { query = query.bind(value); } }
+                } else {
+                    quote! { query = query.bind(&new.#field_name); }
+                }
+            });
+            let (doc, return_type, query, run) = if returning {
+                (
+                    " Insert a new row, returning it as stored by the database.",
+                    quote! { #row },
+                    quote! { sqlx::query_as::<_, #row>(&sql) },
+                    quote! { query.fetch_one(executor).await },
+                )
+            } else if has_auto_increment_key(table) {
+                (
+                    " Insert a new row, returning the id assigned by the database.",
+                    quote! { u64 },
+                    quote! { sqlx::query(&sql) },
+                    quote! { Ok(query.execute(executor).await?.last_insert_id()) },
+                )
+            } else {
+                (
+                    " Insert a new row, returning the number of affected rows.",
+                    quote! { u64 },
+                    quote! { sqlx::query(&sql) },
+                    quote! { Ok(query.execute(executor).await?.rows_affected()) },
+                )
+            };
+            quote! {
+                #[doc = #doc]
+                pub async fn insert<'e, E>(executor: E, new: &#new) -> Result<#return_type, sqlx::Error>
+                where
+                    E: sqlx::Executor<'e, Database = #db>,
+                {
+                    let mut columns: Vec<&str> = Vec::new();
+                    #(#push_columns)*
+                    #build_sql
+                    let mut query = #query;
+                    #(#binds)*
+                    #run
+                }
+            }
+        }
+        RustBackend::TokioPostgres => quote! {
+            /// Insert a new row, returning it as stored by the database.
+            pub async fn insert(client: &impl tokio_postgres::GenericClient, new: &#new) -> Result<#row, tokio_postgres::Error> {
+                let mut columns: Vec<&str> = Vec::new();
+                let mut values: Vec<&(dyn tokio_postgres::types::ToSql + Sync)> = Vec::new();
+                #(#push_values)*
+                #build_sql
+                let row = client.query_one(sql.as_str(), &values).await?;
+                #row::from_row(&row)
+            }
+        },
+        RustBackend::Rusqlite => quote! {
+            /// Insert a new row, returning it as stored by the database.
+            pub fn insert(conn: &rusqlite::Connection, new: &#new) -> rusqlite::Result<#row> {
+                let mut columns: Vec<&str> = Vec::new();
+                let mut values: Vec<&dyn rusqlite::ToSql> = Vec::new();
+                #(#push_values)*
+                #build_sql
+                conn.query_row(&sql, values.as_slice(), #row::from_row)
+            }
+        },
+    }
+}
+
+/// The primary key is a single column numbered by the database, whose id is the last inserted one
+fn has_auto_increment_key(table: &Table) -> bool {
+    matches!(table.primary_key_columns().collect::<Vec<_>>().as_slice(), [column] if column.generated && column.default.is_none())
+}
+
+/// `get_by_pk`
+fn get_by_pk_fn(table: &Table, row: &Ident, key: &KeyParam, dialect: &Dialect, backend: RustBackend) -> TokenStream {
+    let sql = format!("SELECT {} FROM {} WHERE {}", column_list(table, dialect), dialect.quote_identifier(&table.name), key_condition(table, dialect, 1));
+    let params = &key.params;
+    let values = &key.values;
+    match backend {
+        RustBackend::Sqlx => {
+            let db = sqlx_database(dialect);
+            quote! {
+                /// Get a row by its primary key.
+                pub async fn get_by_pk<'e, E>(executor: E, #params) -> Result<Option<#row>, sqlx::Error>
+                where
+                    E: sqlx::Executor<'e, Database = #db>,
+                {
+                    sqlx::query_as::<_, #row>(#sql)
+                        #(.bind(#values))*
+                        .fetch_optional(executor)
+                        .await
+                }
+            }
+        }
+        RustBackend::TokioPostgres => quote! {
+            /// Get a row by its primary key.
+            pub async fn get_by_pk(client: &impl tokio_postgres::GenericClient, #params) -> Result<Option<#row>, tokio_postgres::Error> {
+                let row = client.query_opt(#sql, &[#(&#values),*]).await?;
+                row.as_ref().map(#row::from_row).transpose()
+            }
+        },
+        RustBackend::Rusqlite => quote! {
+            /// Get a row by its primary key.
+            pub fn get_by_pk(conn: &rusqlite::Connection, #params) -> rusqlite::Result<Option<#row>> {
+                OptionalExtension::optional(conn.query_row(#sql, rusqlite::params![#(#values),*], #row::from_row))
+            }
+        },
+    }
+}
+
+/// `update`: writes all the non-key columns of a row
+fn update_fn(table: &Table, row: &Ident, dialect: &Dialect, backend: RustBackend) -> TokenStream {
+    // Numbered placeholders start with the key, as in the patch structs;
+    // positional ones follow the order of the statement
+    let numbered = dialect.placeholder_prefix().is_some();
+    let key_count = table.primary_key.len();
+    let assignments: Vec<String> = table.non_key_columns()
+        .enumerate()
+        .map(|(i, c)| {
+            let index = if numbered { key_count + i + 1 } else { i + 1 };
+            format!("{} = {}", dialect.quote_identifier(&c.name), dialect.placeholder(index))
+        })
+        .collect();
+    let first_key_index = if numbered { 1 } else { assignments.len() + 1 };
+    let sql = format!(
+        "UPDATE {} SET {} WHERE {}",
+        dialect.quote_identifier(&table.name),
+        assignments.join(", "),
+        key_condition(table, dialect, first_key_index)
+    );
+    let key_fields: Vec<Ident> = table.primary_key_columns().map(super::langgen_rust::field_ident).collect();
+    let set_fields: Vec<Ident> = table.non_key_columns().map(super::langgen_rust::field_ident).collect();
+    let fields: Vec<&Ident> = if numbered {
+        key_fields.iter().chain(set_fields.iter()).collect()
+    } else {
+        set_fields.iter().chain(key_fields.iter()).collect()
+    };
+    match backend {
+        RustBackend::Sqlx => {
+            let db = sqlx_database(dialect);
+            quote! {
+                /// Update all the columns of a row, returning the number of affected rows.
+                pub async fn update<'e, E>(executor: E, row: &#row) -> Result<u64, sqlx::Error>
+                where
+                    E: sqlx::Executor<'e, Database = #db>,
+                {
+                    let result = sqlx::query(#sql)
+                        #(.bind(&row.#fields))*
+                        .execute(executor)
+                        .await?;
+                    Ok(result.rows_affected())
+                }
+            }
+        }
+        RustBackend::TokioPostgres => quote! {
+            /// Update all the columns of a row, returning the number of affected rows.
+            pub async fn update(client: &impl tokio_postgres::GenericClient, row: &#row) -> Result<u64, tokio_postgres::Error> {
+                client.execute(#sql, &[#(&row.#fields),*]).await
+            }
+        },
+        RustBackend::Rusqlite => quote! {
+            /// Update all the columns of a row, returning the number of affected rows.
+            pub fn update(conn: &rusqlite::Connection, row: &#row) -> rusqlite::Result<usize> {
+                conn.execute(#sql, rusqlite::params![#(row.#fields),*])
+            }
+        },
+    }
+}
+
+/// `delete`
+fn delete_fn(table: &Table, key: &KeyParam, dialect: &Dialect, backend: RustBackend) -> TokenStream {
+    let sql = format!("DELETE FROM {} WHERE {}", dialect.quote_identifier(&table.name), key_condition(table, dialect, 1));
+    let params = &key.params;
+    let values = &key.values;
+    match backend {
+        RustBackend::Sqlx => {
+            let db = sqlx_database(dialect);
+            quote! {
+                /// Delete a row by its primary key, returning the number of affected rows.
+                pub async fn delete<'e, E>(executor: E, #params) -> Result<u64, sqlx::Error>
+                where
+                    E: sqlx::Executor<'e, Database = #db>,
+                {
+                    let result = sqlx::query(#sql)
+                        #(.bind(#values))*
+                        .execute(executor)
+                        .await?;
+                    Ok(result.rows_affected())
+                }
+            }
+        }
+        RustBackend::TokioPostgres => quote! {
+            /// Delete a row by its primary key, returning the number of affected rows.
+            pub async fn delete(client: &impl tokio_postgres::GenericClient, #params) -> Result<u64, tokio_postgres::Error> {
+                client.execute(#sql, &[#(&#values),*]).await
+            }
+        },
+        RustBackend::Rusqlite => quote! {
+            /// Delete a row by its primary key, returning the number of affected rows.
+            pub fn delete(conn: &rusqlite::Connection, #params) -> rusqlite::Result<usize> {
+                conn.execute(#sql, rusqlite::params![#(#values),*])
+            }
+        },
+    }
+}
+
+/// `list`
+fn list_fn(table: &Table, row: &Ident, dialect: &Dialect, backend: RustBackend) -> TokenStream {
+    let sql = format!("SELECT {} FROM {}", column_list(table, dialect), dialect.quote_identifier(&table.name));
+    match backend {
+        RustBackend::Sqlx => {
+            let db = sqlx_database(dialect);
+            quote! {
+                /// List all the rows.
+                pub async fn list<'e, E>(executor: E) -> Result<Vec<#row>, sqlx::Error>
+                where
+                    E: sqlx::Executor<'e, Database = #db>,
+                {
+                    sqlx::query_as::<_, #row>(#sql).fetch_all(executor).await
+                }
+            }
+        }
+        RustBackend::TokioPostgres => quote! {
+            /// List all the rows.
+            pub async fn list(client: &impl tokio_postgres::GenericClient) -> Result<Vec<#row>, tokio_postgres::Error> {
+                let rows = client.query(#sql, &[]).await?;
+                rows.iter().map(#row::from_row).collect()
+            }
+        },
+        RustBackend::Rusqlite => quote! {
+            /// List all the rows.
+            pub fn list(conn: &rusqlite::Connection) -> rusqlite::Result<Vec<#row>> {
+                let mut stmt = conn.prepare(#sql)?;
+                let rows = stmt.query_map([], #row::from_row)?;
+                rows.collect()
+            }
+        },
+    }
+}
+
+/// Names of the functions of the row struct `impl`s besides the relation helpers
+pub(super) fn function_names(table: &Table) -> Vec<String> {
+    let mut names: Vec<String> = ["from_row", "insert", "get_by_pk", "update", "delete", "list"].map(String::from).to_vec();
+    names.extend(table.lookups().iter().map(lookup_name));
+    names.extend(table.columns.iter().filter(|c| c.has_default()).map(|c| format_ident!("default_{}", super::langgen_rust::field_ident(c)).to_string()));
+    names
+}
+
+/// `find_by_<columns>` for unique lookups, `list_by_<columns>` for the other indexes
+fn lookup_name(lookup: &Lookup) -> String {
+    let names: Vec<String> = lookup.columns.iter().map(|c| c.name.to_lowercase()).collect();
+    format!("{}_by_{}", if lookup.unique { "find" } else { "list" }, names.join("_and_"))
+}
+
+/// `find_by_<columns>` for unique lookups, `list_by_<columns>` for the other indexes
+fn lookup_fn(table: &Table, types: &TableTypes, lookup: &Lookup, dialect: &Dialect, backend: RustBackend) -> TokenStream {
+    let row = types.row;
+    let names: Vec<String> = lookup.columns.iter().map(|c| c.name.to_lowercase()).collect();
+    let fn_name = rust_ident(&lookup_name(lookup));
+    let doc = format!(
+        " {} by the {} `{}` index.",
+        if lookup.unique { "Find a row" } else { "List the rows" },
+        if lookup.unique { "unique" } else { "non-unique" },
+        names.join(", ")
+    );
+
+    let mut condition: Vec<String> = lookup.columns.iter()
+        .enumerate()
+        .map(|(i, column)| format!("{} = {}", dialect.quote_identifier(&column.name), dialect.placeholder(i + 1)))
+        .collect();
+    if let Some(predicate) = lookup.predicate {
+        condition.push(format!("({})", predicate));
+    }
+    let sql = format!("SELECT {} FROM {} WHERE {}", column_list(table, dialect), dialect.quote_identifier(&table.name), condition.join(" AND "));
+
+    let values: Vec<Ident> = lookup.columns.iter().map(|c| super::langgen_rust::field_ident(c)).collect();
+    let param_types: Vec<TokenStream> = lookup.columns.iter().map(|column| {
+        let rust_type = super::langgen_rust::column_type(column, &types.options.types, dialect);
+        if rust_type.to_token_stream().to_string() == "String" {
+            quote! { &str }
+        } else {
+            quote! { #rust_type }
+        }
+    }).collect();
+    let params = quote! { #(#values: #param_types),* };
+
+    match backend {
+        RustBackend::Sqlx => {
+            let db = sqlx_database(dialect);
+            let (return_type, fetch) = if lookup.unique {
+                (quote! { Option<#row> }, quote! { fetch_optional })
+            } else {
+                (quote! { Vec<#row> }, quote! { fetch_all })
+            };
+            quote! {
+                #[doc = #doc]
+                pub async fn #fn_name<'e, E>(executor: E, #params) -> Result<#return_type, sqlx::Error>
+                where
+                    E: sqlx::Executor<'e, Database = #db>,
+                {
+                    sqlx::query_as::<_, #row>(#sql)
+                        #(.bind(#values))*
+                        .#fetch(executor)
+                        .await
+                }
+            }
+        }
+        RustBackend::TokioPostgres => {
+            if lookup.unique {
+                quote! {
+                    #[doc = #doc]
+                    pub async fn #fn_name(client: &impl tokio_postgres::GenericClient, #params) -> Result<Option<#row>, tokio_postgres::Error> {
+                        let row = client.query_opt(#sql, &[#(&#values),*]).await?;
+                        row.as_ref().map(#row::from_row).transpose()
+                    }
+                }
+            } else {
+                quote! {
+                    #[doc = #doc]
+                    pub async fn #fn_name(client: &impl tokio_postgres::GenericClient, #params) -> Result<Vec<#row>, tokio_postgres::Error> {
+                        let rows = client.query(#sql, &[#(&#values),*]).await?;
+                        rows.iter().map(#row::from_row).collect()
+                    }
+                }
+            }
+        }
+        RustBackend::Rusqlite => {
+            if lookup.unique {
+                quote! {
+                    #[doc = #doc]
+                    pub fn #fn_name(conn: &rusqlite::Connection, #params) -> rusqlite::Result<Option<#row>> {
+                        OptionalExtension::optional(conn.query_row(#sql, rusqlite::params![#(#values),*], #row::from_row))
+                    }
+                }
+            } else {
+                quote! {
+                    #[doc = #doc]
+                    pub fn #fn_name(conn: &rusqlite::Connection, #params) -> rusqlite::Result<Vec<#row>> {
+                        let mut stmt = conn.prepare(#sql)?;
+                        let rows = stmt.query_map(rusqlite::params![#(#values),*], #row::from_row)?;
+                        rows.collect()
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// `"key1" = $1 AND "key2" = $2` condition on the primary key
+fn key_condition(table: &Table, dialect: &Dialect, first_index: usize) -> String {
+    table.primary_key_columns()
+        .enumerate()
+        .map(|(i, column)| format!("{} = {}", dialect.quote_identifier(&column.name), dialect.placeholder(first_index + i)))
+        .collect::<Vec<_>>()
+        .join(" AND ")
+}
+
+/// Comma separated list of all the columns, quoted for the dialect
+pub(super) fn column_list(table: &Table, dialect: &Dialect) -> String {
+    table.columns.iter().map(|c| dialect.quote_identifier(&c.name)).collect::<Vec<_>>().join(", ")
+}
+
+/// The sqlx database type for a dialect
+pub(super) fn sqlx_database(dialect: &Dialect) -> TokenStream {
+    match dialect {
+        Dialect::Generic | Dialect::PostgreSQL => quote! { sqlx::Postgres },
+        Dialect::MySQL => quote! { sqlx::MySql },
+        Dialect::SQLite => quote! { sqlx::Sqlite },
+    }
+}
@@ -0,0 +1,317 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{Ident, Item};
+
+use super::langgen_rust::{field_ident, rust_ident};
+use super::langgen_rust_crud::{column_list, function_names, sqlx_database, RustBackend, TableTypes};
+use super::{Dialect, Relation, RelationKind, Schema, Table};
+
+/// Relation helpers of a table: the functions to add to the row struct `impl`,
+/// and the items of the typed join queries
+pub(super) fn relation_items(schema: &Schema, table: &Table, types: &TableTypes, dialect: &Dialect, backend: RustBackend) -> (Vec<TokenStream>, Vec<Item>) {
+    let naming = types.options.naming;
+    let mut functions = Vec::new();
+    let mut items = Vec::new();
+    let relations = schema.relations_of(&table.name);
+    let names = method_names(&relations, &function_names(table));
+    for (relation, name) in relations.iter().zip(names) {
+        let Some(related) = schema.table(&relation.related_table) else { continue };
+        let Some(function) = relation_fn(table, related, relation, &name, naming.type_name(&related.name), dialect, backend) else { continue };
+        functions.push(function);
+        if relation.kind == RelationKind::ManyToOne {
+            let (function, join_items) = join_query(table, related, relation, types, &name, dialect, backend);
+            functions.push(function);
+            items.extend(join_items);
+        }
+    }
+    (functions, items)
+}
+
+/// Method names for the relations of a table, disambiguated when they collide with each other
+/// or with the other functions of the row struct, e.g. `list` for a `list_id` column becomes `list_row`
+fn method_names(relations: &[Relation], reserved: &[String]) -> Vec<String> {
+    let base_names: Vec<String> = relations.iter().map(|relation| match relation.kind {
+        RelationKind::ManyToOne => match relation.columns.as_slice() {
+            [column] if column.len() > 3 && column.to_lowercase().ends_with("_id") => column[..column.len() - 3].to_lowercase(),
+            _ => relation.related_table.to_lowercase(),
+        },
+        RelationKind::OneToMany | RelationKind::ManyToMany => pluralize(&relation.related_table.to_lowercase()),
+    }).collect();
+
+    let names: Vec<String> = relations.iter().zip(&base_names).map(|(relation, name)| {
+        if base_names.iter().filter(|n| *n == name).count() == 1 {
+            return name.clone();
+        }
+        match (&relation.kind, &relation.join) {
+            (RelationKind::ManyToMany, Some(join)) => format!("{}_via_{}", name, join.name.to_lowercase()),
+            (RelationKind::OneToMany, _) => format!("{}_by_{}", name, column_names(&relation.related_columns)),
+            _ => format!("{}_by_{}", name, column_names(&relation.columns)),
+        }
+    }).collect();
+
+    // Many-to-many relations of a table with itself go through the same join table twice
+    let names: Vec<String> = relations.iter().zip(&names).map(|(relation, name)| {
+        match &relation.join {
+            Some(join) if names.iter().filter(|n| *n == name).count() > 1 => {
+                format!("{}_by_{}", name, column_names(&join.columns))
+            }
+            _ => name.clone(),
+        }
+    }).collect();
+
+    let mut used = reserved.to_vec();
+    relations.iter().zip(names).map(|(relation, mut name)| {
+        if used.contains(&name) {
+            name.push_str(if relation.kind == RelationKind::ManyToOne { "_row" } else { "_rows" });
+        }
+        while used.contains(&name) {
+            name.push('_');
+        }
+        used.push(name.clone());
+        name
+    }).collect()
+}
+
+/// Function returning the related rows
+fn relation_fn(table: &Table, related: &Table, relation: &Relation, name: &str, related_row: Ident, dialect: &Dialect, backend: RustBackend) -> Option<TokenStream> {
+    if relation.columns.is_empty() || relation.columns.len() != relation.related_columns.len() {
+        return None;
+    }
+    let fn_name = rust_ident(name);
+    let values = relation.columns.iter()
+        .map(|name| table.column(name).map(field_ident))
+        .collect::<Option<Vec<Ident>>>()?;
+
+    let quote = |name: &str| dialect.quote_identifier(name);
+    let (sql, doc) = match &relation.join {
+        None => {
+            let condition: Vec<String> = relation.related_columns.iter()
+                .enumerate()
+                .map(|(i, column)| format!("{} = {}", quote(column), dialect.placeholder(i + 1)))
+                .collect();
+            let sql = format!("SELECT {} FROM {} WHERE {}", column_list(related, dialect), quote(&related.name), condition.join(" AND "));
+            let doc = match relation.kind {
+                RelationKind::ManyToOne => format!(" Get the `{}` row referenced by `{}`.", related.name, relation.columns.join(", ")),
+                _ => format!(" List the `{}` rows referencing this row through `{}`.", related.name, relation.related_columns.join(", ")),
+            };
+            (sql, doc)
+        }
+        Some(join) => {
+            let (related_name, join_name) = (quote(&related.name), quote(&join.name));
+            let columns: Vec<String> = related.columns.iter().map(|c| format!("{}.{}", related_name, quote(&c.name))).collect();
+            let on: Vec<String> = join.related_columns.iter()
+                .zip(&relation.related_columns)
+                .map(|(join_column, column)| format!("{}.{} = {}.{}", join_name, quote(join_column), related_name, quote(column)))
+                .collect();
+            let condition: Vec<String> = join.columns.iter()
+                .enumerate()
+                .map(|(i, column)| format!("{}.{} = {}", join_name, quote(column), dialect.placeholder(i + 1)))
+                .collect();
+            let sql = format!(
+                "SELECT {} FROM {} JOIN {} ON {} WHERE {}",
+                columns.join(", "), related_name, join_name, on.join(" AND "), condition.join(" AND ")
+            );
+            let doc = format!(" List the `{}` rows linked to this row through `{}`.", related.name, join.name);
+            (sql, doc)
+        }
+    };
+
+    let one = relation.kind == RelationKind::ManyToOne;
+    Some(match backend {
+        RustBackend::Sqlx => {
+            let db = sqlx_database(dialect);
+            let (return_type, fetch) = if one {
+                (quote! { Option<#related_row> }, quote! { fetch_optional })
+            } else {
+                (quote! { Vec<#related_row> }, quote! { fetch_all })
+            };
+            quote! {
+                #[doc = #doc]
+                pub async fn #fn_name<'e, E>(&self, executor: E) -> Result<#return_type, sqlx::Error>
+                where
+                    E: sqlx::Executor<'e, Database = #db>,
+                {
+                    sqlx::query_as::<_, #related_row>(#sql)
+                        #(.bind(&self.#values))*
+                        .#fetch(executor)
+                        .await
+                }
+            }
+        }
+        RustBackend::TokioPostgres => {
+            if one {
+                quote! {
+                    #[doc = #doc]
+                    pub async fn #fn_name(&self, client: &impl tokio_postgres::GenericClient) -> Result<Option<#related_row>, tokio_postgres::Error> {
+                        let row = client.query_opt(#sql, &[#(&self.#values),*]).await?;
+                        row.as_ref().map(#related_row::from_row).transpose()
+                    }
+                }
+            } else {
+                quote! {
+                    #[doc = #doc]
+                    pub async fn #fn_name(&self, client: &impl tokio_postgres::GenericClient) -> Result<Vec<#related_row>, tokio_postgres::Error> {
+                        let rows = client.query(#sql, &[#(&self.#values),*]).await?;
+                        rows.iter().map(#related_row::from_row).collect()
+                    }
+                }
+            }
+        }
+        RustBackend::Rusqlite => {
+            if one {
+                quote! {
+                    #[doc = #doc]
+                    pub fn #fn_name(&self, conn: &rusqlite::Connection) -> rusqlite::Result<Option<#related_row>> {
+                        OptionalExtension::optional(conn.query_row(#sql, rusqlite::params![#(self.#values),*], #related_row::from_row))
+                    }
+                }
+            } else {
+                quote! {
+                    #[doc = #doc]
+                    pub fn #fn_name(&self, conn: &rusqlite::Connection) -> rusqlite::Result<Vec<#related_row>> {
+                        let mut stmt = conn.prepare(#sql)?;
+                        let rows = stmt.query_map(rusqlite::params![#(self.#values),*], #related_row::from_row)?;
+                        rows.collect()
+                    }
+                }
+            }
+        }
+    })
+}
+
+/// Typed join query of a many-to-one relation: a struct pairing each row with the
+/// referenced one, and the `list_with_<relation>` function returning them
+fn join_query(table: &Table, related: &Table, relation: &Relation, types: &TableTypes, name: &str, dialect: &Dialect, backend: RustBackend) -> (TokenStream, Vec<Item>) {
+    let row = types.row;
+    let related_row = types.options.naming.type_name(&related.name);
+    let join_struct = format_ident!("{}With{}", row, types.options.naming.type_name(name));
+    let fn_name = rust_ident(&format!("list_with_{}", name));
+    let row_field = rust_ident(&table.name.to_lowercase());
+    let related_field = if table.name.eq_ignore_ascii_case(name) {
+        format_ident!("related")
+    } else {
+        rust_ident(name)
+    };
+
+    // Tables are aliased as t0 and t1, so that a table can be joined with itself
+    let quote = |name: &str| dialect.quote_identifier(name);
+    let alias = |prefix: &str, name: &str| quote(&format!("{}{}", prefix, name.to_lowercase()));
+    let mut columns: Vec<String> = table.columns.iter().map(|c| format!("t0.{} AS {}", quote(&c.name), alias("t0_", &c.name))).collect();
+    columns.extend(related.columns.iter().map(|c| format!("t1.{} AS {}", quote(&c.name), alias("t1_", &c.name))));
+    let on: Vec<String> = relation.related_columns.iter()
+        .zip(&relation.columns)
+        .map(|(related_column, column)| format!("t1.{} = t0.{}", quote(related_column), quote(column)))
+        .collect();
+    let sql = format!("SELECT {} FROM {} AS t0 JOIN {} AS t1 ON {}", columns.join(", "), quote(&table.name), quote(&related.name), on.join(" AND "));
+    let doc = format!(" List the rows together with the `{}` row referenced by `{}`.", related.name, relation.columns.join(", "));
+
+    let mut items: Vec<Item> = vec![syn::parse_quote! {
+        /// Row joined with the row it references
+        pub struct #join_struct {
+            pub #row_field: #row,
+            pub #related_field: #related_row,
+        }
+    }];
+    let row_init = struct_init(table, row, "t0_", backend);
+    let related_init = struct_init(related, &related_row, "t1_", backend);
+
+    let function = match backend {
+        RustBackend::Sqlx => {
+            let db = sqlx_database(dialect);
+            let db_row = match dialect {
+                Dialect::Generic | Dialect::PostgreSQL => quote! { sqlx::postgres::PgRow },
+                Dialect::MySQL => quote! { sqlx::mysql::MySqlRow },
+                Dialect::SQLite => quote! { sqlx::sqlite::SqliteRow },
+            };
+            items.push(syn::parse_quote! {
+                impl<'r> sqlx::FromRow<'r, #db_row> for #join_struct {
+                    fn from_row(row: &'r #db_row) -> Result<Self, sqlx::Error> {
+                        use sqlx::Row;
+                        Ok(Self { #row_field: #row_init, #related_field: #related_init })
+                    }
+                }
+            });
+            quote! {
+                #[doc = #doc]
+                pub async fn #fn_name<'e, E>(executor: E) -> Result<Vec<#join_struct>, sqlx::Error>
+                where
+                    E: sqlx::Executor<'e, Database = #db>,
+                {
+                    sqlx::query_as::<_, #join_struct>(#sql).fetch_all(executor).await
+                }
+            }
+        }
+        RustBackend::TokioPostgres => {
+            items.push(syn::parse_quote! {
+                impl #join_struct {
+                    /// Build the struct from a `tokio_postgres::Row`.
+                    pub fn from_row(row: &tokio_postgres::Row) -> Result<Self, tokio_postgres::Error> {
+                        Ok(Self { #row_field: #row_init, #related_field: #related_init })
+                    }
+                }
+            });
+            quote! {
+                #[doc = #doc]
+                pub async fn #fn_name(client: &impl tokio_postgres::GenericClient) -> Result<Vec<#join_struct>, tokio_postgres::Error> {
+                    let rows = client.query(#sql, &[]).await?;
+                    rows.iter().map(#join_struct::from_row).collect()
+                }
+            }
+        }
+        RustBackend::Rusqlite => {
+            items.push(syn::parse_quote! {
+                impl #join_struct {
+                    /// Build the struct from a `rusqlite::Row`.
+                    pub fn from_row(row: &rusqlite::Row) -> rusqlite::Result<Self> {
+                        Ok(Self { #row_field: #row_init, #related_field: #related_init })
+                    }
+                }
+            });
+            quote! {
+                #[doc = #doc]
+                pub fn #fn_name(conn: &rusqlite::Connection) -> rusqlite::Result<Vec<#join_struct>> {
+                    let mut stmt = conn.prepare(#sql)?;
+                    let rows = stmt.query_map([], #join_struct::from_row)?;
+                    rows.collect()
+                }
+            }
+        }
+    };
+    (function, items)
+}
+
+/// Struct expression reading the columns from prefixed aliases of a backend row
+fn struct_init(table: &Table, row: &Ident, prefix: &str, backend: RustBackend) -> TokenStream {
+    let fields: Vec<Ident> = table.columns.iter().map(field_ident).collect();
+    let aliases: Vec<String> = table.columns.iter().map(|c| format!("{}{}", prefix, c.name.to_lowercase())).collect();
+    match backend {
+        RustBackend::Sqlx => quote! { #row { #(#fields: row.try_get(#aliases)?),* } },
+        RustBackend::TokioPostgres => quote! { #row { #(#fields: row.try_get(#aliases)?),* } },
+        RustBackend::Rusqlite => quote! { #row { #(#fields: row.get(#aliases)?),* } },
+    }
+}
+
+/// Column names joined for a method name, without the `_id` suffixes
+fn column_names(columns: &[String]) -> String {
+    columns.iter()
+        .map(|c| {
+            let c = c.to_lowercase();
+            match c.strip_suffix("_id") {
+                Some(stripped) if !stripped.is_empty() => stripped.to_string(),
+                _ => c,
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("_and_")
+}
+
+/// Naive English plural of a table name
+fn pluralize(name: &str) -> String {
+    if name.ends_with('s') || name.ends_with('x') || name.ends_with("ch") || name.ends_with("sh") {
+        format!("{}es", name)
+    } else if name.ends_with('y') && !name.ends_with("ay") && !name.ends_with("ey") && !name.ends_with("oy") && !name.ends_with("uy") {
+        format!("{}ies", &name[..name.len() - 1])
+    } else {
+        format!("{}s", name)
+    }
+}
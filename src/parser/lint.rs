@@ -0,0 +1,642 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use serde::{de, Deserialize, Deserializer, Serialize};
+use sqlparser::ast::{AlterTableOperation, CharacterLength, ColumnOption, DataType, ExactNumberInfo, Statement};
+use sqlparser::parser::Parser;
+use sqlparser::tokenizer::{Token, Tokenizer, Whitespace};
+
+use super::schema::{is_serial_type, object_name};
+use super::{Dialect, ParserError, Schema, SchemaChange, Table};
+
+/// Prefix of the SQL comments suppressing lints, e.g. `-- noorm:allow(drop-column)`
+const ALLOW_ANNOTATION: &str = "noorm:allow(";
+/// Prefix of the SQL comments suppressing lints in a whole file, e.g. `-- noorm:allow-file(rename)`
+const ALLOW_FILE_ANNOTATION: &str = "noorm:allow-file(";
+
+/// The lint rules with their default severity: the migration rules, then the schema rules
+pub const RULES: &[Rule] = &[
+    Rule { name: "drop-table", severity: Severity::Error, description: "Drops a table and its rows" },
+    Rule { name: "drop-column", severity: Severity::Error, description: "Drops a column and its values" },
+    Rule { name: "narrow-type", severity: Severity::Error, description: "Changes a column to a type the existing values may not fit" },
+    Rule { name: "add-not-null-column", severity: Severity::Error, description: "Adds a NOT NULL column without a default, failing on a table with rows" },
+    Rule { name: "data-loss", severity: Severity::Error, description: "Deletes every row of a table" },
+    Rule { name: "change-type", severity: Severity::Warning, description: "Changes the type of a column, rewriting the table" },
+    Rule { name: "set-not-null", severity: Severity::Warning, description: "Makes a column NOT NULL, scanning the table" },
+    Rule { name: "table-rewrite", severity: Severity::Warning, description: "Rewrites every row of a table" },
+    Rule { name: "rename", severity: Severity::Warning, description: "Renames a table or column used by the deployed code" },
+    Rule { name: "add-unique", severity: Severity::Warning, description: "Adds a unique or primary key to a table with rows" },
+    Rule { name: "add-foreign-key", severity: Severity::Warning, description: "Adds a foreign key to a table with rows" },
+    Rule { name: "create-index-lock", severity: Severity::Warning, description: "Creates a PostgreSQL index without CONCURRENTLY" },
+    Rule { name: "missing-primary-key", severity: Severity::Warning, description: "Table without a primary key" },
+    Rule { name: "unindexed-foreign-key", severity: Severity::Warning, description: "Foreign key columns without an index" },
+    Rule { name: "naming", severity: Severity::Warning, description: "Table or column name not following the naming convention" },
+    Rule { name: "nullable-boolean", severity: Severity::Warning, description: "Boolean column accepting NULL" },
+    Rule { name: "varchar-without-length", severity: Severity::Warning, description: "VARCHAR column without a length" },
+    Rule { name: "reserved-word", severity: Severity::Warning, description: "Table or column named after an SQL reserved word" },
+];
+
+/// SQL words reserved in the supported dialects, needing quotes as identifiers
+const RESERVED_WORDS: &[&str] = &[
+    "ALL", "AND", "ANY", "AS", "ASC", "BETWEEN", "BY", "CASE", "CHECK", "COLUMN", "CONSTRAINT", "CREATE", "CROSS",
+    "CURRENT_DATE", "CURRENT_TIME", "CURRENT_TIMESTAMP", "CURRENT_USER", "DEFAULT", "DELETE", "DESC", "DISTINCT", "DROP",
+    "ELSE", "END", "EXCEPT", "EXISTS", "FALSE", "FETCH", "FOR", "FOREIGN", "FROM", "FULL", "GRANT", "GROUP", "HAVING", "IN",
+    "INDEX", "INNER", "INSERT", "INTERSECT", "INTO", "IS", "JOIN", "KEY", "LEFT", "LIKE", "LIMIT", "NOT", "NULL", "OFFSET",
+    "ON", "OR", "ORDER", "OUTER", "PRIMARY", "REFERENCES", "RIGHT", "ROW", "SELECT", "SET", "TABLE", "THEN", "TO", "TRUE",
+    "UNION", "UNIQUE", "UPDATE", "USER", "USING", "VALUES", "WHEN", "WHERE", "WITH",
+];
+
+/// How serious a lint is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// Risky, to be reviewed
+    Warning,
+    /// Loses data or fails on existing rows
+    Error,
+}
+
+/// A lint rule.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rule {
+    /// Name of the rule, e.g. `drop-column`
+    pub name: &'static str,
+    /// Default severity
+    pub severity: Severity,
+    /// What the rule reports
+    pub description: &'static str,
+}
+
+/// A problem found in a migration or in the schema.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Lint {
+    /// Name of the rule, e.g. `drop-column`, used to suppress it
+    pub rule: &'static str,
+    /// Severity of the rule
+    pub severity: Severity,
+    /// Description of the problem
+    pub message: String,
+    /// Name of the migration, e.g. its file, or of the source creating the table
+    pub source: String,
+    /// Line of the statement, starting from 1, if known
+    pub line: Option<usize>,
+}
+
+/// Lint configuration, the `[lint]` table of the configuration file:
+///
+/// ```toml
+/// [lint]
+/// naming = "camelCase"
+/// rules = { missing-primary-key = "error", reserved-word = "off" }
+/// ```
+#[derive(Debug, Default, Clone, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct LintConfig {
+    /// Naming convention of the tables and columns
+    #[serde(default)]
+    pub naming: NamingStyle,
+    /// Level of the rules, overriding their default severity
+    #[serde(default, deserialize_with = "deserialize_rules")]
+    pub rules: BTreeMap<String, RuleLevel>,
+}
+
+/// Naming convention checked by the `naming` rule.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum NamingStyle {
+    /// `created_at`
+    #[default]
+    #[serde(rename = "snake_case")]
+    SnakeCase,
+    /// `createdAt`
+    #[serde(rename = "camelCase")]
+    CamelCase,
+}
+
+/// Level of a rule in the configuration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RuleLevel {
+    /// Not reported
+    Off,
+    /// Reported as a warning
+    Warning,
+    /// Reported as an error
+    Error,
+}
+
+impl LintConfig {
+    /// Apply the configured levels to lints, leaving out the rules turned off.
+    pub fn apply(&self, lints: Vec<Lint>) -> Vec<Lint> {
+        lints.into_iter().filter_map(|mut lint| {
+            match self.rules.get(lint.rule) {
+                Some(RuleLevel::Off) => return None,
+                Some(RuleLevel::Warning) => lint.severity = Severity::Warning,
+                Some(RuleLevel::Error) => lint.severity = Severity::Error,
+                None => {}
+            }
+            Some(lint)
+        }).collect()
+    }
+}
+
+/// Check that the configured rules exist
+fn deserialize_rules<'de, D: Deserializer<'de>>(deserializer: D) -> Result<BTreeMap<String, RuleLevel>, D::Error> {
+    let rules = BTreeMap::<String, RuleLevel>::deserialize(deserializer)?;
+    if let Some(name) = rules.keys().find(|name| !RULES.iter().any(|rule| rule.name == name.as_str())) {
+        let names: Vec<&str> = RULES.iter().map(|rule| rule.name).collect();
+        return Err(de::Error::custom(format!("unknown lint rule `{}`, expected one of {}", name, names.join(", "))));
+    }
+    Ok(rules)
+}
+
+/// Default severity of a rule
+fn rule_severity(name: &str) -> Severity {
+    RULES.iter().find(|rule| rule.name == name).map_or(Severity::Warning, |rule| rule.severity)
+}
+
+/// A rule and message, before being located and checked for suppression
+type Finding = (&'static str, String);
+
+/// Line and suppressed rules of a statement
+#[derive(Default)]
+struct StatementInfo {
+    line: usize,
+    allowed: Vec<String>,
+}
+
+impl Schema {
+    /// Lint a migration against the schema, then apply it to the schema.
+    ///
+    /// The statements are checked for destructive changes and for the ones failing or locking
+    /// on tables with rows: dropped tables and columns, narrowed types, columns added
+    /// `NOT NULL` without a default, table rewrites and the like. Changes to tables created by the same
+    /// migration are not reported. A comment `-- noorm:allow(rule, ...)` before or inside
+    /// a statement, or after it on the same line, suppresses the rules for the statement;
+    /// `-- noorm:allow-file(rule, ...)` suppresses them for the whole migration, and `all`
+    /// suppresses every rule.
+    ///
+    /// # Errors
+    ///
+    /// * `ParserError::SqlFile` if the migration fails to parse
+    /// * `ParserError::DuplicateTable` if a table is created twice
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use noorm::prelude::*;
+    /// use noorm::parser::{Schema, Severity};
+    ///
+    /// let mut schema = Schema::from_sql("CREATE TABLE person ( id INTEGER, name TEXT, age INTEGER NULL )", &Dialect::PostgreSQL).unwrap();
+    /// let sql = "ALTER TABLE person DROP COLUMN name;\nALTER TABLE person DROP COLUMN age; -- noorm:allow(drop-column)";
+    /// let lints = schema.lint_migration("002_drop.sql", sql, &Dialect::PostgreSQL).unwrap();
+    /// assert_eq!(lints.len(), 1);
+    /// assert_eq!(lints[0].severity, Severity::Error);
+    /// assert_eq!(lints[0].to_string(), "002_drop.sql:1: error[drop-column]: drops column person.name and its values");
+    /// assert!(schema.tables[0].column("name").is_none());
+    /// ```
+    pub fn lint_migration(&mut self, source: &str, sql: &str, dialect: &Dialect) -> Result<Vec<Lint>, ParserError> {
+        let statements = Parser::parse_sql(dialect.sqlparser_dialect().as_ref(), sql)
+            .map_err(|error| ParserError::SqlFile { path: source.into(), source: error })?;
+        let (infos, file_allowed) = statement_infos(sql, dialect);
+        let mut lints = Vec::new();
+        let mut created: Vec<String> = Vec::new();
+        // Tables dropped to be replaced by a renamed one, with the schema before the drop
+        let mut rebuilds: Vec<(String, Schema)> = Vec::new();
+        for (index, statement) in statements.iter().enumerate() {
+            let before = self.clone();
+            self.add_statements(vec![statement.clone()], sql, Some(source), dialect)?;
+            self.resolve_foreign_keys();
+
+            let is_created = |name: &str| created.iter().any(|c| c.eq_ignore_ascii_case(name));
+            let mut findings = Vec::new();
+            let mut new_tables = Vec::new();
+            for change in before.diff(self).changes {
+                let rebuild = match &change {
+                    SchemaChange::RenameTable { to, .. } => rebuilds.iter().position(|(name, _)| name.eq_ignore_ascii_case(to)),
+                    _ => None,
+                };
+                if let Some(position) = rebuild {
+                    let (name, old) = rebuilds.remove(position);
+                    findings.push(("table-rewrite", format!("rebuilds table {}, copying every row", name)));
+                    for change in old.diff(self).changes.iter().filter(|c| c.table().eq_ignore_ascii_case(&name)) {
+                        findings.extend(change_lints(change));
+                    }
+                    continue;
+                }
+                match &change {
+                    SchemaChange::CreateTable(table) => new_tables.push(table.name.clone()),
+                    SchemaChange::RenameTable { from, to } if is_created(from) => new_tables.push(to.clone()),
+                    SchemaChange::DropTable(table) if !table.is_view() && is_replaced(&statements[index + 1..], &table.name) => {
+                        rebuilds.push((table.name.clone(), before.clone()));
+                    }
+                    change if !is_created(change.table()) => findings.extend(change_lints(change)),
+                    _ => {}
+                }
+            }
+            findings.extend(statement_lints(statement, &created, dialect));
+            created.extend(new_tables);
+
+            let info = infos.get(index);
+            let is_allowed = |rule: &str| {
+                file_allowed.iter().chain(info.iter().flat_map(|info| &info.allowed))
+                    .any(|allowed| allowed == rule || allowed == "all")
+            };
+            lints.extend(findings.into_iter().filter(|(rule, _)| !is_allowed(rule)).map(|(rule, message)| Lint {
+                rule,
+                severity: rule_severity(rule),
+                message,
+                source: source.to_string(),
+                line: Some(info.map_or(1, |info| info.line)),
+            }));
+        }
+        Ok(lints)
+    }
+}
+
+impl Schema {
+    /// Lint the design of the schema, with the configured naming convention and rule levels.
+    ///
+    /// Tables are checked for a primary key and for indexes on their foreign keys, and
+    /// tables and columns for their names, nullable booleans and `VARCHAR`s without a length.
+    /// The lints are located at the source creating each table, if any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use noorm::prelude::*;
+    /// use noorm::parser::{LintConfig, Schema};
+    ///
+    /// let schema = Schema::from_sql("CREATE TABLE person ( id INTEGER, active BOOLEAN NULL )", &Dialect::Generic).unwrap();
+    /// let rules: Vec<&str> = schema.lint(&LintConfig::default()).iter().map(|lint| lint.rule).collect();
+    /// assert_eq!(rules, vec!["missing-primary-key", "nullable-boolean"]);
+    /// ```
+    pub fn lint(&self, config: &LintConfig) -> Vec<Lint> {
+        let mut lints = Vec::new();
+        for table in &self.tables {
+            let mut findings = Vec::new();
+            findings.extend(name_lints("table", &table.name, config.naming));
+            if !table.is_view() {
+                findings.extend(table_lints(table));
+                for column in &table.columns {
+                    let name = format!("{}.{}", table.name, column.name);
+                    findings.extend(name_lints("column", &column.name, config.naming));
+                    match &column.data_type {
+                        DataType::Bool | DataType::Boolean if column.nullable => {
+                            findings.push(("nullable-boolean", format!("{} is a nullable boolean, with three states", name)));
+                        }
+                        DataType::Varchar(None) | DataType::CharacterVarying(None) | DataType::CharVarying(None) | DataType::Nvarchar(None) => {
+                            findings.push(("varchar-without-length", format!("{} is a {} without a length", name, column.data_type)));
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            let source = table.source.clone().unwrap_or_else(|| "schema".to_string());
+            lints.extend(findings.into_iter().map(|(rule, message)| Lint {
+                rule,
+                severity: rule_severity(rule),
+                message,
+                source: source.clone(),
+                line: None,
+            }));
+        }
+        config.apply(lints)
+    }
+}
+
+/// Lints of the keys of a table
+fn table_lints(table: &Table) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    if table.primary_key.is_empty() {
+        findings.push(("missing-primary-key", format!("table {} has no primary key", table.name)));
+    }
+    let indexed = std::iter::once(&table.primary_key)
+        .chain(&table.unique_keys)
+        .chain(table.indexes.iter().map(|index| &index.columns));
+    let indexed: Vec<&Vec<String>> = indexed.collect();
+    for foreign_key in &table.foreign_keys {
+        let is_prefix = |columns: &&Vec<String>| {
+            columns.len() >= foreign_key.columns.len()
+                && foreign_key.columns.iter().zip(columns.iter()).all(|(a, b)| a.eq_ignore_ascii_case(b))
+        };
+        if !indexed.iter().any(is_prefix) {
+            findings.push((
+                "unindexed-foreign-key",
+                format!(
+                    "foreign key {}({}) -> {} has no index, slowing the joins and the deletes of {}",
+                    table.name, foreign_key.columns.join(", "), foreign_key.foreign_table, foreign_key.foreign_table
+                ),
+            ));
+        }
+    }
+    findings
+}
+
+/// Lints of a table or column name
+fn name_lints(kind: &str, name: &str, naming: NamingStyle) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    let follows = match naming {
+        NamingStyle::SnakeCase => name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_'),
+        NamingStyle::CamelCase => name.starts_with(|c: char| c.is_ascii_lowercase()) && name.chars().all(|c| c.is_ascii_alphanumeric()),
+    };
+    if !follows {
+        let style = match naming {
+            NamingStyle::SnakeCase => "snake_case",
+            NamingStyle::CamelCase => "camelCase",
+        };
+        findings.push(("naming", format!("{} {} is not {}", kind, name, style)));
+    }
+    if RESERVED_WORDS.iter().any(|word| word.eq_ignore_ascii_case(name)) {
+        findings.push(("reserved-word", format!("{} {} is a reserved word, to be quoted in every query", kind, name)));
+    }
+    findings
+}
+
+/// Lints of a change to a table existing before the migration
+fn change_lints(change: &SchemaChange) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    match change {
+        SchemaChange::DropTable(table) if !table.is_view() => {
+            findings.push(("drop-table", format!("drops table {} and all its rows", table.name)));
+        }
+        SchemaChange::DropColumn { table, column } => {
+            findings.push(("drop-column", format!("drops column {}.{} and its values", table, column)));
+        }
+        SchemaChange::RenameTable { from, to } => {
+            findings.push(("rename", format!("renames table {} to {}, breaking the code using the old name", from, to)));
+        }
+        SchemaChange::RenameColumn { table, from, to } => {
+            findings.push(("rename", format!("renames column {}.{} to {}, breaking the code using the old name", table, from, to)));
+        }
+        SchemaChange::AddColumn { table, column } if column.generated || is_serial_type(&column.data_type) => {
+            findings.push(("table-rewrite", format!("fills {}.{} in every row, rewriting the table", table, column.name)));
+        }
+        SchemaChange::AlterColumn { table, from, to } => {
+            let name = format!("{}.{}", table, to.name);
+            match type_change(&from.data_type, &to.data_type) {
+                TypeChange::Same | TypeChange::Widens => {}
+                TypeChange::Rewrites => findings.push((
+                    "change-type",
+                    format!("changes {} from {} to {}, rewriting the table", name, from.data_type, to.data_type),
+                )),
+                TypeChange::Narrows => findings.push((
+                    "narrow-type",
+                    format!("narrows {} from {} to {}, failing on the values that do not fit", name, from.data_type, to.data_type),
+                )),
+            }
+            if from.nullable && !to.nullable {
+                findings.push(("set-not-null", format!("makes {} NOT NULL, scanning the table and failing on NULLs", name)));
+            }
+        }
+        SchemaChange::SetPrimaryKey { table, to, .. } if !to.is_empty() => findings.push((
+            "add-unique",
+            format!("sets the primary key of {} to ({}), failing on duplicates and locking the table while indexing", table, to.join(", ")),
+        )),
+        SchemaChange::AddUnique { table, columns } => findings.push((
+            "add-unique",
+            format!("adds a unique key on {}({}), failing on duplicates and locking the table while indexing", table, columns.join(", ")),
+        )),
+        SchemaChange::AddForeignKey { table, foreign_key } => findings.push((
+            "add-foreign-key",
+            format!("adds a foreign key {}({}) -> {}, checking every row while locking the table", table, foreign_key.columns.join(", "), foreign_key.foreign_table),
+        )),
+        _ => {}
+    }
+    findings
+}
+
+/// Lints of a statement itself, besides the changes to the schema
+fn statement_lints(statement: &Statement, created: &[String], dialect: &Dialect) -> Vec<Finding> {
+    let is_created = |table: &str| created.iter().any(|c| c.eq_ignore_ascii_case(table));
+    let mut findings = Vec::new();
+    match statement {
+        Statement::AlterTable { name, operations, .. } if !is_created(&object_name(name)) => {
+            for operation in operations {
+                let AlterTableOperation::AddColumn { column_def, .. } = operation else { continue };
+                let is_not_null = column_def.options.iter().any(|option| {
+                    matches!(option.option, ColumnOption::NotNull | ColumnOption::Unique { is_primary: true, .. })
+                });
+                let is_filled = is_serial_type(&column_def.data_type) || column_def.options.iter().any(|option| {
+                    matches!(option.option, ColumnOption::Default(_) | ColumnOption::Generated { .. } | ColumnOption::DialectSpecific(_))
+                });
+                if is_not_null && !is_filled {
+                    findings.push((
+                        "add-not-null-column",
+                        format!("adds {}.{} NOT NULL without a default, failing on a table with rows", object_name(name), column_def.name),
+                    ));
+                }
+            }
+        }
+        Statement::CreateIndex(create_index) if *dialect == Dialect::PostgreSQL && !create_index.concurrently => {
+            let table = object_name(&create_index.table_name);
+            if !is_created(&table) {
+                findings.push(("create-index-lock", format!("indexes {} without CONCURRENTLY, blocking writes while building", table)));
+            }
+        }
+        Statement::Truncate { table_names, .. } => {
+            let names: Vec<String> = table_names.iter().map(|t| object_name(&t.name)).collect();
+            findings.push(("data-loss", format!("truncates {}", names.join(", "))));
+        }
+        Statement::Delete(delete) if delete.selection.is_none() => {
+            findings.push(("data-loss", "deletes every row, without a WHERE clause".to_string()));
+        }
+        Statement::Update { table, selection: None, .. } => {
+            findings.push(("table-rewrite", format!("updates every row of {}, without a WHERE clause", table.relation)));
+        }
+        _ => {}
+    }
+    findings
+}
+
+/// Check if a later statement renames a table to the name, replacing a dropped table
+fn is_replaced(statements: &[Statement], name: &str) -> bool {
+    statements.iter().any(|statement| match statement {
+        Statement::AlterTable { operations, .. } => operations.iter().any(|operation| {
+            matches!(operation, AlterTableOperation::RenameTable { table_name } if object_name(table_name).eq_ignore_ascii_case(name))
+        }),
+        _ => false,
+    })
+}
+
+/// Line and suppressed rules of each statement, and the rules suppressed in the whole file
+fn statement_infos(sql: &str, dialect: &Dialect) -> (Vec<StatementInfo>, Vec<String>) {
+    let sqlparser_dialect = dialect.sqlparser_dialect();
+    let tokens = Tokenizer::new(sqlparser_dialect.as_ref(), sql).tokenize_with_location().unwrap_or_default();
+
+    let mut infos: Vec<StatementInfo> = Vec::new();
+    let mut file_allowed = Vec::new();
+    let mut current: Option<StatementInfo> = None;
+    let mut pending = Vec::new();
+    let mut end_line = None;
+    for token in tokens {
+        let line = token.location.line as usize;
+        match token.token {
+            Token::Whitespace(Whitespace::SingleLineComment { comment, .. } | Whitespace::MultiLineComment(comment)) => {
+                let comment = comment.trim();
+                if let Some(rules) = annotation_rules(comment, ALLOW_FILE_ANNOTATION) {
+                    file_allowed.extend(rules);
+                } else if let Some(rules) = annotation_rules(comment, ALLOW_ANNOTATION) {
+                    match (&mut current, infos.last_mut()) {
+                        (Some(info), _) => info.allowed.extend(rules),
+                        (None, Some(info)) if end_line == Some(line) => info.allowed.extend(rules),
+                        _ => pending.extend(rules),
+                    }
+                }
+            }
+            Token::Whitespace(_) => {}
+            Token::SemiColon => {
+                if let Some(info) = current.take() {
+                    infos.push(info);
+                    end_line = Some(line);
+                }
+            }
+            _ => {
+                if current.is_none() {
+                    current = Some(StatementInfo { line, allowed: std::mem::take(&mut pending) });
+                }
+            }
+        }
+    }
+    infos.extend(current);
+    (infos, file_allowed)
+}
+
+/// Rules listed by an annotation, e.g. `noorm:allow(drop-column, rename)`
+fn annotation_rules(comment: &str, prefix: &str) -> Option<Vec<String>> {
+    let rules = comment.strip_prefix(prefix)?.split(')').next()?;
+    Some(rules.split(',').map(|rule| rule.trim().to_string()).filter(|rule| !rule.is_empty()).collect())
+}
+
+/// Effect of a type change on the existing values
+#[derive(Debug, PartialEq)]
+enum TypeChange {
+    Same,
+    /// Every value fits, without rewriting the table
+    Widens,
+    /// Every value fits, rewriting the table
+    Rewrites,
+    /// Some values may not fit or convert
+    Narrows,
+}
+
+/// Family and capacity of a type, to compare the types of a family
+#[derive(Debug, PartialEq)]
+enum TypeClass {
+    /// Integers, by size in bytes
+    Integer(u64),
+    /// Floating point numbers, by size in bytes
+    Float(u64),
+    /// Exact numbers, by digits before and after the point
+    Decimal(u64, u64),
+    /// Character strings, by maximum length
+    Text(u64),
+    Other,
+}
+
+fn type_change(from: &DataType, to: &DataType) -> TypeChange {
+    if from == to {
+        return TypeChange::Same;
+    }
+    match (type_class(from), type_class(to)) {
+        (TypeClass::Text(a), TypeClass::Text(b)) if a <= b => TypeChange::Widens,
+        (TypeClass::Integer(a), TypeClass::Integer(b)) | (TypeClass::Float(a), TypeClass::Float(b)) if a == b => TypeChange::Same,
+        (TypeClass::Integer(a), TypeClass::Integer(b)) | (TypeClass::Float(a), TypeClass::Float(b)) if a < b => TypeChange::Rewrites,
+        (TypeClass::Decimal(a, b), TypeClass::Decimal(c, d)) if a <= c && b <= d => TypeChange::Rewrites,
+        (TypeClass::Integer(_), TypeClass::Float(_) | TypeClass::Decimal(..)) => TypeChange::Rewrites,
+        (TypeClass::Integer(_) | TypeClass::Float(_) | TypeClass::Decimal(..), TypeClass::Text(u64::MAX)) => TypeChange::Rewrites,
+        _ => TypeChange::Narrows,
+    }
+}
+
+fn type_class(data_type: &DataType) -> TypeClass {
+    let length = |length: &Option<CharacterLength>| match length {
+        Some(CharacterLength::IntegerLength { length, .. }) => *length,
+        _ => u64::MAX,
+    };
+    match data_type {
+        DataType::TinyInt(_) | DataType::UnsignedTinyInt(_) => TypeClass::Integer(1),
+        DataType::SmallInt(_) | DataType::UnsignedSmallInt(_) | DataType::Int2(_) | DataType::UnsignedInt2(_) => TypeClass::Integer(2),
+        DataType::MediumInt(_) | DataType::UnsignedMediumInt(_) => TypeClass::Integer(3),
+        DataType::Int(_) | DataType::Integer(_) | DataType::Int4(_) | DataType::UnsignedInt(_) | DataType::UnsignedInteger(_)
+        | DataType::UnsignedInt4(_) => TypeClass::Integer(4),
+        DataType::BigInt(_) | DataType::UnsignedBigInt(_) | DataType::Int8(_) | DataType::UnsignedInt8(_) => TypeClass::Integer(8),
+        DataType::Real | DataType::Float4 => TypeClass::Float(4),
+        DataType::Double | DataType::DoublePrecision | DataType::Float8 => TypeClass::Float(8),
+        DataType::Float(bits) => TypeClass::Float(if bits.is_some_and(|bits| bits <= 24) { 4 } else { 8 }),
+        DataType::Numeric(info) | DataType::Decimal(info) | DataType::Dec(info) => match info {
+            ExactNumberInfo::None => TypeClass::Decimal(u64::MAX, u64::MAX),
+            ExactNumberInfo::Precision(precision) => TypeClass::Decimal(*precision, 0),
+            ExactNumberInfo::PrecisionAndScale(precision, scale) => TypeClass::Decimal(precision.saturating_sub(*scale), *scale),
+        },
+        DataType::Char(len) | DataType::Character(len) | DataType::Varchar(len) | DataType::CharacterVarying(len)
+        | DataType::CharVarying(len) | DataType::Nvarchar(len) => TypeClass::Text(length(len)),
+        DataType::Text | DataType::String(None) => TypeClass::Text(u64::MAX),
+        DataType::String(Some(len)) => TypeClass::Text(*len),
+        DataType::Custom(name, _) => match name.to_string().to_uppercase().as_str() {
+            "SMALLSERIAL" | "SERIAL2" => TypeClass::Integer(2),
+            "SERIAL" | "SERIAL4" => TypeClass::Integer(4),
+            "BIGSERIAL" | "SERIAL8" => TypeClass::Integer(8),
+            _ => TypeClass::Other,
+        },
+        _ => TypeClass::Other,
+    }
+}
+
+/// Lints as a JSON array.
+pub fn lints_to_json(lints: &[Lint]) -> String {
+    serde_json::to_string_pretty(lints).unwrap_or_default()
+}
+
+/// Lints as a SARIF 2.1.0 log, for code scanning tools.
+pub fn lints_to_sarif(lints: &[Lint]) -> String {
+    let rules: Vec<serde_json::Value> = RULES.iter().map(|rule| serde_json::json!({
+        "id": rule.name,
+        "shortDescription": { "text": rule.description },
+        "defaultConfiguration": { "level": rule.severity.to_string() },
+    })).collect();
+    let results: Vec<serde_json::Value> = lints.iter().map(|lint| {
+        let mut location = serde_json::json!({ "artifactLocation": { "uri": lint.source } });
+        if let Some(line) = lint.line {
+            location["region"] = serde_json::json!({ "startLine": line });
+        }
+        serde_json::json!({
+            "ruleId": lint.rule,
+            "level": lint.severity.to_string(),
+            "message": { "text": lint.message },
+            "locations": [{ "physicalLocation": location }],
+        })
+    }).collect();
+    let log = serde_json::json!({
+        "version": "2.1.0",
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "runs": [{
+            "tool": { "driver": {
+                "name": "noorm",
+                "version": env!("CARGO_PKG_VERSION"),
+                "informationUri": "https://github.com/davidedelpapa/noorm",
+                "rules": rules,
+            } },
+            "results": results,
+        }],
+    });
+    serde_json::to_string_pretty(&log).unwrap_or_default()
+}
+
+/// Lint listing, e.g. `migrations/002.sql:3: error[drop-column]: drops column person.name and its values`
+impl fmt::Display for Lint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "{}:{}: ", self.source, line)?,
+            None => write!(f, "{}: ", self.source)?,
+        }
+        write!(f, "{}[{}]: {}", self.severity, self.rule, self.message)
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
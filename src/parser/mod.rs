@@ -1,20 +1,45 @@
+#[allow(clippy::module_inception)]
 mod parser;
 mod errors;
 mod langgen;
 mod langgen_rust;
+mod langgen_rust_crud;
+mod langgen_rust_relations;
 mod langgen_python;
 mod langgen_javascript;
 mod langgen_dummy;
+mod schema;
+mod schema_format;
+mod output;
+mod diagram;
+mod docs;
+mod diff;
+mod lint;
+mod rust_structs;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+mod views;
 
 pub use parser::Dialect;
-pub use parser::ParserConfig;
+pub use parser::{ParserConfig, Target, TargetOutput, CONFIG_FILE};
 pub use parser::Parser;
+pub use parser::read_sql_files;
+pub use schema_format::is_schema_file;
+#[cfg(feature = "sqlite")]
+pub use sqlite::is_sqlite_database;
 
-pub use errors::ParserConfigError;
+pub use errors::{ParserConfigError, ParserError};
 
-pub use langgen_rust::{RustAst, rust_ast_to_string, rust_parse_create_table};
-pub use langgen_python::{PythonAst, python_parse_create_table, python_ast_to_string};
-pub use langgen_javascript::{JavaScriptAst, javascript_parse_create_table, javascript_ast_to_string};
+pub use langgen_rust::{DateTimeCrate, RustAst, RustNaming, RustOptions, RustTypeCrates, rust_ast_to_file, rust_ast_to_string, rust_parse_create_table, rust_schema_to_ast, rust_schema_to_files, write_rust_ast};
+pub use langgen_rust_crud::RustBackend;
+pub use langgen_python::{PythonAst, python_parse_create_table, python_ast_to_string, python_schema_to_ast, python_schema_to_files};
+pub use langgen_javascript::{JavaScriptAst, javascript_parse_create_table, javascript_ast_to_string, javascript_schema_to_ast, javascript_schema_to_files};
 pub use langgen_dummy::{DummyAst, dummy_parse_create_table, dummy_ast_to_string};
 
-pub use langgen::Language;
\ No newline at end of file
+pub use schema::{Schema, Table, TableKind, Column, DefaultValue, Index, Lookup, ForeignKey, Relation, RelationKind, JoinTable, is_serial_type};
+
+pub use diff::{SchemaChange, SchemaDiff};
+pub use lint::{Lint, LintConfig, NamingStyle, Rule, RuleLevel, Severity, RULES, lints_to_json, lints_to_sarif};
+
+pub use langgen::Language;
+pub use output::{Drift, DriftKind, GeneratedFile, GeneratedFiles, MANIFEST_FILE};
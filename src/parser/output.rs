@@ -0,0 +1,280 @@
+use std::collections::BTreeSet;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+use similar::TextDiff;
+
+/// Name of the file listing the generated files of the output directory
+pub const MANIFEST_FILE: &str = ".noorm-files";
+
+/// Marker of the header line written at the top of the generated files
+const HEADER_MARKER: &str = "@generated by noorm-gen, do not edit. checksum: ";
+
+/// A generated source file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeneratedFile {
+    /// Path relative to the output directory
+    pub path: PathBuf,
+    /// File contents
+    pub contents: String,
+}
+
+impl GeneratedFile {
+    /// Contents as written to disk: the header line with the checksum of the
+    /// contents, then the contents.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use noorm::parser::GeneratedFile;
+    ///
+    /// let file = GeneratedFile { path: "person.py".into(), contents: "class Person: ...\n".into() };
+    /// assert!(file.render().starts_with("# @generated by noorm-gen"));
+    /// ```
+    pub fn render(&self) -> String {
+        let (open, close) = comment_delimiters(&self.path);
+        format!("{} {}{:016x}{}\n{}", open, HEADER_MARKER, checksum(&self.contents), close, self.contents)
+    }
+}
+
+/// Comment syntax of the language of a file, from its extension
+fn comment_delimiters(path: &Path) -> (&'static str, &'static str) {
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("py") => ("#", ""),
+        Some("sql") => ("--", ""),
+        Some("md" | "html") => ("<!--", " -->"),
+        _ => ("//", ""),
+    }
+}
+
+/// 64-bit FNV-1a hash: enough to detect edits, not meant to resist tampering
+fn checksum(contents: &str) -> u64 {
+    contents.bytes().fold(0xcbf29ce484222325, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x100000001b3))
+}
+
+/// Whether a file on disk still matches the checksum of its header
+fn is_unedited(on_disk: &str) -> bool {
+    let Some((header, contents)) = on_disk.split_once('\n') else { return false };
+    header.split_once(HEADER_MARKER)
+        .and_then(|(_, sum)| u64::from_str_radix(sum.trim().trim_end_matches("-->").trim_end(), 16).ok())
+        .is_some_and(|sum| sum == checksum(contents))
+}
+
+/// How a file of the output directory differs from the generated one
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DriftKind {
+    /// The file has not been written yet
+    Missing,
+    /// The file was generated from a different schema
+    Stale,
+    /// The file was edited by hand since it was generated
+    Edited,
+    /// The file was generated by a previous run, and is no longer generated
+    Removed,
+}
+
+/// A difference between the generated files and the output directory.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Drift {
+    /// Path relative to the output directory
+    pub path: PathBuf,
+    /// Kind of difference
+    pub kind: DriftKind,
+    /// Unified diff from the file on disk to the generated one
+    pub diff: String,
+}
+
+impl fmt::Display for Drift {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self.kind {
+            DriftKind::Missing => "missing",
+            DriftKind::Stale => "out of date",
+            DriftKind::Edited => "edited by hand",
+            DriftKind::Removed => "no longer generated",
+        };
+        write!(f, "{}: {}", self.path.display(), kind)
+    }
+}
+
+/// Set of generated files: one per table, plus the module index.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct GeneratedFiles {
+    /// Generated files, the index last
+    pub files: Vec<GeneratedFile>,
+}
+
+impl GeneratedFiles {
+    /// Add a file to the set.
+    pub fn push(&mut self, path: impl Into<PathBuf>, contents: impl Into<String>) {
+        self.files.push(GeneratedFile { path: path.into(), contents: contents.into() });
+    }
+
+    /// Find a file by its relative path.
+    pub fn get(&self, path: impl AsRef<Path>) -> Option<&GeneratedFile> {
+        self.files.iter().find(|f| f.path == path.as_ref())
+    }
+
+    /// Write the files to an output directory.
+    ///
+    /// All the files are first written next to their destination, then renamed over it,
+    /// so that an error leaves the previous files in place. Files already up to date are
+    /// not rewritten, to keep their modification time. The files generated by the
+    /// previous run and missing from this set are removed, unless they were edited by hand;
+    /// other files are left alone.
+    ///
+    /// # Returns
+    ///
+    /// * The files no longer generated but kept because they were edited by hand
+    ///
+    /// # Errors
+    ///
+    /// * Returns the `io::Error` of the first file operation failing
+    /// * An `io::ErrorKind::InvalidData` error if the list of the previous files has a path
+    ///   outside the output directory
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use noorm::parser::GeneratedFiles;
+    ///
+    /// let mut files = GeneratedFiles::default();
+    /// files.push("person.rs", "pub struct Person {}\n");
+    /// files.write_to("src/db").unwrap();
+    /// ```
+    pub fn write_to(&self, dir: impl AsRef<Path>) -> io::Result<Vec<PathBuf>> {
+        let dir = dir.as_ref();
+        fs::create_dir_all(dir)?;
+        let previous = read_manifest(dir)?;
+
+        let mut staged = Vec::new();
+        for file in &self.files {
+            let path = dir.join(&file.path);
+            let contents = file.render();
+            if fs::read_to_string(&path).is_ok_and(|on_disk| on_disk == contents) {
+                continue;
+            }
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            let tmp = tmp_path(&path);
+            if let Err(e) = fs::write(&tmp, contents) {
+                staged.iter().for_each(|(tmp, _)| { let _ = fs::remove_file(tmp); });
+                return Err(e);
+            }
+            staged.push((tmp, path));
+        }
+        for (tmp, path) in &staged {
+            fs::rename(tmp, path)?;
+        }
+
+        let mut current: BTreeSet<PathBuf> = self.files.iter().map(|f| f.path.clone()).collect();
+        let mut kept = Vec::new();
+        for stale in previous.difference(&current) {
+            let path = dir.join(stale);
+            match fs::read_to_string(&path) {
+                Ok(on_disk) if is_unedited(&on_disk) => fs::remove_file(path)?,
+                // Edited by hand, or not even text
+                Ok(_) => kept.push(stale.clone()),
+                Err(e) if e.kind() == io::ErrorKind::InvalidData => kept.push(stale.clone()),
+                Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+                Err(e) => return Err(e),
+            }
+        }
+        // The kept files stay listed, to be reported until removed by hand
+        current.extend(kept.iter().cloned());
+        write_manifest(dir, &current)?;
+        Ok(kept)
+    }
+
+    /// Compare the files with the ones of an output directory, without writing anything.
+    ///
+    /// Returns one `Drift` per file that `write_to` would create, change or remove;
+    /// an up to date directory gives an empty list.
+    ///
+    /// # Errors
+    ///
+    /// * Returns the `io::Error` of the first file failing to be read
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use noorm::parser::GeneratedFiles;
+    ///
+    /// let mut files = GeneratedFiles::default();
+    /// files.push("person.rs", "pub struct Person {}\n");
+    /// for drift in files.check("src/db").unwrap() {
+    ///     println!("{}\n{}", drift, drift.diff);
+    /// }
+    /// ```
+    pub fn check(&self, dir: impl AsRef<Path>) -> io::Result<Vec<Drift>> {
+        let dir = dir.as_ref();
+        let mut drifts = Vec::new();
+        for file in &self.files {
+            let expected = file.render();
+            let (kind, on_disk) = match fs::read_to_string(dir.join(&file.path)) {
+                Ok(on_disk) if on_disk == expected => continue,
+                Ok(on_disk) if is_unedited(&on_disk) => (DriftKind::Stale, on_disk),
+                Ok(on_disk) => (DriftKind::Edited, on_disk),
+                Err(e) if e.kind() == io::ErrorKind::NotFound => (DriftKind::Missing, String::new()),
+                Err(e) => return Err(e),
+            };
+            drifts.push(Drift { diff: unified_diff(&file.path, &on_disk, &expected), path: file.path.clone(), kind });
+        }
+
+        let current: BTreeSet<&PathBuf> = self.files.iter().map(|f| &f.path).collect();
+        for stale in read_manifest(dir)? {
+            if current.contains(&stale) {
+                continue;
+            }
+            match fs::read_to_string(dir.join(&stale)) {
+                Ok(on_disk) => drifts.push(Drift { diff: unified_diff(&stale, &on_disk, ""), path: stale, kind: DriftKind::Removed }),
+                Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(drifts)
+    }
+}
+
+fn unified_diff(path: &Path, old: &str, new: &str) -> String {
+    let path = path.display().to_string();
+    TextDiff::from_lines(old, new)
+        .unified_diff()
+        .header(&format!("a/{}", path), &format!("b/{}", path))
+        .to_string()
+}
+
+/// Temporary path next to the destination, on the same filesystem for the rename
+fn tmp_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".noorm-tmp");
+    path.with_file_name(name)
+}
+
+/// Files written by the previous run, one relative path per line; a path leaving the
+/// output directory, e.g. `../main.rs` or `/etc/hosts`, is an `InvalidData` error
+fn read_manifest(dir: &Path) -> io::Result<BTreeSet<PathBuf>> {
+    let manifest = match fs::read_to_string(dir.join(MANIFEST_FILE)) {
+        Ok(manifest) => manifest,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(BTreeSet::new()),
+        Err(e) => return Err(e),
+    };
+    manifest.lines().filter(|l| !l.is_empty()).map(|line| {
+        let path = PathBuf::from(line);
+        if path.components().all(|c| matches!(c, Component::Normal(_))) {
+            Ok(path)
+        } else {
+            Err(io::Error::new(io::ErrorKind::InvalidData, format!("{} lists {}, outside of {}", MANIFEST_FILE, line, dir.display())))
+        }
+    }).collect()
+}
+
+fn write_manifest(dir: &Path, files: &BTreeSet<PathBuf>) -> io::Result<()> {
+    let manifest: String = files.iter().map(|path| format!("{}\n", path.display())).collect();
+    let path = dir.join(MANIFEST_FILE);
+    let tmp = tmp_path(&path);
+    fs::write(&tmp, manifest)?;
+    fs::rename(tmp, path)
+}
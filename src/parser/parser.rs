@@ -1,10 +1,10 @@
-use std::{fmt::Display, path::PathBuf};
+use std::{fmt::Display, fs, ops::Range, path::{Path, PathBuf}};
 use serde::Deserialize;
-use toml;
+use toml::{self, Spanned};
 
-use crate::parser::Language;
+use crate::parser::{Language, LintConfig, Schema};
 
-use super::{errors::ParserError, ParserConfigError};
+use super::{errors::ParserError, Drift, GeneratedFiles, ParserConfigError};
 
 /// Dialects used to parse SQL.
 #[derive(Debug, Default, PartialEq, Clone, Deserialize)]
@@ -15,10 +15,95 @@ pub enum Dialect {
     Generic,
     /// MySQL dialect
     MySQL,   
+    /// PostgreSQL dialect
+    PostgreSQL,
+    /// SQLite dialect
+    SQLite,
 }
 
+impl Dialect {
+    /// Get a Dialect from its name, case insensitive, e.g. "PostgreSQL"
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use noorm::prelude::*;
+    ///
+    /// assert_eq!(Dialect::new("postgres"), Some(Dialect::PostgreSQL));
+    /// assert_eq!(Dialect::new("oracle"), None);
+    /// ```
+    pub fn new(dialect_name: &str) -> Option<Self> {
+        match dialect_name.to_lowercase().as_str() {
+            "generic" => Some(Dialect::Generic),
+            "mysql" => Some(Dialect::MySQL),
+            "postgresql" | "postgres" => Some(Dialect::PostgreSQL),
+            "sqlite" => Some(Dialect::SQLite),
+            _ => None,
+        }
+    }
+
+    /// Get the sqlparser Dialect used to parse SQL statements.
+    pub fn sqlparser_dialect(&self) -> Box<dyn sqlparser::dialect::Dialect> {
+        match self {
+            Dialect::Generic => Box::new(sqlparser::dialect::GenericDialect {}),
+            Dialect::MySQL => Box::new(sqlparser::dialect::MySqlDialect {}),
+            Dialect::PostgreSQL => Box::new(sqlparser::dialect::PostgreSqlDialect {}),
+            Dialect::SQLite => Box::new(sqlparser::dialect::SQLiteDialect {}),
+        }
+    }
+
+    /// Get the prefix of numbered query placeholders (`$1`, `?1`),
+    /// or `None` if the dialect only supports positional `?` placeholders.
+    pub fn placeholder_prefix(&self) -> Option<&'static str> {
+        match self {
+            Dialect::Generic | Dialect::PostgreSQL => Some("$"),
+            Dialect::SQLite => Some("?"),
+            Dialect::MySQL => None,
+        }
+    }
+
+    /// Get the query placeholder for the parameter at `index` (starting from 1).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use noorm::prelude::*;
+    ///
+    /// assert_eq!(Dialect::PostgreSQL.placeholder(1), "$1");
+    /// assert_eq!(Dialect::MySQL.placeholder(1), "?");
+    /// ```
+    pub fn placeholder(&self, index: usize) -> String {
+        match self.placeholder_prefix() {
+            Some(prefix) => format!("{}{}", prefix, index),
+            None => "?".to_string(),
+        }
+    }
+
+    /// Quote a table or column name for the SQL of the dialect: in backticks for MySQL,
+    /// in double quotes for the others.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use noorm::prelude::*;
+    ///
+    /// assert_eq!(Dialect::PostgreSQL.quote_identifier("user"), "\"user\"");
+    /// assert_eq!(Dialect::MySQL.quote_identifier("order"), "`order`");
+    /// ```
+    pub fn quote_identifier(&self, name: &str) -> String {
+        match self {
+            Dialect::MySQL => format!("`{}`", name.replace('`', "``")),
+            Dialect::Generic | Dialect::PostgreSQL | Dialect::SQLite => format!("\"{}\"", name.replace('"', "\"\"")),
+        }
+    }
+}
+
+/// Name of the configuration file looked for by `ParserConfig::discover`
+pub const CONFIG_FILE: &str = "noorm.toml";
+
 /// Parser configuration
 #[derive(Debug, Default, Clone, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
 pub struct ParserConfig {
     /// Dialect used for SQL parsing
     #[serde(default)]
@@ -29,9 +114,55 @@ pub struct ParserConfig {
     /// Directory where the migrations are found
     #[serde(default = "get_default_path")]
     pub migrations: PathBuf,
-    /// Directory where the queries are found
+    /// Directory where the queries are found, none when empty
+    #[serde(default)]
     pub queries: PathBuf,
+    /// Directory where the generated files are written
+    #[serde(default)]
+    pub output: Option<PathBuf>,
+    /// Targets generated from the same SQL, each with its language and output directory;
+    /// when there are none, `language` and `output` are the only target
+    #[serde(default)]
+    pub targets: Vec<Target>,
+    /// Configuration of the lint rules
+    #[serde(default)]
+    pub lint: LintConfig,
 }
+
+/// A code generation target of the configuration, e.g. in TOML:
+///
+/// ```toml
+/// [[targets]]
+/// language = "python"
+/// output = "py/db/"
+///
+/// [[targets]]
+/// language = { rust = { backend = "sqlx" } }
+/// output = "src/db/"
+/// ```
+#[derive(Debug, Default, Clone, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Target {
+    /// Target language, with its generator options
+    pub language: Language,
+    /// Directory where the generated files are written
+    #[serde(default)]
+    pub output: Option<PathBuf>,
+}
+
+/// Code generated for a target.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TargetOutput {
+    /// Name of the target language
+    pub language: &'static str,
+    /// Directory where the files are written
+    pub output: Option<PathBuf>,
+    /// Generated code, as a single source
+    pub code: String,
+    /// Generated code, as a set of files
+    pub files: GeneratedFiles,
+}
+
 impl ParserConfig {
     /// Create a new ParserConfig.
     /// 
@@ -48,6 +179,9 @@ impl ParserConfig {
             language: Language::default(),
             migrations: PathBuf::new(),
             queries: PathBuf::new(),
+            output: None,
+            targets: Vec::new(),
+            lint: LintConfig::default(),
         }
     }
 
@@ -69,15 +203,197 @@ impl ParserConfig {
     /// let conf_string = r#"
     ///     migrations = "migrations/"
     ///     queries = "queries/"
-    ///     dialect = {type = "Generic"}
+    ///     sql_dialect = {type = "Generic"}
     /// "#;
     /// 
     /// let config = ParserConfig::from_toml(&conf_string).unwrap();
+    /// assert_eq!(config.sql_dialect, Dialect::Generic);
     /// ```
     pub fn from_toml(conf_string: &str) -> Result<Self, ParserConfigError> {
         let config: ParserConfig = toml::from_str(conf_string)?;
         Ok(config)
     }
+
+    /// Create a new ParserConfig from a TOML file: a `noorm.toml`, or a `Cargo.toml`
+    /// with a `[package.metadata.noorm]` table.
+    ///
+    /// Relative paths are resolved against the directory of the file, and the
+    /// migrations and queries paths must exist. Without `queries`, none are parsed.
+    ///
+    /// # Errors
+    ///
+    /// * `ParserConfigError::Read` if the file cannot be read
+    /// * `ParserConfigError::Invalid` if the TOML is invalid or a path does not exist,
+    ///   with the line and column of the error
+    /// * `ParserConfigError::MissingSection` if a `Cargo.toml` has no `[package.metadata.noorm]`
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use noorm::prelude::*;
+    ///
+    /// let config = ParserConfig::from_file("noorm.toml").unwrap();
+    /// println!("{}", config.migrations.display());
+    /// ```
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, ParserConfigError> {
+        let path = path.as_ref();
+        let toml = fs::read_to_string(path)
+            .map_err(|source| ParserConfigError::Read { path: path.to_path_buf(), source })?;
+        let invalid = |error: toml::de::Error| invalid_config(path, &toml, error.span(), error.message());
+
+        let (mut config, spans) = if path.file_name().is_some_and(|name| name == "Cargo.toml") {
+            let manifest: CargoManifest<ParserConfig> = toml::from_str(&toml).map_err(invalid)?;
+            let spans: CargoManifest<PathSpans> = toml::from_str(&toml).map_err(invalid)?;
+            match (manifest.noorm(), spans.noorm()) {
+                (Some(config), Some(spans)) => (config, spans),
+                _ => return Err(ParserConfigError::MissingSection(path.to_path_buf())),
+            }
+        } else {
+            (toml::from_str(&toml).map_err(invalid)?, toml::from_str(&toml).map_err(invalid)?)
+        };
+
+        let base = path.parent().unwrap_or(Path::new(""));
+        for (dir, span) in [(&mut config.migrations, spans.migrations), (&mut config.queries, spans.queries)] {
+            // An empty path stays empty, instead of becoming the directory of the file
+            if dir.as_os_str().is_empty() {
+                continue;
+            }
+            *dir = base.join(&*dir);
+            if !dir.exists() {
+                let span = span.map(|span| span.span());
+                return Err(invalid_config(path, &toml, span, &format!("{} does not exist", dir.display())));
+            }
+        }
+        config.output = config.output.map(|output| base.join(output));
+        for target in &mut config.targets {
+            target.output = target.output.take().map(|output| base.join(output));
+        }
+        Ok(config)
+    }
+
+    /// Find the configuration of the current directory: the first `noorm.toml`, or
+    /// `Cargo.toml` with a `[package.metadata.noorm]` table, in it or its parents.
+    ///
+    /// # Errors
+    ///
+    /// * `ParserConfigError::NotFound` if there is no configuration file
+    /// * The errors of `ParserConfig::from_file` for the configuration found
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use noorm::prelude::*;
+    ///
+    /// let config = ParserConfig::discover().unwrap();
+    /// ```
+    pub fn discover() -> Result<Self, ParserConfigError> {
+        let dir = std::env::current_dir().map_err(|source| ParserConfigError::Read { path: PathBuf::from("."), source })?;
+        Self::discover_from(dir)
+    }
+
+    /// Find the configuration of a directory, as `ParserConfig::discover` does.
+    ///
+    /// # Errors
+    ///
+    /// * The errors of `ParserConfig::discover`
+    pub fn discover_from(dir: impl AsRef<Path>) -> Result<Self, ParserConfigError> {
+        for dir in dir.as_ref().ancestors() {
+            let config_file = dir.join(CONFIG_FILE);
+            if config_file.is_file() {
+                return Self::from_file(config_file);
+            }
+            match Self::from_file(dir.join("Cargo.toml")) {
+                Err(ParserConfigError::Read { .. } | ParserConfigError::MissingSection(_)) => continue,
+                result => return result,
+            }
+        }
+        Err(ParserConfigError::NotFound(dir.as_ref().to_path_buf()))
+    }
+}
+
+/// The `[package.metadata.noorm]` table of a `Cargo.toml`
+#[derive(Deserialize)]
+struct CargoManifest<T> {
+    package: Option<CargoPackage<T>>,
+}
+
+#[derive(Deserialize)]
+struct CargoPackage<T> {
+    metadata: Option<CargoMetadata<T>>,
+}
+
+#[derive(Deserialize)]
+struct CargoMetadata<T> {
+    noorm: Option<T>,
+}
+
+impl<T> CargoManifest<T> {
+    fn noorm(self) -> Option<T> {
+        self.package?.metadata?.noorm
+    }
+}
+
+/// Location of the path settings in the TOML, to report the paths not found
+#[derive(Deserialize)]
+struct PathSpans {
+    migrations: Option<Spanned<toml::Value>>,
+    queries: Option<Spanned<toml::Value>>,
+}
+
+/// Configuration error at a byte span of a TOML file, reported as line and column
+fn invalid_config(path: &Path, toml: &str, span: Option<Range<usize>>, message: &str) -> ParserConfigError {
+    let offset = span.map_or(0, |span| span.start.min(toml.len()));
+    let before = &toml[..offset];
+    let line = before.matches('\n').count() + 1;
+    let column = before.chars().rev().take_while(|&c| c != '\n').count() + 1;
+    ParserConfigError::Invalid { path: path.to_path_buf(), line, column, message: message.trim().to_string() }
+}
+
+/// Read the `.sql` files of a directory, sorted by file name, or a single SQL file.
+///
+/// # Errors
+///
+/// * `ParserError::Read` if the path or one of its files cannot be read
+///
+/// # Examples
+///
+/// ```no_run
+/// use noorm::parser::read_sql_files;
+///
+/// for (path, sql) in read_sql_files("migrations/").unwrap() {
+///     println!("{}: {} bytes", path.display(), sql.len());
+/// }
+/// ```
+pub fn read_sql_files(path: impl AsRef<Path>) -> Result<Vec<(PathBuf, String)>, ParserError> {
+    let path = path.as_ref();
+    let read_error = |source| ParserError::Read { path: path.to_path_buf(), source };
+    let mut paths = Vec::new();
+    if path.is_dir() {
+        for entry in fs::read_dir(path).map_err(read_error)? {
+            let file = entry.map_err(read_error)?.path();
+            if file.is_file() && file.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("sql")) {
+                paths.push(file);
+            }
+        }
+        paths.sort();
+    } else {
+        paths.push(path.to_path_buf());
+    }
+    paths.into_iter()
+        .map(|file| match fs::read_to_string(&file) {
+            Ok(sql) => Ok((file, sql)),
+            Err(source) => Err(ParserError::Read { path: file, source }),
+        })
+        .collect()
+}
+
+/// Parse each file on its own, so that errors point to the file
+fn check_sql_files(files: &[(PathBuf, String)], dialect: &Dialect) -> Result<(), ParserError> {
+    for (path, sql) in files {
+        sqlparser::parser::Parser::parse_sql(dialect.sqlparser_dialect().as_ref(), sql)
+            .map_err(|source| ParserError::SqlFile { path: path.clone(), source })?;
+    }
+    Ok(())
 }
 
 fn get_default_path() -> PathBuf {
@@ -85,11 +401,20 @@ fn get_default_path() -> PathBuf {
 }
 
 /// Generic Parser.
+#[derive(Default)]
 pub struct Parser {
     conf: ParserConfig,
     statement: Option<String>,
+    /// Named sources of SQL statements, applied after the statement
+    sources: Vec<(String, String)>,
+    /// Schema read from a database or a schema file, under the statement and the sources
+    base_schema: Option<Schema>,
     /// Output of the parser exection
     pub output: Option<String>,
+    /// Output of the parser execution, as a set of files
+    pub files: Option<GeneratedFiles>,
+    /// Output of the parser execution for each target; `output` and `files` are the first one
+    pub targets: Vec<TargetOutput>,
 }
 impl Parser {
     /// Create a new Parser.
@@ -105,7 +430,11 @@ impl Parser {
         Self {
             conf: ParserConfig::new(),
             statement: None,
-            output: None, 
+            sources: Vec::new(),
+            base_schema: None,
+            output: None,
+            files: None,
+            targets: Vec::new(),
         }
     }
 
@@ -170,6 +499,149 @@ impl Parser {
         self
     }
 
+    /// Get a Parser's statement.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use noorm::prelude::*;
+    ///
+    /// let parser = Parser::new().statement("CREATE TABLE person ( id INTEGER )");
+    /// assert_eq!(parser.get_statement(), Some("CREATE TABLE person ( id INTEGER )"));
+    /// ```
+    pub fn get_statement(&self) -> Option<&str> {
+        self.statement.as_deref()
+    }
+
+    /// Add a named source of SQL statements, e.g. a migration.
+    ///
+    /// The sources are applied in order after the statement, merged in one schema.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use noorm::prelude::*;
+    ///
+    /// let mut parser = Parser::new()
+    ///     .add_source("001_person.sql", "CREATE TABLE person ( id INTEGER PRIMARY KEY )")
+    ///     .add_source("002_office.sql", "CREATE TABLE office ( id INTEGER, person_id INTEGER REFERENCES person )");
+    /// let schema = parser.schema().unwrap();
+    /// assert_eq!(schema.tables.len(), 2);
+    /// ```
+    pub fn add_source(mut self, name: impl Display, sql: impl Display) -> Self {
+        self.sources.push((name.to_string(), sql.to_string()));
+        self
+    }
+
+    /// Add an SQL file as a source, named after its path.
+    ///
+    /// # Errors
+    ///
+    /// * `ParserError::Read` if the file cannot be read
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use noorm::prelude::*;
+    ///
+    /// let parser = Parser::new()
+    ///     .add_file("migrations/001_person.sql").unwrap()
+    ///     .add_file("migrations/002_office.sql").unwrap();
+    /// ```
+    pub fn add_file(self, path: impl AsRef<Path>) -> Result<Self, ParserError> {
+        let path = path.as_ref();
+        let sql = fs::read_to_string(path).map_err(|source| ParserError::Read { path: path.to_path_buf(), source })?;
+        Ok(self.add_source(path.display(), sql))
+    }
+
+    /// Get the schema of the statement and of the sources.
+    ///
+    /// # Errors
+    ///
+    /// * `ParserError::Statement` if there is no statement nor source
+    /// * The errors of `Schema::add_sql`
+    pub fn schema(&self) -> Result<Schema, ParserError> {
+        if self.statement.is_none() && self.sources.is_empty() && self.base_schema.is_none() {
+            return Err(ParserError::Statement);
+        }
+        let mut schema = self.base_schema.clone().unwrap_or_default();
+        if let Some(sql) = &self.statement {
+            schema.add_sql(sql, None, &self.conf.sql_dialect)?;
+        }
+        for (name, sql) in &self.sources {
+            schema.add_sql(sql, Some(name), &self.conf.sql_dialect)?;
+        }
+        Ok(schema)
+    }
+
+    /// Add the migrations of the configuration as sources, and check that the
+    /// migrations and the queries parse.
+    ///
+    /// The SQL files are read in file name order; each one is parsed on its own,
+    /// so that the errors name the file. An empty `queries` path is skipped.
+    /// A `migrations` path to an SQLite database, or to a `.json`, `.yaml` or `.yml`
+    /// schema file, is read as the schema instead.
+    ///
+    /// # Errors
+    ///
+    /// * `ParserError::Read` if a directory or file cannot be read
+    /// * `ParserError::Sqlite` if the SQLite database cannot be read
+    /// * `ParserError::SchemaFormat` if the schema file is not valid
+    /// * `ParserError::NoSqlFiles` if the migrations directory has no SQL file
+    /// * `ParserError::SqlFile` if a file fails to parse
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use noorm::prelude::*;
+    ///
+    /// let mut config = ParserConfig::new();
+    /// config.migrations = "migrations/".into();
+    /// let mut parser = Parser::new().set_config(config).load().expect("Loading error");
+    /// parser.parse().expect("Parser error");
+    /// ```
+    pub fn load(self) -> Result<Self, ParserError> {
+        let parser = self.load_migrations()?;
+        parser.check_queries()?;
+        Ok(parser)
+    }
+
+    /// Add the migrations of the configuration as sources, like `load`, without checking the queries.
+    ///
+    /// # Errors
+    ///
+    /// * The errors of `load`, for the migrations
+    pub fn load_migrations(self) -> Result<Self, ParserError> {
+        #[cfg(feature = "sqlite")]
+        if super::is_sqlite_database(&self.conf.migrations) {
+            let schema = Schema::from_sqlite(&self.conf.migrations)?;
+            return Ok(Self { base_schema: Some(schema), ..self });
+        }
+        if super::is_schema_file(&self.conf.migrations) {
+            let schema = Schema::from_schema_file(&self.conf.migrations)?;
+            return Ok(Self { base_schema: Some(schema), ..self });
+        }
+        let migrations = read_sql_files(&self.conf.migrations)?;
+        if migrations.is_empty() {
+            return Err(ParserError::NoSqlFiles(self.conf.migrations.clone()));
+        }
+        check_sql_files(&migrations, &self.conf.sql_dialect)?;
+        Ok(migrations.into_iter().fold(self, |parser, (path, sql)| parser.add_source(path.display(), sql)))
+    }
+
+    /// Check that the queries of the configuration parse; an empty `queries` path is skipped.
+    ///
+    /// # Errors
+    ///
+    /// * `ParserError::Read` if a directory or file cannot be read
+    /// * `ParserError::SqlFile` if a file fails to parse
+    pub fn check_queries(&self) -> Result<(), ParserError> {
+        if self.conf.queries.as_os_str().is_empty() {
+            return Ok(());
+        }
+        check_sql_files(&read_sql_files(&self.conf.queries)?, &self.conf.sql_dialect)
+    }
+
     /// Run a Parser.
     /// 
     /// # Examples
@@ -182,15 +654,160 @@ impl Parser {
     /// parser.parse().expect("Parser error");
     /// ```
     pub fn parse (&mut self) -> Result<(), ParserError> {
-        let lang = &mut self.conf.language;
- 
-        match &self.statement {
-            Some(sql) => {
-                lang.parse_create_table(sql.as_str());
-                self.output = Some(lang.to_string());
-                Ok(())
-            },
-            None => Err(ParserError::Statement),
+        let needs_schema = match self.conf.targets.is_empty() {
+            true => !matches!(self.conf.language, Language::Dummy(_)),
+            false => self.conf.targets.iter().any(|target| !matches!(target.language, Language::Dummy(_))),
+        };
+        // The SQL is parsed once for all the targets; the Dummy language only echoes it
+        let schema = if needs_schema { self.schema()? } else { Schema::default() };
+        let base_sql = self.base_schema.as_ref().map(|schema| schema.create_sql(&self.conf.sql_dialect));
+        let sql = base_sql.iter().chain(&self.statement).chain(self.sources.iter().map(|(_, sql)| sql)).cloned().collect::<Vec<_>>().join("\n");
+        if sql.is_empty() {
+            return Err(ParserError::Statement);
+        }
+        let dialect = &self.conf.sql_dialect;
+        let languages: Vec<(&mut Language, Option<&PathBuf>)> = if self.conf.targets.is_empty() {
+            vec![(&mut self.conf.language, self.conf.output.as_ref())]
+        } else {
+            self.conf.targets.iter_mut().map(|target| (&mut target.language, target.output.as_ref())).collect()
+        };
+
+        let mut targets = Vec::new();
+        for (lang, output) in languages {
+            match lang {
+                Language::Dummy(_) => { lang.parse_create_table(&sql, dialect)?; }
+                _ => lang.generate(&schema, dialect)?,
+            }
+            targets.push(TargetOutput {
+                language: lang.name(),
+                output: output.cloned(),
+                code: lang.to_string(),
+                files: lang.to_files(&schema, dialect),
+            });
+        }
+        self.output = targets.first().map(|target| target.code.clone());
+        self.files = targets.first().map(|target| target.files.clone());
+        self.targets = targets;
+        Ok(())
+    }
+
+    /// Write the generated files to the output directories of the targets,
+    /// removing the files left over from the previous run.
+    ///
+    /// # Returns
+    ///
+    /// * The files left over but kept because they were edited by hand, with their output directory
+    ///
+    /// # Errors
+    ///
+    /// * `ParserError::OutputDir` if no target has an output directory
+    /// * `ParserError::Statement` if nothing has been parsed yet
+    /// * `ParserError::Io` if writing the files fails
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use noorm::prelude::*;
+    ///
+    /// let mut config = ParserConfig::new();
+    /// config.output = Some("src/db".into());
+    /// let mut parser = Parser::new()
+    ///     .set_config(config)
+    ///     .statement("CREATE TABLE person ( id INTEGER, name TEXT )");
+    /// parser.parse().expect("Parser error");
+    /// parser.write_output().expect("Output error");
+    /// ```
+    pub fn write_output(&self) -> Result<Vec<PathBuf>, ParserError> {
+        let mut kept = Vec::new();
+        for (dir, files) in self.target_dirs()? {
+            kept.extend(files.write_to(dir)?.into_iter().map(|path| dir.join(path)));
+        }
+        Ok(kept)
+    }
+
+    /// Compare the generated files with the ones of the output directories of the
+    /// targets, without writing anything; the paths of the differences include the
+    /// output directory.
+    ///
+    /// # Errors
+    ///
+    /// * `ParserError::OutputDir` if no target has an output directory
+    /// * `ParserError::Statement` if nothing has been parsed yet
+    /// * `ParserError::Io` if reading the files fails
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use noorm::prelude::*;
+    ///
+    /// let mut config = ParserConfig::new();
+    /// config.output = Some("src/db".into());
+    /// let mut parser = Parser::new()
+    ///     .set_config(config)
+    ///     .statement("CREATE TABLE person ( id INTEGER, name TEXT )");
+    /// parser.parse().expect("Parser error");
+    /// let drifts = parser.check_output().expect("Output error");
+    /// assert!(drifts.is_empty(), "generated code is out of date");
+    /// ```
+    pub fn check_output(&self) -> Result<Vec<Drift>, ParserError> {
+        let mut drifts = Vec::new();
+        for (dir, files) in self.target_dirs()? {
+            drifts.extend(files.check(dir)?.into_iter().map(|drift| Drift { path: dir.join(&drift.path), ..drift }));
+        }
+        Ok(drifts)
+    }
+
+    /// Compare the generated files with the output directories of the targets, like
+    /// `check_output`, and write the targets that differ, leaving the others untouched.
+    ///
+    /// # Returns
+    ///
+    /// * The differences found, i.e. the files written or removed
+    ///
+    /// # Errors
+    ///
+    /// * `ParserError::OutputDir` if no target has an output directory
+    /// * `ParserError::Statement` if nothing has been parsed yet
+    /// * `ParserError::Io` if reading or writing the files fails
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use noorm::prelude::*;
+    ///
+    /// let mut config = ParserConfig::new();
+    /// config.output = Some("src/db".into());
+    /// let mut parser = Parser::new()
+    ///     .set_config(config)
+    ///     .statement("CREATE TABLE person ( id INTEGER, name TEXT )");
+    /// parser.parse().expect("Parser error");
+    /// for drift in parser.update_output().expect("Output error") {
+    ///     println!("Updated {}", drift.path.display());
+    /// }
+    /// ```
+    pub fn update_output(&self) -> Result<Vec<Drift>, ParserError> {
+        let mut drifts = Vec::new();
+        for (dir, files) in self.target_dirs()? {
+            let changes = files.check(dir)?;
+            if !changes.is_empty() {
+                files.write_to(dir)?;
+                drifts.extend(changes.into_iter().map(|drift| Drift { path: dir.join(&drift.path), ..drift }));
+            }
+        }
+        Ok(drifts)
+    }
+
+    /// Generated files of the targets with an output directory
+    fn target_dirs(&self) -> Result<Vec<(&PathBuf, &GeneratedFiles)>, ParserError> {
+        if self.targets.is_empty() {
+            return Err(ParserError::Statement);
+        }
+        let dirs: Vec<(&PathBuf, &GeneratedFiles)> = self.targets.iter()
+            .filter_map(|target| Some((target.output.as_ref()?, &target.files)))
+            .collect();
+        if dirs.is_empty() {
+            return Err(ParserError::OutputDir);
         }
+        Ok(dirs)
     }
 }
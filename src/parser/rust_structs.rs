@@ -0,0 +1,405 @@
+use sqlparser::ast::{CharacterLength, DataType, ExactNumberInfo, TimezoneInfo};
+use sqlparser::parser::Parser;
+use sqlparser::tokenizer::Token;
+use syn::{Attribute, Expr, ExprLit, GenericArgument, Item, ItemStruct, Lit, LitStr, PathArguments, Type};
+use syn::ext::IdentExt;
+
+use super::{diff, is_serial_type, Column, Dialect, ForeignKey, Index, ParserError, Schema, Table, TableKind};
+
+/// Name of the attribute marking the structs and fields, e.g. `#[noorm(primary_key)]`
+const ATTRIBUTE: &str = "noorm";
+
+/// Options of a field, from its `#[noorm(...)]` attributes
+#[derive(Default)]
+struct FieldOptions {
+    column: Option<String>,
+    primary_key: bool,
+    auto_increment: bool,
+    unique: bool,
+    index: bool,
+    skip: bool,
+    sql_type: Option<String>,
+    default: Option<String>,
+    references: Option<String>,
+}
+
+impl Schema {
+    /// Create a new Schema from the annotated structs of a Rust source file.
+    ///
+    /// Every struct with a `#[noorm]` attribute is a table, named after the struct in snake_case
+    /// or by `#[noorm(table = "...")]`, with a column per field: `Option` fields are `NULL`.
+    /// The fields are annotated with:
+    ///
+    /// * `primary_key`, `auto_increment`, `unique`, `index` - keys and indexes of the column; only
+    ///   integer columns are `auto_increment`, and `u64` fields are `NUMERIC(20)`, beyond `BIGINT`
+    /// * `column = "..."` - name of the column, instead of the field name
+    /// * `sql_type = "..."` - SQL type of the column, instead of the one of the Rust type
+    /// * `default = "..."` - `DEFAULT` expression of the column
+    /// * `references = "table"` or `"table(column)"` - foreign key of the column
+    /// * `skip` - the field is not a column
+    ///
+    /// # Errors
+    ///
+    /// * Can return a `ParserError` if the Rust parsing fails, or an attribute or field type is not valid
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use noorm::prelude::*;
+    /// use noorm::parser::Schema;
+    ///
+    /// let source = r#"
+    ///     #[noorm]
+    ///     struct Person {
+    ///         #[noorm(primary_key, auto_increment)]
+    ///         id: i64,
+    ///         #[noorm(unique, sql_type = "VARCHAR(100)")]
+    ///         email: String,
+    ///         age: Option<i32>,
+    ///     }
+    /// "#;
+    /// let schema = Schema::from_rust(source, &Dialect::PostgreSQL).unwrap();
+    /// assert_eq!(schema.tables[0].create_statements(&Dialect::PostgreSQL), vec![
+    ///     "CREATE TABLE person (
+    ///     id BIGINT NOT NULL GENERATED BY DEFAULT AS IDENTITY,
+    ///     email VARCHAR(100) NOT NULL,
+    ///     age INTEGER NULL,
+    ///     PRIMARY KEY (id),
+    ///     UNIQUE (email)
+    /// )"]);
+    /// ```
+    pub fn from_rust(source: &str, dialect: &Dialect) -> Result<Self, ParserError> {
+        let mut schema = Schema::default();
+        schema.add_rust(source, None, dialect)?;
+        Ok(schema)
+    }
+
+    /// Add the tables of the annotated structs of a Rust source file, named after the source, e.g. its path.
+    ///
+    /// # Errors
+    ///
+    /// * Can return a `ParserError` if the Rust parsing fails, an attribute or field type is not valid,
+    ///   or a table is already defined
+    pub fn add_rust(&mut self, code: &str, source: Option<&str>, dialect: &Dialect) -> Result<(), ParserError> {
+        let file = syn::parse_file(code).map_err(|e| rust_error(source, e))?;
+        for item in &file.items {
+            let Item::Struct(item) = item else { continue };
+            if !item.attrs.iter().any(|attr| attr.path().is_ident(ATTRIBUTE)) {
+                continue;
+            }
+            let mut table = struct_table(item, dialect).map_err(|e| rust_error(source, e))?;
+            table.source = source.map(str::to_string);
+            if let Some(existing) = self.table(&table.name) {
+                return Err(ParserError::DuplicateTable {
+                    name: table.name,
+                    first: existing.source.clone(),
+                    second: table.source,
+                });
+            }
+            self.tables.push(table);
+        }
+        self.resolve_foreign_keys();
+        Ok(())
+    }
+
+    /// `CREATE TABLE` and `CREATE INDEX` statements of the schema, the referenced tables first.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use noorm::prelude::*;
+    /// use noorm::parser::Schema;
+    ///
+    /// let sql = "CREATE TABLE office ( id INT PRIMARY KEY, person_id INT REFERENCES person(id) );
+    ///     CREATE TABLE person ( id INT PRIMARY KEY )";
+    /// let schema = Schema::from_sql(sql, &Dialect::Generic).unwrap();
+    /// assert!(schema.create_sql(&Dialect::Generic).starts_with("CREATE TABLE person"));
+    /// ```
+    pub fn create_sql(&self, dialect: &Dialect) -> String {
+        diff::dependency_order(self.tables.iter().collect()).iter()
+            .flat_map(|table| table.create_statements(dialect))
+            .map(|statement| format!("{};\n", statement))
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+}
+
+/// Table of an annotated struct
+fn struct_table(item: &ItemStruct, dialect: &Dialect) -> Result<Table, syn::Error> {
+    let mut name = snake_case(&item.ident.unraw().to_string());
+    for attr in item.attrs.iter().filter(|attr| attr.path().is_ident(ATTRIBUTE)) {
+        if matches!(attr.meta, syn::Meta::Path(_)) {
+            continue;
+        }
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("table") {
+                name = meta.value()?.parse::<LitStr>()?.value();
+                Ok(())
+            } else {
+                Err(meta.error("unknown noorm struct attribute, expected `table`"))
+            }
+        })?;
+    }
+    let mut table = Table {
+        name,
+        kind: TableKind::Table,
+        columns: Vec::new(),
+        primary_key: Vec::new(),
+        unique_keys: Vec::new(),
+        indexes: Vec::new(),
+        foreign_keys: Vec::new(),
+        comment: doc_comment(&item.attrs),
+        source: None,
+        definition: None,
+    };
+    let syn::Fields::Named(fields) = &item.fields else {
+        return Err(syn::Error::new_spanned(&item.ident, format!("struct {} needs named fields to be a table", item.ident)));
+    };
+    for field in &fields.named {
+        let options = field_options(&field.attrs)?;
+        if options.skip {
+            continue;
+        }
+        let ident = field.ident.as_ref().expect("named field");
+        let name = options.column.clone().unwrap_or_else(|| ident.unraw().to_string());
+        let (rust_type, nullable) = match option_inner(&field.ty) {
+            Some(inner) => (inner, true),
+            None => (&field.ty, false),
+        };
+        let is_key = options.primary_key || options.unique;
+        let data_type = match &options.sql_type {
+            Some(sql_type) => parse_sql(sql_type, dialect, |parser| parser.parse_data_type())
+                .map_err(|e| syn::Error::new_spanned(&field.ty, format!("invalid sql_type of {}.{}: {}", table.name, name, e)))?,
+            None => rust_sql_type(rust_type, is_key, dialect).ok_or_else(|| syn::Error::new_spanned(
+                &field.ty,
+                format!("no SQL type for the Rust type of {}.{}, set one with #[noorm(sql_type = \"...\")]", table.name, name),
+            ))?,
+        };
+        if options.auto_increment && !is_integer_type(&data_type) {
+            return Err(syn::Error::new_spanned(
+                &field.ty,
+                format!("auto_increment needs an integer column, {}.{} is {}", table.name, name, data_type),
+            ));
+        }
+        let mut column = Column {
+            name: name.clone(),
+            data_type,
+            nullable,
+            default: None,
+            generated: options.auto_increment,
+            comment: doc_comment(&field.attrs),
+        };
+        if let Some(default) = &options.default {
+            let expr = parse_sql(default, dialect, |parser| parser.parse_expr())
+                .map_err(|e| syn::Error::new_spanned(ident, format!("invalid default of {}.{}: {}", table.name, name, e)))?;
+            column.default = Some(expr);
+            column.generated |= column.default_value().is_none();
+        }
+        table.columns.push(column);
+        if options.primary_key {
+            table.primary_key.push(name.clone());
+        }
+        if options.unique {
+            table.unique_keys.push(vec![name.clone()]);
+        }
+        if options.index {
+            table.indexes.push(Index { name: None, columns: vec![name.clone()], unique: false, predicate: None });
+        }
+        if let Some(references) = &options.references {
+            let (foreign_table, referred_columns) = match references.split_once('(') {
+                Some((foreign_table, columns)) => (
+                    foreign_table.trim().to_string(),
+                    columns.trim_end_matches(')').split(',').map(|c| c.trim().to_string()).collect(),
+                ),
+                None => (references.trim().to_string(), Vec::new()),
+            };
+            table.foreign_keys.push(ForeignKey {
+                name: None,
+                columns: vec![name],
+                foreign_table,
+                referred_columns,
+                on_delete: None,
+                on_update: None,
+            });
+        }
+    }
+    Ok(table)
+}
+
+/// Options of the `#[noorm(...)]` attributes of a field
+fn field_options(attrs: &[Attribute]) -> Result<FieldOptions, syn::Error> {
+    let mut options = FieldOptions::default();
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident(ATTRIBUTE)) {
+        attr.parse_nested_meta(|meta| {
+            let path = &meta.path;
+            if path.is_ident("primary_key") {
+                options.primary_key = true;
+            } else if path.is_ident("auto_increment") {
+                options.auto_increment = true;
+            } else if path.is_ident("unique") {
+                options.unique = true;
+            } else if path.is_ident("index") {
+                options.index = true;
+            } else if path.is_ident("skip") {
+                options.skip = true;
+            } else if path.is_ident("column") {
+                options.column = Some(meta.value()?.parse::<LitStr>()?.value());
+            } else if path.is_ident("sql_type") {
+                options.sql_type = Some(meta.value()?.parse::<LitStr>()?.value());
+            } else if path.is_ident("default") {
+                options.default = Some(meta.value()?.parse::<LitStr>()?.value());
+            } else if path.is_ident("references") {
+                options.references = Some(meta.value()?.parse::<LitStr>()?.value());
+            } else {
+                return Err(meta.error(
+                    "unknown noorm field attribute, expected one of `primary_key`, `auto_increment`, `unique`, `index`, \
+                     `skip`, `column`, `sql_type`, `default`, `references`",
+                ));
+            }
+            Ok(())
+        })?;
+    }
+    Ok(options)
+}
+
+/// SQL type of a Rust type, `None` if there is no obvious one
+fn rust_sql_type(rust_type: &Type, is_key: bool, dialect: &Dialect) -> Option<DataType> {
+    let Type::Path(type_path) = rust_type else {
+        return match rust_type {
+            Type::Reference(reference) => rust_sql_type(&reference.elem, is_key, dialect),
+            _ => None,
+        };
+    };
+    let segment = type_path.path.segments.last()?;
+    // SQLite only auto-increments columns declared `INTEGER`
+    let integer = |data_type: DataType| if *dialect == Dialect::SQLite { DataType::Integer(None) } else { data_type };
+    let data_type = match segment.ident.to_string().as_str() {
+        "i8" | "u8" | "i16" => integer(DataType::SmallInt(None)),
+        "u16" | "i32" => integer(DataType::Integer(None)),
+        "u32" | "i64" | "isize" => integer(DataType::BigInt(None)),
+        // Beyond the range of BIGINT
+        "u64" | "usize" => DataType::Numeric(ExactNumberInfo::Precision(20)),
+        "f32" => DataType::Real,
+        "f64" => DataType::DoublePrecision,
+        "bool" => DataType::Boolean,
+        "char" => DataType::Char(Some(CharacterLength::IntegerLength { length: 1, unit: None })),
+        // MySQL cannot index TEXT columns without a prefix length
+        "String" | "str" if is_key && *dialect == Dialect::MySQL => {
+            DataType::Varchar(Some(CharacterLength::IntegerLength { length: 255, unit: None }))
+        }
+        "String" | "str" => DataType::Text,
+        "Vec" if generic_argument(segment).is_some_and(|inner| is_ident(inner, "u8")) => match dialect {
+            Dialect::Generic | Dialect::PostgreSQL => DataType::Bytea,
+            Dialect::MySQL | Dialect::SQLite => DataType::Blob(None),
+        },
+        "NaiveDate" | "Date" => DataType::Date,
+        "NaiveTime" | "Time" => DataType::Time(None, TimezoneInfo::None),
+        "NaiveDateTime" | "PrimitiveDateTime" if *dialect == Dialect::MySQL => DataType::Datetime(None),
+        "NaiveDateTime" | "PrimitiveDateTime" => DataType::Timestamp(None, TimezoneInfo::None),
+        "DateTime" | "OffsetDateTime" => match dialect {
+            Dialect::Generic | Dialect::PostgreSQL => DataType::Timestamp(None, TimezoneInfo::WithTimeZone),
+            Dialect::MySQL | Dialect::SQLite => DataType::Timestamp(None, TimezoneInfo::None),
+        },
+        "Uuid" => match dialect {
+            Dialect::Generic | Dialect::PostgreSQL => DataType::Uuid,
+            Dialect::MySQL | Dialect::SQLite => DataType::Char(Some(CharacterLength::IntegerLength { length: 36, unit: None })),
+        },
+        "Decimal" => DataType::Numeric(ExactNumberInfo::None),
+        "Value" if type_path.path.segments.len() > 1 || *dialect != Dialect::PostgreSQL => DataType::JSON,
+        "Value" => DataType::JSONB,
+        _ => return None,
+    };
+    Some(data_type)
+}
+
+/// Integer types, the only ones a database numbers
+fn is_integer_type(data_type: &DataType) -> bool {
+    is_serial_type(data_type) || matches!(
+        data_type,
+        DataType::TinyInt(_) | DataType::SmallInt(_) | DataType::MediumInt(_) | DataType::Int(_) | DataType::Integer(_)
+            | DataType::BigInt(_) | DataType::Int2(_) | DataType::Int4(_) | DataType::Int8(_)
+            | DataType::UnsignedTinyInt(_) | DataType::UnsignedSmallInt(_) | DataType::UnsignedMediumInt(_)
+            | DataType::UnsignedInt(_) | DataType::UnsignedInteger(_) | DataType::UnsignedBigInt(_)
+            | DataType::UnsignedInt2(_) | DataType::UnsignedInt4(_) | DataType::UnsignedInt8(_)
+    )
+}
+
+/// Type inside an `Option`, if the type is one
+fn option_inner(rust_type: &Type) -> Option<&Type> {
+    let Type::Path(type_path) = rust_type else { return None };
+    let segment = type_path.path.segments.last()?;
+    if segment.ident != "Option" {
+        return None;
+    }
+    generic_argument(segment)
+}
+
+/// First generic type argument of a path segment, e.g. `T` of `Vec<T>`
+fn generic_argument(segment: &syn::PathSegment) -> Option<&Type> {
+    let PathArguments::AngleBracketed(arguments) = &segment.arguments else { return None };
+    arguments.args.iter().find_map(|argument| match argument {
+        GenericArgument::Type(inner) => Some(inner),
+        _ => None,
+    })
+}
+
+fn is_ident(rust_type: &Type, name: &str) -> bool {
+    matches!(rust_type, Type::Path(type_path) if type_path.path.is_ident(name))
+}
+
+/// Comment of the `///` doc attributes, one line each
+fn doc_comment(attrs: &[Attribute]) -> Option<String> {
+    let lines: Vec<String> = attrs.iter()
+        .filter(|attr| attr.path().is_ident("doc"))
+        .filter_map(|attr| match &attr.meta {
+            syn::Meta::NameValue(name_value) => match &name_value.value {
+                Expr::Lit(ExprLit { lit: Lit::Str(doc), .. }) => Some(doc.value().trim().to_string()),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect();
+    if lines.is_empty() { None } else { Some(lines.join("\n")) }
+}
+
+/// `snake_case` of a struct name, e.g. `office_desk` for `OfficeDesk`
+fn snake_case(name: &str) -> String {
+    let mut snake = String::new();
+    for (i, c) in name.chars().enumerate() {
+        if c.is_uppercase() {
+            if i > 0 && !snake.ends_with('_') {
+                snake.push('_');
+            }
+            snake.extend(c.to_lowercase());
+        } else {
+            snake.push(c);
+        }
+    }
+    snake
+}
+
+/// Parse a fragment of SQL of an attribute, e.g. a type or an expression
+fn parse_sql<T>(
+    sql: &str,
+    dialect: &Dialect,
+    parse: impl FnOnce(&mut Parser) -> Result<T, sqlparser::parser::ParserError>,
+) -> Result<T, sqlparser::parser::ParserError> {
+    let sql_dialect = dialect.sqlparser_dialect();
+    let mut parser = Parser::new(sql_dialect.as_ref()).try_with_sql(sql)?;
+    let parsed = parse(&mut parser)?;
+    // Nothing may follow, e.g. `INT garbage` is not a type
+    parser.expect_token(&Token::EOF)?;
+    Ok(parsed)
+}
+
+/// Error of a Rust source, with the line of the problem
+fn rust_error(source: Option<&str>, error: syn::Error) -> ParserError {
+    let line = error.span().start().line;
+    ParserError::Rust {
+        location: match source {
+            Some(source) => format!("{}:{}", source, line),
+            None => format!("line {}", line),
+        },
+        message: error.to_string(),
+    }
+}
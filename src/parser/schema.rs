@@ -0,0 +1,1146 @@
+use std::fmt;
+use serde::{Deserialize, Serialize};
+use sqlparser::ast::{
+    AlterColumnOperation, AlterTableOperation, ColumnDef, ColumnOption, ColumnOptionDef, CommentDef, CommentObject, DataType, Expr,
+    Ident, ObjectName, ObjectType, ReferentialAction, SelectItem, SetExpr, Statement, TableConstraint, UnaryOperator, Value,
+};
+use sqlparser::keywords::Keyword;
+use sqlparser::parser::Parser;
+use sqlparser::tokenizer::{Token, Tokenizer, Whitespace};
+
+use super::{diff, errors::ParserError, schema_format, views, Dialect};
+
+/// Database schema extracted from SQL statements.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Schema {
+    /// Tables defined through `CREATE TABLE`, and views through `CREATE [MATERIALIZED] VIEW`
+    pub tables: Vec<Table>,
+}
+
+/// A table or view of the schema.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Table {
+    /// Table name
+    pub name: String,
+    /// Table or view
+    #[serde(default)]
+    pub kind: TableKind,
+    /// Table columns, in declaration order
+    pub columns: Vec<Column>,
+    /// Names of the primary key columns, in key order
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub primary_key: Vec<String>,
+    /// Column sets with a `UNIQUE` constraint
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub unique_keys: Vec<Vec<String>>,
+    /// Indexes of the table, both inline and from `CREATE INDEX`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub indexes: Vec<Index>,
+    /// Foreign keys referencing other tables
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub foreign_keys: Vec<ForeignKey>,
+    /// Documentation from `COMMENT` clauses or leading SQL comments
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    /// Name of the source creating the table, e.g. the migration file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    /// `CREATE VIEW` statement of a view
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub definition: Option<String>,
+}
+
+/// Kind of relation defined in the schema.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TableKind {
+    /// `CREATE TABLE`
+    #[default]
+    Table,
+    /// `CREATE VIEW`, with the columns inferred from its query
+    View,
+    /// `CREATE MATERIALIZED VIEW`, with the columns inferred from its query
+    MaterializedView,
+}
+
+/// A foreign key of a table.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ForeignKey {
+    /// Constraint name
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Referencing columns
+    pub columns: Vec<String>,
+    /// Referenced table
+    pub foreign_table: String,
+    /// Referenced columns, the primary key of `foreign_table` if not given
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub referred_columns: Vec<String>,
+    /// `ON DELETE` action
+    #[serde(default, skip_serializing_if = "Option::is_none", with = "schema_format::referential_action")]
+    pub on_delete: Option<ReferentialAction>,
+    /// `ON UPDATE` action
+    #[serde(default, skip_serializing_if = "Option::is_none", with = "schema_format::referential_action")]
+    pub on_update: Option<ReferentialAction>,
+}
+
+/// Kind of relation between two tables.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RelationKind {
+    /// Each row references one row of the related table
+    ManyToOne,
+    /// Each row is referenced by many rows of the related table
+    OneToMany,
+    /// Rows are linked to many rows of the related table through a join table
+    ManyToMany,
+}
+
+/// A relation from a table to a related table.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Relation {
+    /// Kind of relation
+    pub kind: RelationKind,
+    /// Table the relation starts from
+    pub table: String,
+    /// Columns of `table` matched
+    pub columns: Vec<String>,
+    /// Related table
+    pub related_table: String,
+    /// Columns of `related_table` matched, through `join` for many-to-many relations
+    pub related_columns: Vec<String>,
+    /// Join table of many-to-many relations
+    pub join: Option<JoinTable>,
+}
+
+/// Join table of a many-to-many relation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct JoinTable {
+    /// Join table name
+    pub name: String,
+    /// Join table columns referencing the starting table
+    pub columns: Vec<String>,
+    /// Join table columns referencing the related table
+    pub related_columns: Vec<String>,
+}
+
+/// An index of a table.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Index {
+    /// Index name
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Indexed columns, or expressions for expression indexes
+    pub columns: Vec<String>,
+    /// The index is `UNIQUE`
+    #[serde(default)]
+    pub unique: bool,
+    /// `WHERE` predicate of a partial index
+    #[serde(default, skip_serializing_if = "Option::is_none", with = "schema_format::expression")]
+    pub predicate: Option<Expr>,
+}
+
+/// An indexed access path to the rows of a table.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Lookup<'a> {
+    /// Columns to look up by
+    pub columns: Vec<&'a Column>,
+    /// At most one row matches
+    pub unique: bool,
+    /// Predicate of the partial index, to be added to the lookup condition
+    pub predicate: Option<&'a Expr>,
+}
+
+/// A column of a table.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Column {
+    /// Column name
+    pub name: String,
+    /// SQL data type
+    #[serde(with = "schema_format::data_type")]
+    pub data_type: DataType,
+    /// The column is declared `NULL`
+    #[serde(default)]
+    pub nullable: bool,
+    /// `DEFAULT` expression of the column
+    #[serde(default, skip_serializing_if = "Option::is_none", with = "schema_format::expression")]
+    pub default: Option<Expr>,
+    /// The value is generated by the database (`SERIAL`, `AUTO_INCREMENT`, `GENERATED ...`,
+    /// or a `DEFAULT` that is not a literal, such as `now()`)
+    #[serde(default)]
+    pub generated: bool,
+    /// Documentation from `COMMENT` clauses or leading SQL comments
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+}
+
+/// Literal value of a column `DEFAULT`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DefaultValue {
+    /// `NULL`
+    Null,
+    /// `TRUE` or `FALSE`
+    Boolean(bool),
+    /// A number, as written in the SQL
+    Number(String),
+    /// A quoted string
+    String(String),
+}
+
+/// Leading SQL comments of a `CREATE TABLE` statement and of its columns
+#[derive(Debug, Default)]
+struct SqlComments {
+    table: String,
+    comment: Option<String>,
+    columns: Vec<(String, String)>,
+}
+
+impl Schema {
+    /// Create a new Schema from SQL statements.
+    ///
+    /// # Arguments
+    ///
+    /// * `sql` - The SQL statements to analyze.
+    /// * `dialect` - The Dialect used to parse the statements.
+    ///
+    /// # Errors
+    ///
+    /// * Can return a `ParserError` if the SQL parsing fails
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use noorm::prelude::*;
+    /// use noorm::parser::Schema;
+    ///
+    /// let sql = "CREATE TABLE person ( id SERIAL, name VARCHAR(255) )";
+    /// let schema = Schema::from_sql(sql, &Dialect::Generic).unwrap();
+    /// assert!(schema.tables[0].columns[0].generated);
+    /// ```
+    pub fn from_sql(sql: &str, dialect: &Dialect) -> Result<Self, ParserError> {
+        let mut schema = Schema::default();
+        schema.add_sql(sql, None, dialect)?;
+        Ok(schema)
+    }
+
+    /// Create a new Schema from named sources of SQL statements, e.g. migration files,
+    /// applied in order.
+    ///
+    /// The tables remember the name of the source creating them. A table created twice is
+    /// an error, unless the second statement is a `CREATE TABLE IF NOT EXISTS`, a
+    /// `CREATE OR REPLACE VIEW`, or follows a `DROP`.
+    ///
+    /// # Errors
+    ///
+    /// * `ParserError::SqlFile` if a source fails to parse, with its name
+    /// * `ParserError::DuplicateTable` if a table is created twice
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use noorm::prelude::*;
+    /// use noorm::parser::Schema;
+    ///
+    /// let sources = [
+    ///     ("001_person.sql", "CREATE TABLE person ( id INTEGER PRIMARY KEY )"),
+    ///     ("002_office.sql", "CREATE TABLE office ( id INTEGER, person_id INTEGER REFERENCES person )"),
+    /// ];
+    /// let schema = Schema::from_sources(sources, &Dialect::Generic).unwrap();
+    /// assert_eq!(schema.table("office").unwrap().source.as_deref(), Some("002_office.sql"));
+    /// ```
+    pub fn from_sources<'a>(sources: impl IntoIterator<Item = (&'a str, &'a str)>, dialect: &Dialect) -> Result<Self, ParserError> {
+        let mut schema = Schema::default();
+        for (name, sql) in sources {
+            schema.add_sql(sql, Some(name), dialect)?;
+        }
+        Ok(schema)
+    }
+
+    /// Apply more SQL statements to the schema, e.g. the next migration, as `Schema::from_sources` does.
+    ///
+    /// # Errors
+    ///
+    /// * `ParserError::SqlFile` if a named source fails to parse, `ParserError::Sql` for an unnamed one
+    /// * `ParserError::DuplicateTable` if a table is created twice
+    pub fn add_sql(&mut self, sql: &str, source: Option<&str>, dialect: &Dialect) -> Result<(), ParserError> {
+        let statements = Parser::parse_sql(dialect.sqlparser_dialect().as_ref(), sql).map_err(|error| match source {
+            Some(name) => ParserError::SqlFile { path: name.into(), source: error },
+            None => ParserError::Sql(error),
+        })?;
+        self.add_statements(statements, sql, source, dialect)?;
+        self.resolve_foreign_keys();
+        Ok(())
+    }
+
+    /// Apply the statements parsed from `sql`, coming from the `source`, to the schema
+    pub(super) fn add_statements(&mut self, statements: Vec<Statement>, sql: &str, source: Option<&str>, dialect: &Dialect) -> Result<(), ParserError> {
+        let sql_comments = sql_comments(sql, dialect);
+        let schema = self;
+        for mut stmt in statements {
+            if dialect == &Dialect::PostgreSQL {
+                fold_unquoted_names(&mut stmt);
+            }
+            let definition = view_definition(&stmt);
+            match stmt {
+                Statement::CreateTable(create_table) => {
+                    let name = object_name(&create_table.name);
+                    if create_table.if_not_exists && schema.table(&name).is_some() {
+                        continue;
+                    }
+                    if !create_table.or_replace {
+                        schema.check_not_defined(&name, source)?;
+                    }
+                    schema.tables.retain(|t| !t.name.eq_ignore_ascii_case(&name));
+                    let mut table = Table {
+                        name,
+                        kind: TableKind::Table,
+                        columns: create_table.columns.iter().map(Column::from_def).collect(),
+                        primary_key: Vec::new(),
+                        unique_keys: Vec::new(),
+                        indexes: Vec::new(),
+                        foreign_keys: Vec::new(),
+                        comment: create_table.comment.as_ref().map(|comment| match comment {
+                            CommentDef::WithEq(text) | CommentDef::WithoutEq(text) | CommentDef::AfterColumnDefsWithoutEq(text) => text.clone(),
+                        }),
+                        source: source.map(str::to_string),
+                        definition: None,
+                    };
+                    table.add_sql_comments(&sql_comments);
+                    for column in &create_table.columns {
+                        table.add_column_constraints(column);
+                    }
+                    for constraint in &create_table.constraints {
+                        table.add_constraint(constraint);
+                    }
+                    schema.tables.push(table);
+                }
+                Statement::CreateView { or_replace, if_not_exists, name, materialized, columns, query, comment, .. } => {
+                    let name = object_name(&name);
+                    if if_not_exists && schema.table(&name).is_some() {
+                        continue;
+                    }
+                    if !or_replace {
+                        schema.check_not_defined(&name, source)?;
+                    }
+                    let mut view_columns = views::query_columns(schema, &query);
+                    for column in &mut view_columns {
+                        column.default = None;
+                        column.generated = false;
+                    }
+                    for (column, def) in view_columns.iter_mut().zip(&columns) {
+                        column.name = def.name.value.clone();
+                        if let Some(data_type) = &def.data_type {
+                            column.data_type = data_type.clone();
+                        }
+                    }
+                    schema.tables.retain(|t| !t.name.eq_ignore_ascii_case(&name));
+                    let mut view = Table {
+                        name,
+                        kind: if materialized { TableKind::MaterializedView } else { TableKind::View },
+                        columns: view_columns,
+                        primary_key: Vec::new(),
+                        unique_keys: Vec::new(),
+                        indexes: Vec::new(),
+                        foreign_keys: Vec::new(),
+                        comment,
+                        source: source.map(str::to_string),
+                        definition,
+                    };
+                    view.add_sql_comments(&sql_comments);
+                    schema.tables.push(view);
+                }
+                Statement::Drop { object_type: ObjectType::Table | ObjectType::View, names, .. } => {
+                    for name in &names {
+                        let name = object_name(name);
+                        schema.tables.retain(|t| !t.name.eq_ignore_ascii_case(&name));
+                    }
+                }
+                Statement::Drop { object_type: ObjectType::Index, names, .. } => {
+                    for name in &names {
+                        let name = object_name(name);
+                        for table in &mut schema.tables {
+                            let table_name = table.name.clone();
+                            table.indexes.retain(|index| !diff::index_name(&table_name, index, dialect).eq_ignore_ascii_case(&name));
+                        }
+                    }
+                }
+                Statement::AlterTable { name, operations, .. } => {
+                    let mut name = object_name(&name);
+                    for operation in &operations {
+                        name = schema.alter_table(&name, operation, dialect);
+                    }
+                }
+                Statement::CreateIndex(create_index) => {
+                    let table_name = object_name(&create_index.table_name);
+                    if let Some(table) = schema.table_mut(&table_name) {
+                        table.indexes.push(Index {
+                            name: create_index.name.as_ref().map(object_name),
+                            columns: create_index.columns.iter().map(|c| match &c.expr {
+                                Expr::Identifier(ident) => ident.value.clone(),
+                                expr => expr.to_string(),
+                            }).collect(),
+                            unique: create_index.unique,
+                            predicate: create_index.predicate.clone(),
+                        });
+                    }
+                }
+                Statement::Comment { object_type, object_name, comment, .. } => {
+                    let names = &object_name.0;
+                    match object_type {
+                        CommentObject::Table => {
+                            if let Some(table) = schema.table_mut(&names.last().map(|n| n.value.clone()).unwrap_or_default()) {
+                                table.comment = comment;
+                            }
+                        }
+                        CommentObject::Column => {
+                            if let [.., table_name, column_name] = names.as_slice() {
+                                let column = schema.table_mut(&table_name.value)
+                                    .and_then(|table| table.columns.iter_mut().find(|c| c.name.eq_ignore_ascii_case(&column_name.value)));
+                                if let Some(column) = column {
+                                    column.comment = comment;
+                                }
+                            }
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Apply an `ALTER TABLE` operation, returning the name of the table after it
+    fn alter_table(&mut self, name: &str, operation: &AlterTableOperation, dialect: &Dialect) -> String {
+        match operation {
+            AlterTableOperation::RenameTable { table_name } => {
+                let new_name = object_name(table_name);
+                for foreign_key in self.tables.iter_mut().flat_map(|t| &mut t.foreign_keys) {
+                    if foreign_key.foreign_table.eq_ignore_ascii_case(name) {
+                        foreign_key.foreign_table = new_name.clone();
+                    }
+                }
+                if let Some(table) = self.table_mut(name) {
+                    table.name = new_name.clone();
+                }
+                return new_name;
+            }
+            AlterTableOperation::RenameColumn { old_column_name, new_column_name } => {
+                self.rename_column(name, &old_column_name.value, &new_column_name.value);
+            }
+            AlterTableOperation::ChangeColumn { old_name, new_name, data_type, options, .. } => {
+                self.rename_column(name, &old_name.value, &new_name.value);
+                if let Some(table) = self.table_mut(name) {
+                    table.modify_column(new_name, data_type, options);
+                }
+            }
+            _ => {
+                if let Some(table) = self.table_mut(name) {
+                    table.alter(operation, dialect);
+                }
+            }
+        }
+        name.to_string()
+    }
+
+    /// Rename a column of a table, in its keys and in the foreign keys referencing it
+    fn rename_column(&mut self, table_name: &str, from: &str, to: &str) {
+        fn rename(names: &mut [String], from: &str, to: &str) {
+            for name in names.iter_mut().filter(|name| name.eq_ignore_ascii_case(from)) {
+                *name = to.to_string();
+            }
+        }
+        for table in &mut self.tables {
+            if table.name.eq_ignore_ascii_case(table_name) {
+                if let Some(column) = table.columns.iter_mut().find(|c| c.name.eq_ignore_ascii_case(from)) {
+                    column.name = to.to_string();
+                }
+                rename(&mut table.primary_key, from, to);
+                table.unique_keys.iter_mut().for_each(|key| rename(key, from, to));
+                table.indexes.iter_mut().for_each(|index| rename(&mut index.columns, from, to));
+                table.foreign_keys.iter_mut().for_each(|fk| rename(&mut fk.columns, from, to));
+            }
+            for foreign_key in table.foreign_keys.iter_mut().filter(|fk| fk.foreign_table.eq_ignore_ascii_case(table_name)) {
+                rename(&mut foreign_key.referred_columns, from, to);
+            }
+        }
+    }
+
+    /// Fail if a table or view is already defined with the name
+    fn check_not_defined(&self, name: &str, source: Option<&str>) -> Result<(), ParserError> {
+        match self.table(name) {
+            Some(table) => Err(ParserError::DuplicateTable {
+                name: name.to_string(),
+                first: table.source.clone(),
+                second: source.map(str::to_string),
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// Point the foreign keys without referred columns to the primary key of the foreign table
+    pub(super) fn resolve_foreign_keys(&mut self) {
+        let primary_keys: Vec<(String, Vec<String>)> = self.tables.iter()
+            .map(|t| (t.name.clone(), t.primary_key.clone()))
+            .collect();
+        for table in &mut self.tables {
+            for foreign_key in &mut table.foreign_keys {
+                if foreign_key.referred_columns.is_empty() {
+                    if let Some((_, primary_key)) = primary_keys.iter().find(|(name, _)| name.eq_ignore_ascii_case(&foreign_key.foreign_table)) {
+                        foreign_key.referred_columns = primary_key.clone();
+                    }
+                }
+            }
+        }
+    }
+
+    /// Subset of the tables matching any of the `include` patterns, all if none is given,
+    /// and none of the `exclude` ones. Patterns are case insensitive names with `*` wildcards.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use noorm::prelude::*;
+    /// use noorm::parser::Schema;
+    ///
+    /// let sql = "CREATE TABLE person ( id INT ); CREATE TABLE person_log ( id INT ); CREATE TABLE office ( id INT )";
+    /// let schema = Schema::from_sql(sql, &Dialect::Generic).unwrap();
+    /// let subset = schema.subset(&["person*"], &["*_log"]);
+    /// assert_eq!(subset.tables.iter().map(|t| t.name.as_str()).collect::<Vec<_>>(), vec!["person"]);
+    /// ```
+    pub fn subset<S: AsRef<str>>(&self, include: &[S], exclude: &[S]) -> Schema {
+        let matches = |patterns: &[S], name: &str| patterns.iter().any(|pattern| matches_pattern(pattern.as_ref(), name));
+        Schema {
+            tables: self.tables.iter()
+                .filter(|table| include.is_empty() || matches(include, &table.name))
+                .filter(|table| !matches(exclude, &table.name))
+                .cloned()
+                .collect(),
+        }
+    }
+
+    /// Relations between the tables, as seen from each table.
+    ///
+    /// Every foreign key gives a many-to-one relation from the referencing table and
+    /// a one-to-many relation from the referenced one. Join tables, having two foreign keys
+    /// and no other columns to supply, also give a many-to-many relation in both directions.
+    pub fn relations(&self) -> Vec<Relation> {
+        let mut relations = Vec::new();
+        for table in &self.tables {
+            for foreign_key in &table.foreign_keys {
+                relations.push(Relation {
+                    kind: RelationKind::ManyToOne,
+                    table: table.name.clone(),
+                    columns: foreign_key.columns.clone(),
+                    related_table: foreign_key.foreign_table.clone(),
+                    related_columns: foreign_key.referred_columns.clone(),
+                    join: None,
+                });
+                relations.push(Relation {
+                    kind: RelationKind::OneToMany,
+                    table: foreign_key.foreign_table.clone(),
+                    columns: foreign_key.referred_columns.clone(),
+                    related_table: table.name.clone(),
+                    related_columns: foreign_key.columns.clone(),
+                    join: None,
+                });
+            }
+            if let [first, second] = table.foreign_keys.as_slice() {
+                if table.is_join_table() {
+                    for (from, to) in [(first, second), (second, first)] {
+                        relations.push(Relation {
+                            kind: RelationKind::ManyToMany,
+                            table: from.foreign_table.clone(),
+                            columns: from.referred_columns.clone(),
+                            related_table: to.foreign_table.clone(),
+                            related_columns: to.referred_columns.clone(),
+                            join: Some(JoinTable {
+                                name: table.name.clone(),
+                                columns: from.columns.clone(),
+                                related_columns: to.columns.clone(),
+                            }),
+                        });
+                    }
+                }
+            }
+        }
+        relations
+    }
+
+    /// Relations starting from a table.
+    pub fn relations_of(&self, table_name: &str) -> Vec<Relation> {
+        self.relations().into_iter().filter(|r| r.table.eq_ignore_ascii_case(table_name)).collect()
+    }
+
+    /// Find a table by name.
+    pub fn table(&self, name: &str) -> Option<&Table> {
+        self.tables.iter().find(|t| t.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Find a table by name, for modification.
+    pub fn table_mut(&mut self, name: &str) -> Option<&mut Table> {
+        self.tables.iter_mut().find(|t| t.name.eq_ignore_ascii_case(name))
+    }
+}
+
+impl Table {
+    /// Fill in the comments not given by `COMMENT` clauses from the leading SQL comments
+    fn add_sql_comments(&mut self, sql_comments: &[SqlComments]) {
+        let Some(comments) = sql_comments.iter().find(|c| c.table.eq_ignore_ascii_case(&self.name)) else { return };
+        self.comment = self.comment.take().or_else(|| comments.comment.clone());
+        for (name, comment) in &comments.columns {
+            if let Some(column) = self.columns.iter_mut().find(|c| c.name.eq_ignore_ascii_case(name)) {
+                column.comment.get_or_insert_with(|| comment.clone());
+            }
+        }
+    }
+
+    /// Check if the table is a read-only view.
+    pub fn is_view(&self) -> bool {
+        self.kind != TableKind::Table
+    }
+
+    /// Add the keys declared in the options of a column
+    fn add_column_constraints(&mut self, column: &ColumnDef) {
+        for opt in &column.options {
+            match &opt.option {
+                ColumnOption::Unique { is_primary: true, .. } => self.primary_key.push(column.name.value.clone()),
+                ColumnOption::Unique { is_primary: false, .. } => self.unique_keys.push(vec![column.name.value.clone()]),
+                ColumnOption::ForeignKey { foreign_table, referred_columns, on_delete, on_update, .. } => {
+                    self.foreign_keys.push(ForeignKey {
+                        name: opt.name.as_ref().map(|n| n.value.clone()),
+                        columns: vec![column.name.value.clone()],
+                        foreign_table: object_name(foreign_table),
+                        referred_columns: referred_columns.iter().map(|c| c.value.clone()).collect(),
+                        on_delete: *on_delete,
+                        on_update: *on_update,
+                    });
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Apply an `ALTER TABLE` operation not involving other tables
+    fn alter(&mut self, operation: &AlterTableOperation, dialect: &Dialect) {
+        match operation {
+            AlterTableOperation::AddConstraint(constraint) => self.add_constraint(constraint),
+            AlterTableOperation::AddColumn { column_def, .. } if self.column(&column_def.name.value).is_none() => {
+                self.columns.push(Column::from_def(column_def));
+                self.add_column_constraints(column_def);
+            }
+            AlterTableOperation::DropColumn { column_name, .. } => {
+                let name = &column_name.value;
+                let has_column = |names: &[String]| names.iter().any(|n| n.eq_ignore_ascii_case(name));
+                self.columns.retain(|c| !c.name.eq_ignore_ascii_case(name));
+                if has_column(&self.primary_key) {
+                    self.primary_key.clear();
+                }
+                self.unique_keys.retain(|key| !has_column(key));
+                self.indexes.retain(|index| !has_column(&index.columns));
+                self.foreign_keys.retain(|fk| !has_column(&fk.columns));
+            }
+            AlterTableOperation::AlterColumn { column_name, op } => {
+                let Some(column) = self.columns.iter_mut().find(|c| c.name.eq_ignore_ascii_case(&column_name.value)) else { return };
+                match op {
+                    AlterColumnOperation::SetNotNull => column.nullable = false,
+                    AlterColumnOperation::DropNotNull => column.nullable = true,
+                    AlterColumnOperation::SetDefault { value } => {
+                        column.generated = is_serial_type(&column.data_type) || literal_value(value).is_none();
+                        column.default = Some(value.clone());
+                    }
+                    AlterColumnOperation::DropDefault => {
+                        column.generated = is_serial_type(&column.data_type);
+                        column.default = None;
+                    }
+                    AlterColumnOperation::SetDataType { data_type, .. } => column.data_type = data_type.clone(),
+                    _ => {}
+                }
+            }
+            AlterTableOperation::ModifyColumn { col_name, data_type, options, .. } => self.modify_column(col_name, data_type, options),
+            AlterTableOperation::DropConstraint { name, .. } => {
+                let table = self.name.clone();
+                let name = &name.value;
+                if name.eq_ignore_ascii_case(&format!("{}_pkey", table)) {
+                    self.primary_key.clear();
+                }
+                self.unique_keys.retain(|key| !diff::unique_key_name(&table, key, dialect).eq_ignore_ascii_case(name));
+                self.foreign_keys.retain(|fk| !diff::foreign_key_name(&table, fk).eq_ignore_ascii_case(name));
+            }
+            AlterTableOperation::DropPrimaryKey => self.primary_key.clear(),
+            _ => {}
+        }
+    }
+
+    /// Replace the definition of a column, e.g. through MySQL's `MODIFY COLUMN`
+    fn modify_column(&mut self, name: &Ident, data_type: &DataType, options: &[ColumnOption]) {
+        let definition = ColumnDef {
+            name: name.clone(),
+            data_type: data_type.clone(),
+            collation: None,
+            options: options.iter().map(|option| ColumnOptionDef { name: None, option: option.clone() }).collect(),
+        };
+        if let Some(column) = self.columns.iter_mut().find(|c| c.name.eq_ignore_ascii_case(&name.value)) {
+            let comment = column.comment.take();
+            *column = Column::from_def(&definition);
+            column.comment = column.comment.take().or(comment);
+        }
+    }
+
+    /// Add a table-level constraint
+    fn add_constraint(&mut self, constraint: &TableConstraint) {
+        match constraint {
+            TableConstraint::PrimaryKey { columns, .. } => {
+                self.primary_key = columns.iter().map(|c| c.value.clone()).collect();
+            }
+            TableConstraint::Unique { columns, .. } => {
+                self.unique_keys.push(columns.iter().map(|c| c.value.clone()).collect());
+            }
+            TableConstraint::Index { name, columns, .. } => self.indexes.push(Index {
+                name: name.as_ref().map(|n| n.value.clone()),
+                columns: columns.iter().map(|c| c.value.clone()).collect(),
+                unique: false,
+                predicate: None,
+            }),
+            TableConstraint::ForeignKey { name, columns, foreign_table, referred_columns, on_delete, on_update, .. } => {
+                self.foreign_keys.push(ForeignKey {
+                    name: name.as_ref().map(|n| n.value.clone()),
+                    columns: columns.iter().map(|c| c.value.clone()).collect(),
+                    foreign_table: object_name(foreign_table),
+                    referred_columns: referred_columns.iter().map(|c| c.value.clone()).collect(),
+                    on_delete: *on_delete,
+                    on_update: *on_update,
+                });
+            }
+            _ => {}
+        }
+    }
+
+    /// Check if the table only joins two other tables: it has two foreign keys,
+    /// and all the other columns are filled in by the database.
+    pub fn is_join_table(&self) -> bool {
+        self.foreign_keys.len() == 2
+            && self.columns.iter().all(|column| {
+                column.generated
+                    || column.has_default()
+                    || self.foreign_keys.iter().any(|fk| fk.columns.iter().any(|c| c.eq_ignore_ascii_case(&column.name)))
+            })
+    }
+
+    /// Columns that must be supplied when inserting a row.
+    pub fn insertable_columns(&self) -> impl Iterator<Item = &Column> {
+        self.columns.iter().filter(|c| !c.generated)
+    }
+
+    /// Columns of the primary key, in key order.
+    pub fn primary_key_columns(&self) -> impl Iterator<Item = &Column> {
+        self.primary_key.iter().filter_map(|key| self.column(key))
+    }
+
+    /// Columns that are not part of the primary key.
+    pub fn non_key_columns(&self) -> impl Iterator<Item = &Column> {
+        self.columns.iter().filter(|c| !self.is_primary_key(&c.name))
+    }
+
+    /// Find a column by name.
+    pub fn column(&self, name: &str) -> Option<&Column> {
+        self.columns.iter().find(|c| c.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Check if a column is part of the primary key.
+    pub fn is_primary_key(&self, name: &str) -> bool {
+        self.primary_key.iter().any(|key| key.eq_ignore_ascii_case(name))
+    }
+
+    /// Indexed access paths to the rows, besides the primary key.
+    ///
+    /// Unique keys come first, followed by the non-unique indexes; expression indexes
+    /// and column sets already covered by an earlier lookup are left out.
+    pub fn lookups(&self) -> Vec<Lookup<'_>> {
+        let unique_keys = self.unique_keys.iter().map(|columns| (columns, true, None));
+        let unique_indexes = self.indexes.iter()
+            .filter(|index| index.unique)
+            .map(|index| (&index.columns, true, index.predicate.as_ref()));
+        let indexes = self.indexes.iter()
+            .filter(|index| !index.unique)
+            .map(|index| (&index.columns, false, index.predicate.as_ref()));
+
+        let mut lookups: Vec<Lookup> = Vec::new();
+        for (names, unique, predicate) in unique_keys.chain(unique_indexes).chain(indexes) {
+            let columns: Option<Vec<&Column>> = names.iter().map(|name| self.column(name)).collect();
+            let Some(columns) = columns else { continue };
+            let same_columns = |other: &[&Column]| {
+                other.len() == columns.len() && other.iter().zip(&columns).all(|(a, b)| a.name == b.name)
+            };
+            let is_primary_key = same_columns(&self.primary_key_columns().collect::<Vec<_>>());
+            if columns.is_empty() || is_primary_key || lookups.iter().any(|l| same_columns(&l.columns)) {
+                continue;
+            }
+            lookups.push(Lookup { columns, unique, predicate });
+        }
+        lookups
+    }
+
+    /// Columns whose values are assigned by the database on insert and are only known
+    /// after it, the generated ones: literal `DEFAULT`s can be filled in beforehand.
+    pub fn database_assigned_columns(&self) -> impl Iterator<Item = &Column> {
+        self.columns.iter().filter(|c| c.generated)
+    }
+}
+
+impl Column {
+    fn from_def(column: &ColumnDef) -> Self {
+        let mut nullable = false;
+        let mut default = None;
+        let mut generated = is_serial_type(&column.data_type);
+        let mut comment = None;
+        for opt in &column.options {
+            match &opt.option {
+                ColumnOption::Null => nullable = true,
+                ColumnOption::Default(expr) => {
+                    generated |= literal_value(expr).is_none();
+                    default = Some(expr.clone());
+                }
+                ColumnOption::Generated { .. }
+                | ColumnOption::Materialized(_)
+                | ColumnOption::Alias(_) => generated = true,
+                ColumnOption::DialectSpecific(tokens) if tokens.iter().any(is_auto_increment) => generated = true,
+                ColumnOption::Comment(text) => comment = Some(text.clone()),
+                _ => {}
+            }
+        }
+        Self {
+            name: column.name.value.clone(),
+            data_type: column.data_type.clone(),
+            nullable,
+            default,
+            generated,
+            comment,
+        }
+    }
+
+    /// The column is omitted on insert and filled in by its literal `DEFAULT`.
+    pub fn has_default(&self) -> bool {
+        !self.generated && self.default.is_some()
+    }
+
+    /// Literal value of the `DEFAULT`, if any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use noorm::prelude::*;
+    /// use noorm::parser::{DefaultValue, Schema};
+    ///
+    /// let sql = "CREATE TABLE person ( status TEXT DEFAULT 'active', created TEXT DEFAULT now() )";
+    /// let schema = Schema::from_sql(sql, &Dialect::Generic).unwrap();
+    /// let columns = &schema.tables[0].columns;
+    /// assert_eq!(columns[0].default_value(), Some(DefaultValue::String("active".to_string())));
+    /// assert_eq!(columns[1].default_value(), None);
+    /// assert!(columns[1].generated);
+    /// ```
+    pub fn default_value(&self) -> Option<DefaultValue> {
+        self.default.as_ref().and_then(literal_value)
+    }
+}
+
+impl fmt::Display for RelationKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RelationKind::ManyToOne => write!(f, "many-to-one"),
+            RelationKind::OneToMany => write!(f, "one-to-many"),
+            RelationKind::ManyToMany => write!(f, "many-to-many"),
+        }
+    }
+}
+
+/// Relation listing, e.g. `office(person_id) -> person(id) [many-to-one]`
+impl fmt::Display for Relation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}({}) -> ", self.table, self.columns.join(", "))?;
+        if let Some(join) = &self.join {
+            write!(f, "{}({} | {}) -> ", join.name, join.columns.join(", "), join.related_columns.join(", "))?;
+        }
+        write!(f, "{}({}) [{}]", self.related_table, self.related_columns.join(", "), self.kind)
+    }
+}
+
+/// Collect the SQL comments documenting tables and columns.
+///
+/// Comments right before `CREATE TABLE` document the table, comments before a column
+/// definition, or after it on the same line, document the column.
+fn sql_comments(sql: &str, dialect: &Dialect) -> Vec<SqlComments> {
+    let sqlparser_dialect = dialect.sqlparser_dialect();
+    let tokens = Tokenizer::new(sqlparser_dialect.as_ref(), sql).tokenize().unwrap_or_default();
+
+    let mut result = Vec::new();
+    let mut current: Option<SqlComments> = None;
+    let mut pending: Vec<String> = Vec::new();
+    let mut statement_comment: Option<String> = None;
+    let mut after_create = false;
+    let mut depth = 0;
+    let mut in_body = false;
+    let mut expect_column = false;
+    let mut last_column: Option<String> = None;
+    let mut same_line = false;
+
+    for token in tokens {
+        match token {
+            Token::Whitespace(Whitespace::SingleLineComment { comment, .. }) => {
+                let text = comment.trim().to_string();
+                match (&mut current, &last_column) {
+                    (Some(table), Some(column)) if same_line && depth == 1 => {
+                        table.columns.push((column.clone(), text));
+                    }
+                    _ => pending.push(text),
+                }
+                same_line = false;
+            }
+            Token::Whitespace(Whitespace::MultiLineComment(comment)) => {
+                let text = comment.lines()
+                    .map(|line| line.trim().trim_start_matches('*').trim())
+                    .filter(|line| !line.is_empty())
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                pending.push(text);
+            }
+            Token::Whitespace(Whitespace::Newline) => same_line = false,
+            Token::Whitespace(_) => {}
+            Token::Word(word) if current.is_none() && depth == 0 => {
+                match word.keyword {
+                    Keyword::CREATE => {
+                        statement_comment = join_comments(&mut pending);
+                        after_create = true;
+                    }
+                    Keyword::TABLE | Keyword::VIEW if after_create => {
+                        current = Some(SqlComments { comment: statement_comment.take(), ..Default::default() });
+                    }
+                    _ => pending.clear(),
+                }
+            }
+            Token::Word(word) => {
+                if let Some(table) = &mut current {
+                    if !in_body && word.keyword == Keyword::AS {
+                        in_body = true;
+                    } else if !in_body {
+                        table.table = word.value;
+                    } else if depth == 1 && expect_column {
+                        expect_column = false;
+                        let is_constraint = matches!(
+                            word.keyword,
+                            Keyword::CONSTRAINT | Keyword::PRIMARY | Keyword::UNIQUE | Keyword::FOREIGN
+                                | Keyword::KEY | Keyword::INDEX | Keyword::CHECK
+                        );
+                        last_column = (!is_constraint).then(|| word.value.clone());
+                        if let (Some(column), Some(comment)) = (&last_column, join_comments(&mut pending)) {
+                            table.columns.push((column.clone(), comment));
+                        }
+                    }
+                }
+                pending.clear();
+                same_line = true;
+            }
+            Token::LParen => {
+                depth += 1;
+                expect_column = depth == 1 && current.is_some() && !in_body;
+                in_body |= current.is_some();
+                pending.clear();
+                same_line = true;
+            }
+            Token::RParen => {
+                depth -= 1;
+                pending.clear();
+                same_line = true;
+            }
+            Token::Comma => {
+                expect_column = depth == 1;
+                same_line = true;
+            }
+            Token::SemiColon => {
+                result.extend(current.take());
+                after_create = false;
+                in_body = false;
+                depth = 0;
+                last_column = None;
+                pending.clear();
+            }
+            _ => {
+                pending.clear();
+                same_line = true;
+            }
+        }
+    }
+    result.extend(current);
+    result
+}
+
+/// Join the pending comment lines into a single comment
+fn join_comments(pending: &mut Vec<String>) -> Option<String> {
+    let comment = pending.join("\n");
+    pending.clear();
+    (!comment.is_empty()).then_some(comment)
+}
+
+/// Value of a literal expression, possibly negated or in parentheses
+fn literal_value(expr: &Expr) -> Option<DefaultValue> {
+    match expr {
+        Expr::Value(Value::Null) => Some(DefaultValue::Null),
+        Expr::Value(Value::Boolean(value)) => Some(DefaultValue::Boolean(*value)),
+        Expr::Value(Value::Number(number, _)) => Some(DefaultValue::Number(number.clone())),
+        Expr::Value(Value::SingleQuotedString(text) | Value::DoubleQuotedString(text)) => Some(DefaultValue::String(text.clone())),
+        Expr::UnaryOp { op: UnaryOperator::Minus, expr } => match literal_value(expr) {
+            Some(DefaultValue::Number(number)) => Some(DefaultValue::Number(format!("-{}", number))),
+            _ => None,
+        },
+        Expr::Nested(expr) => literal_value(expr),
+        _ => None,
+    }
+}
+
+/// `CREATE VIEW` statement of a view, without `OR REPLACE` and `IF NOT EXISTS`
+fn view_definition(stmt: &Statement) -> Option<String> {
+    let Statement::CreateView { .. } = stmt else { return None };
+    let mut view = stmt.clone();
+    if let Statement::CreateView { or_replace, if_not_exists, .. } = &mut view {
+        *or_replace = false;
+        *if_not_exists = false;
+    }
+    Some(view.to_string())
+}
+
+/// Fold the unquoted names of the tables, columns and constraints to lowercase, as PostgreSQL
+/// does, so that the schema has the names of the database
+fn fold_unquoted_names(statement: &mut Statement) {
+    fn fold(ident: &mut Ident) {
+        if ident.quote_style.is_none() {
+            ident.value = ident.value.to_lowercase();
+        }
+    }
+    fn fold_object(name: &mut ObjectName) {
+        name.0.iter_mut().for_each(fold);
+    }
+    fn fold_column(column: &mut ColumnDef) {
+        fold(&mut column.name);
+        for option in &mut column.options {
+            option.name.iter_mut().for_each(fold);
+            if let ColumnOption::ForeignKey { foreign_table, referred_columns, .. } = &mut option.option {
+                fold_object(foreign_table);
+                referred_columns.iter_mut().for_each(fold);
+            }
+        }
+    }
+    fn fold_constraint(constraint: &mut TableConstraint) {
+        match constraint {
+            TableConstraint::PrimaryKey { name, columns, .. } | TableConstraint::Unique { name, columns, .. } => {
+                name.iter_mut().for_each(fold);
+                columns.iter_mut().for_each(fold);
+            }
+            TableConstraint::Index { name, columns, .. } => {
+                name.iter_mut().for_each(fold);
+                columns.iter_mut().for_each(fold);
+            }
+            TableConstraint::ForeignKey { name, columns, foreign_table, referred_columns, .. } => {
+                name.iter_mut().for_each(fold);
+                columns.iter_mut().for_each(fold);
+                fold_object(foreign_table);
+                referred_columns.iter_mut().for_each(fold);
+            }
+            _ => {}
+        }
+    }
+
+    match statement {
+        Statement::CreateTable(create_table) => {
+            fold_object(&mut create_table.name);
+            create_table.columns.iter_mut().for_each(fold_column);
+            create_table.constraints.iter_mut().for_each(fold_constraint);
+        }
+        Statement::CreateView { name, columns, query, .. } => {
+            fold_object(name);
+            columns.iter_mut().for_each(|column| fold(&mut column.name));
+            if let SetExpr::Select(select) = query.body.as_mut() {
+                for item in &mut select.projection {
+                    if let SelectItem::ExprWithAlias { alias, .. } = item {
+                        fold(alias);
+                    }
+                }
+            }
+        }
+        Statement::Drop { names, .. } => names.iter_mut().for_each(fold_object),
+        Statement::AlterTable { name, operations, .. } => {
+            fold_object(name);
+            for operation in operations {
+                match operation {
+                    AlterTableOperation::AddConstraint(constraint) => fold_constraint(constraint),
+                    AlterTableOperation::AddColumn { column_def, .. } => fold_column(column_def),
+                    AlterTableOperation::DropColumn { column_name, .. } | AlterTableOperation::AlterColumn { column_name, .. } => fold(column_name),
+                    AlterTableOperation::DropConstraint { name, .. } => fold(name),
+                    AlterTableOperation::RenameColumn { old_column_name, new_column_name } => {
+                        fold(old_column_name);
+                        fold(new_column_name);
+                    }
+                    AlterTableOperation::RenameTable { table_name } => fold_object(table_name),
+                    _ => {}
+                }
+            }
+        }
+        Statement::CreateIndex(create_index) => {
+            create_index.name.iter_mut().for_each(fold_object);
+            fold_object(&mut create_index.table_name);
+            for column in &mut create_index.columns {
+                if let Expr::Identifier(ident) = &mut column.expr {
+                    fold(ident);
+                }
+            }
+        }
+        Statement::Comment { object_name, .. } => fold_object(object_name),
+        _ => {}
+    }
+}
+
+/// Unqualified name of a database object
+pub(super) fn object_name(name: &ObjectName) -> String {
+    name.0.last().map(|ident| ident.value.clone()).unwrap_or_default()
+}
+
+/// Match a name against a case insensitive pattern with `*` wildcards
+fn matches_pattern(pattern: &str, name: &str) -> bool {
+    let (pattern, name) = (pattern.to_lowercase(), name.to_lowercase());
+    let parts: Vec<&str> = pattern.split('*').collect();
+    let [first, middle @ .., last] = parts.as_slice() else {
+        return pattern == name;
+    };
+    let Some(mut rest) = name.strip_prefix(first) else { return false };
+    for part in middle {
+        match rest.find(part) {
+            Some(position) => rest = &rest[position + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
+
+/// Check if a type is one of the PostgreSQL auto-incrementing `SERIAL` pseudo-types
+pub fn is_serial_type(data_type: &DataType) -> bool {
+    match data_type {
+        DataType::Custom(name, _) => matches!(
+            name.to_string().to_uppercase().as_str(),
+            "SERIAL" | "BIGSERIAL" | "SMALLSERIAL" | "SERIAL2" | "SERIAL4" | "SERIAL8"
+        ),
+        _ => false,
+    }
+}
+
+/// Check for MySQL's `AUTO_INCREMENT` or SQLite's `AUTOINCREMENT`
+fn is_auto_increment(token: &Token) -> bool {
+    match token {
+        Token::Word(w) => {
+            let value = w.value.to_uppercase();
+            value == "AUTO_INCREMENT" || value == "AUTOINCREMENT"
+        }
+        _ => false,
+    }
+}
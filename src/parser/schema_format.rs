@@ -0,0 +1,205 @@
+use std::{fs, path::Path};
+use serde::{de, Deserialize, Deserializer, Serializer};
+use sqlparser::ast::{DataType, Expr, ReferentialAction};
+use sqlparser::parser::Parser;
+use sqlparser::tokenizer::Token;
+
+use super::{Dialect, ParserError, Schema};
+
+/// Check if a path is a JSON or YAML schema file, from its extension
+pub fn is_schema_file(path: impl AsRef<Path>) -> bool {
+    path.as_ref().extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ["json", "yaml", "yml"].iter().any(|format| ext.eq_ignore_ascii_case(format)))
+}
+
+impl Schema {
+    /// Serialize the schema to JSON.
+    ///
+    /// The schema has a list of `tables`, each one with its `name`, `kind` (`table`, `view` or
+    /// `materialized_view`) and `columns`, and when present its `primary_key`, `unique_keys`,
+    /// `indexes`, `foreign_keys`, `comment`, `source` and view `definition`. Columns have a `name`,
+    /// a `data_type` and a `default` in SQL, and the `nullable` and `generated` flags.
+    /// Index predicates are SQL, and foreign key actions are e.g. `CASCADE` or `SET NULL`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use noorm::prelude::*;
+    /// use noorm::parser::Schema;
+    ///
+    /// let schema = Schema::from_sql("CREATE TABLE person ( id INT PRIMARY KEY, name VARCHAR(100) NULL DEFAULT 'none' )", &Dialect::Generic).unwrap();
+    /// assert_eq!(schema.to_json(), r#"{
+    ///   "tables": [
+    ///     {
+    ///       "name": "person",
+    ///       "kind": "table",
+    ///       "columns": [
+    ///         {
+    ///           "name": "id",
+    ///           "data_type": "INT",
+    ///           "nullable": false,
+    ///           "generated": false
+    ///         },
+    ///         {
+    ///           "name": "name",
+    ///           "data_type": "VARCHAR(100)",
+    ///           "nullable": true,
+    ///           "default": "'none'",
+    ///           "generated": false
+    ///         }
+    ///       ],
+    ///       "primary_key": [
+    ///         "id"
+    ///       ]
+    ///     }
+    ///   ]
+    /// }"#);
+    /// assert_eq!(Schema::from_json(&schema.to_json()).unwrap(), schema);
+    /// ```
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+
+    /// Serialize the schema to YAML, in the format of `to_json`.
+    pub fn to_yaml(&self) -> String {
+        serde_yaml::to_string(self).unwrap_or_default()
+    }
+
+    /// Create a new Schema from its JSON serialization.
+    ///
+    /// Only the names of the tables and columns, and the column types, are required.
+    /// Foreign keys without referred columns refer to the primary key of their table.
+    ///
+    /// # Errors
+    ///
+    /// * `ParserError::SchemaFormat` if the JSON is not a valid schema
+    pub fn from_json(json: &str) -> Result<Self, ParserError> {
+        let mut schema: Schema = serde_json::from_str(json).map_err(|e| ParserError::SchemaFormat(e.to_string()))?;
+        schema.resolve_foreign_keys();
+        Ok(schema)
+    }
+
+    /// Create a new Schema from its YAML serialization, in the format of `from_json`.
+    ///
+    /// # Errors
+    ///
+    /// * `ParserError::SchemaFormat` if the YAML is not a valid schema
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use noorm::parser::Schema;
+    ///
+    /// let yaml = "
+    /// tables:
+    ///   - name: person
+    ///     columns:
+    ///       - { name: id, data_type: BIGINT, generated: true }
+    ///       - { name: name, data_type: TEXT, nullable: true }
+    ///     primary_key: [id]
+    /// ";
+    /// let schema = Schema::from_yaml(yaml).unwrap();
+    /// assert_eq!(schema.tables[0].columns[1].data_type.to_string(), "TEXT");
+    /// ```
+    pub fn from_yaml(yaml: &str) -> Result<Self, ParserError> {
+        let mut schema: Schema = serde_yaml::from_str(yaml).map_err(|e| ParserError::SchemaFormat(e.to_string()))?;
+        schema.resolve_foreign_keys();
+        Ok(schema)
+    }
+
+    /// Read a schema from a `.json`, `.yaml` or `.yml` file.
+    ///
+    /// # Errors
+    ///
+    /// * `ParserError::Read` if the file cannot be read
+    /// * `ParserError::SchemaFormat` if the file is not a valid schema, with its path
+    pub fn from_schema_file(path: impl AsRef<Path>) -> Result<Self, ParserError> {
+        let path = path.as_ref();
+        let content = fs::read_to_string(path).map_err(|source| ParserError::Read { path: path.to_path_buf(), source })?;
+        let is_json = path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("json"));
+        let schema = if is_json { Schema::from_json(&content) } else { Schema::from_yaml(&content) };
+        schema.map_err(|e| match e {
+            ParserError::SchemaFormat(message) => ParserError::SchemaFormat(format!("{}: {}", path.display(), message)),
+            e => e,
+        })
+    }
+}
+
+/// Parse a fragment of SQL of the schema format, e.g. a type or an expression, up to its end:
+/// `INT garbage` is not a type
+fn parse_sql<T, E: de::Error>(sql: &str, parse: impl FnOnce(&mut Parser) -> Result<T, sqlparser::parser::ParserError>) -> Result<T, E> {
+    let dialect = Dialect::Generic.sqlparser_dialect();
+    Parser::new(dialect.as_ref()).try_with_sql(sql)
+        .and_then(|mut parser| {
+            let parsed = parse(&mut parser)?;
+            parser.expect_token(&Token::EOF)?;
+            Ok(parsed)
+        })
+        .map_err(|e| E::custom(format!("invalid SQL `{}`: {}", sql, e)))
+}
+
+/// SQL data types as their SQL, e.g. `"VARCHAR(100)"`
+pub(super) mod data_type {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(data_type: &DataType, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(data_type)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<DataType, D::Error> {
+        let sql = String::deserialize(deserializer)?;
+        match sql.trim() {
+            "" => Ok(DataType::Unspecified),
+            sql => parse_sql(sql, |parser| parser.parse_data_type()),
+        }
+    }
+}
+
+/// Optional SQL expressions as their SQL, e.g. `"now()"`
+pub(super) mod expression {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(expr: &Option<Expr>, serializer: S) -> Result<S::Ok, S::Error> {
+        match expr {
+            Some(expr) => serializer.collect_str(expr),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Expr>, D::Error> {
+        Option::<String>::deserialize(deserializer)?
+            .map(|sql| parse_sql(&sql, |parser| parser.parse_expr()))
+            .transpose()
+    }
+}
+
+/// Optional foreign key actions as their SQL, e.g. `"SET NULL"`
+pub(super) mod referential_action {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(action: &Option<ReferentialAction>, serializer: S) -> Result<S::Ok, S::Error> {
+        match action {
+            Some(action) => serializer.collect_str(action),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<ReferentialAction>, D::Error> {
+        let Some(action) = Option::<String>::deserialize(deserializer)? else { return Ok(None) };
+        let actions = [
+            ReferentialAction::Restrict,
+            ReferentialAction::Cascade,
+            ReferentialAction::SetNull,
+            ReferentialAction::NoAction,
+            ReferentialAction::SetDefault,
+        ];
+        actions.into_iter()
+            .find(|known| known.to_string().eq_ignore_ascii_case(action.trim()))
+            .map(Some)
+            .ok_or_else(|| de::Error::custom(format!(
+                "unknown referential action `{}`, expected one of RESTRICT, CASCADE, SET NULL, NO ACTION, SET DEFAULT",
+                action
+            )))
+    }
+}
@@ -0,0 +1,226 @@
+use std::{fs, io::Read, path::Path};
+use rusqlite::{Connection, OpenFlags};
+use sqlparser::ast::{DataType, ReferentialAction, Statement};
+use sqlparser::parser::Parser;
+use sqlparser::tokenizer::{Token, Tokenizer};
+
+use super::{Column, Dialect, ForeignKey, Index, ParserError, Schema, Table, TableKind};
+
+/// First bytes of every SQLite database file
+const SQLITE_HEADER: &[u8] = b"SQLite format 3\0";
+
+/// Check if a path is an SQLite database file, from its header.
+pub fn is_sqlite_database(path: impl AsRef<Path>) -> bool {
+    let mut header = [0; 16];
+    fs::File::open(path).and_then(|mut file| file.read_exact(&mut header)).is_ok() && header == SQLITE_HEADER
+}
+
+/// A column from `pragma_table_xinfo`
+struct ColumnInfo {
+    name: String,
+    data_type: String,
+    not_null: bool,
+    default: Option<String>,
+    primary_key: usize,
+    hidden: i64,
+}
+
+impl Schema {
+    /// Create a new Schema from an existing SQLite database file, opened read-only.
+    ///
+    /// The tables are read from `sqlite_master` and the `table_info`, `foreign_key_list` and
+    /// `index_list` pragmas, the views are parsed from their definition. The tables and views
+    /// are named after the path as their source.
+    ///
+    /// # Errors
+    ///
+    /// * `ParserError::Sqlite` if the database cannot be opened or read
+    /// * The errors of `Schema::add_sql` for the definitions of the views
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use noorm::parser::Schema;
+    ///
+    /// let schema = Schema::from_sqlite("legacy.sqlite").unwrap();
+    /// for table in &schema.tables {
+    ///     println!("{}: {} columns", table.name, table.columns.len());
+    /// }
+    /// ```
+    pub fn from_sqlite(path: impl AsRef<Path>) -> Result<Self, ParserError> {
+        let path = path.as_ref();
+        let sqlite_error = |source| ParserError::Sqlite { path: path.to_path_buf(), source };
+        let connection = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY).map_err(sqlite_error)?;
+        let source = path.display().to_string();
+
+        let mut statement = connection.prepare(
+            "SELECT type, name, sql FROM sqlite_master WHERE type IN ('table', 'view') AND name NOT LIKE 'sqlite_%' ORDER BY rowid",
+        ).map_err(sqlite_error)?;
+        let objects = statement.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, Option<String>>(2)?)))
+            .and_then(Iterator::collect::<Result<Vec<_>, _>>)
+            .map_err(sqlite_error)?;
+
+        let mut schema = Schema::default();
+        let mut views = Vec::new();
+        for (kind, name, sql) in objects {
+            let sql = sql.unwrap_or_default();
+            if kind == "view" {
+                views.push(sql);
+                continue;
+            }
+            let mut table = read_table(&connection, &name, &sql).map_err(sqlite_error)?;
+            table.source = Some(source.clone());
+            schema.tables.push(table);
+        }
+        schema.resolve_foreign_keys();
+        for sql in views {
+            schema.add_sql(&sql, Some(&source), &Dialect::SQLite)?;
+        }
+        Ok(schema)
+    }
+}
+
+/// Table from the pragmas of the database, with the comments of its definition
+fn read_table(connection: &Connection, name: &str, sql: &str) -> rusqlite::Result<Table> {
+    let mut statement = connection.prepare("SELECT name, type, \"notnull\", dflt_value, pk, hidden FROM pragma_table_xinfo(?1) ORDER BY cid")?;
+    let infos = statement.query_map([name], |row| Ok(ColumnInfo {
+        name: row.get(0)?,
+        data_type: row.get(1)?,
+        not_null: row.get(2)?,
+        default: row.get(3)?,
+        primary_key: row.get(4)?,
+        hidden: row.get(5)?,
+    }))?.collect::<rusqlite::Result<Vec<_>>>()?;
+
+    // Only the `INTEGER PRIMARY KEY AUTOINCREMENT` column is generated on insert, besides the generated columns
+    let auto_increment = tokens(sql).iter().any(|token| matches!(token, Token::Word(w) if w.value.eq_ignore_ascii_case("AUTOINCREMENT")));
+    let mut columns = Vec::new();
+    let mut primary_key: Vec<(usize, String)> = Vec::new();
+    for info in infos.into_iter().filter(|info| info.hidden != 1) {
+        let data_type = match info.data_type.trim() {
+            "" => DataType::Unspecified,
+            data_type => parse_sql(data_type, |parser| parser.parse_data_type())
+                .unwrap_or_else(|_| DataType::Custom(sqlparser::ast::ObjectName(vec![data_type.into()]), Vec::new())),
+        };
+        let mut column = Column {
+            name: info.name.clone(),
+            data_type,
+            nullable: !info.not_null && info.primary_key == 0,
+            default: info.default.as_deref().and_then(|default| parse_sql(default, |parser| parser.parse_expr()).ok()),
+            generated: info.hidden == 2 || info.hidden == 3 || (auto_increment && info.primary_key > 0),
+            comment: None,
+        };
+        column.generated |= column.default.is_some() && column.default_value().is_none();
+        if info.primary_key > 0 {
+            primary_key.push((info.primary_key, info.name));
+        }
+        columns.push(column);
+    }
+    primary_key.sort();
+
+    let mut table = Table {
+        name: name.to_string(),
+        kind: TableKind::Table,
+        columns,
+        primary_key: primary_key.into_iter().map(|(_, name)| name).collect(),
+        unique_keys: Vec::new(),
+        indexes: Vec::new(),
+        foreign_keys: read_foreign_keys(connection, name)?,
+        comment: None,
+        source: None,
+        definition: None,
+    };
+    read_indexes(connection, &mut table)?;
+
+    // The comments are only in the definition
+    if let Ok(parsed) = Schema::from_sql(sql, &Dialect::SQLite) {
+        if let Some(parsed) = parsed.tables.into_iter().next() {
+            table.comment = parsed.comment;
+            for column in &mut table.columns {
+                column.comment = parsed.columns.iter().find(|c| c.name.eq_ignore_ascii_case(&column.name)).and_then(|c| c.comment.clone());
+            }
+        }
+    }
+    Ok(table)
+}
+
+/// Foreign keys of a table, in declaration order
+fn read_foreign_keys(connection: &Connection, table: &str) -> rusqlite::Result<Vec<ForeignKey>> {
+    let mut statement = connection.prepare(
+        "SELECT id, \"table\", \"from\", \"to\", on_update, on_delete FROM pragma_foreign_key_list(?1) ORDER BY id DESC, seq",
+    )?;
+    let mut rows = statement.query([table])?;
+    let mut foreign_keys: Vec<(i64, ForeignKey)> = Vec::new();
+    while let Some(row) = rows.next()? {
+        let id: i64 = row.get(0)?;
+        if foreign_keys.last().is_none_or(|(last, _)| *last != id) {
+            foreign_keys.push((id, ForeignKey {
+                name: None,
+                columns: Vec::new(),
+                foreign_table: row.get(1)?,
+                referred_columns: Vec::new(),
+                on_delete: referential_action(&row.get::<_, String>(5)?),
+                on_update: referential_action(&row.get::<_, String>(4)?),
+            }));
+        }
+        let (_, foreign_key) = foreign_keys.last_mut().expect("foreign key just pushed");
+        foreign_key.columns.push(row.get(2)?);
+        // Without referred columns, the foreign key refers to the primary key
+        if let Some(referred) = row.get::<_, Option<String>>(3)? {
+            foreign_key.referred_columns.push(referred);
+        }
+    }
+    Ok(foreign_keys.into_iter().map(|(_, foreign_key)| foreign_key).collect())
+}
+
+/// Unique keys and indexes of a table, in declaration order
+fn read_indexes(connection: &Connection, table: &mut Table) -> rusqlite::Result<()> {
+    let mut statement = connection.prepare(
+        "SELECT l.name, l.\"unique\", l.origin, m.sql FROM pragma_index_list(?1) AS l
+        LEFT JOIN sqlite_master AS m ON m.type = 'index' AND m.name = l.name ORDER BY l.seq DESC",
+    )?;
+    let indexes = statement.query_map([&table.name], |row| {
+        Ok((row.get::<_, String>(0)?, row.get::<_, bool>(1)?, row.get::<_, String>(2)?, row.get::<_, Option<String>>(3)?))
+    })?.collect::<rusqlite::Result<Vec<_>>>()?;
+    let mut columns_statement = connection.prepare("SELECT name FROM pragma_index_info(?1) ORDER BY seqno")?;
+    for (name, unique, origin, sql) in indexes {
+        let columns = columns_statement.query_map([&name], |row| row.get::<_, Option<String>>(0))?
+            .collect::<rusqlite::Result<Option<Vec<String>>>>()?;
+        // Indexes on expressions have no column names
+        let Some(columns) = columns else { continue };
+        match origin.as_str() {
+            "pk" => {}
+            "u" => table.unique_keys.push(columns),
+            _ => {
+                let predicate = sql.and_then(|sql| match parse_sql(&sql, |parser| parser.parse_statement()) {
+                    Ok(Statement::CreateIndex(create_index)) => create_index.predicate,
+                    _ => None,
+                });
+                table.indexes.push(Index { name: Some(name), columns, unique, predicate });
+            }
+        }
+    }
+    Ok(())
+}
+
+fn referential_action(action: &str) -> Option<ReferentialAction> {
+    match action.to_uppercase().as_str() {
+        "CASCADE" => Some(ReferentialAction::Cascade),
+        "SET NULL" => Some(ReferentialAction::SetNull),
+        "SET DEFAULT" => Some(ReferentialAction::SetDefault),
+        "RESTRICT" => Some(ReferentialAction::Restrict),
+        _ => None,
+    }
+}
+
+/// Parse a fragment of SQL of the database, e.g. a type or an expression
+fn parse_sql<T>(sql: &str, parse: impl FnOnce(&mut Parser) -> Result<T, sqlparser::parser::ParserError>) -> Result<T, sqlparser::parser::ParserError> {
+    let dialect = Dialect::SQLite.sqlparser_dialect();
+    let mut parser = Parser::new(dialect.as_ref()).try_with_sql(sql)?;
+    parse(&mut parser)
+}
+
+fn tokens(sql: &str) -> Vec<Token> {
+    let dialect = Dialect::SQLite.sqlparser_dialect();
+    Tokenizer::new(dialect.as_ref(), sql).tokenize().unwrap_or_default()
+}
@@ -0,0 +1,220 @@
+use sqlparser::ast::{
+    BinaryOperator, DataType, Expr, FunctionArg, FunctionArgExpr, FunctionArguments, Ident, JoinOperator, Query,
+    SelectItem, SetExpr, TableFactor, Value,
+};
+
+use super::schema::object_name;
+use super::{Column, Schema};
+
+/// A relation visible in the `FROM` clause of a query
+#[derive(Clone)]
+struct Source {
+    /// Alias, or name of the table
+    name: String,
+    columns: Vec<Column>,
+}
+
+/// Output columns of a view query, resolved against the tables of the schema.
+///
+/// Columns taken from tables keep their type and nullability, made nullable on the
+/// optional side of outer joins. Other expressions get a type inferred from their shape,
+/// and are named after the function called or their position, e.g. `column2`.
+pub(super) fn query_columns(schema: &Schema, query: &Query) -> Vec<Column> {
+    query_columns_with(schema, query, &[])
+}
+
+fn query_columns_with(schema: &Schema, query: &Query, outer_ctes: &[Source]) -> Vec<Column> {
+    let mut ctes: Vec<Source> = Vec::new();
+    if let Some(with) = &query.with {
+        for cte in &with.cte_tables {
+            let visible: Vec<Source> = ctes.iter().chain(outer_ctes).map(Source::clone).collect();
+            let mut columns = query_columns_with(schema, &cte.query, &visible);
+            rename_columns(&mut columns, &cte.alias.columns);
+            ctes.push(Source { name: cte.alias.name.value.clone(), columns });
+        }
+    }
+    ctes.extend(outer_ctes.iter().map(Source::clone));
+    set_expr_columns(schema, &query.body, &ctes)
+}
+
+fn set_expr_columns(schema: &Schema, body: &SetExpr, ctes: &[Source]) -> Vec<Column> {
+    let select = match body {
+        SetExpr::Select(select) => select,
+        SetExpr::Query(query) => return query_columns_with(schema, query, ctes),
+        SetExpr::SetOperation { left, .. } => return set_expr_columns(schema, left, ctes),
+        _ => return Vec::new(),
+    };
+
+    let mut sources: Vec<Source> = Vec::new();
+    for table in &select.from {
+        sources.extend(table_factor_source(schema, &table.relation, ctes));
+        for join in &table.joins {
+            let Some(mut source) = table_factor_source(schema, &join.relation, ctes) else { continue };
+            match join.join_operator {
+                JoinOperator::LeftOuter(_) | JoinOperator::OuterApply => make_nullable(&mut source.columns),
+                JoinOperator::RightOuter(_) => sources.iter_mut().for_each(|s| make_nullable(&mut s.columns)),
+                JoinOperator::FullOuter(_) => {
+                    make_nullable(&mut source.columns);
+                    sources.iter_mut().for_each(|s| make_nullable(&mut s.columns));
+                }
+                _ => {}
+            }
+            sources.push(source);
+        }
+    }
+
+    let mut columns = Vec::new();
+    for item in &select.projection {
+        match item {
+            SelectItem::Wildcard(_) => columns.extend(sources.iter().flat_map(|s| s.columns.iter().cloned())),
+            SelectItem::QualifiedWildcard(name, _) => {
+                if let Some(source) = find_source(&sources, &object_name(name)) {
+                    columns.extend(source.columns.iter().cloned());
+                }
+            }
+            SelectItem::UnnamedExpr(expr) => {
+                let mut column = expr_column(expr, &sources);
+                if column.name.is_empty() {
+                    column.name = format!("column{}", columns.len() + 1);
+                }
+                columns.push(column);
+            }
+            SelectItem::ExprWithAlias { expr, alias } => {
+                let mut column = expr_column(expr, &sources);
+                column.name = alias.value.clone();
+                columns.push(column);
+            }
+        }
+    }
+    columns
+}
+
+/// Columns of a table, view, common table expression or subquery of the `FROM` clause
+fn table_factor_source(schema: &Schema, factor: &TableFactor, ctes: &[Source]) -> Option<Source> {
+    let (name, mut columns, alias) = match factor {
+        TableFactor::Table { name, alias, .. } => {
+            let name = object_name(name);
+            let columns = match find_source(ctes, &name) {
+                Some(cte) => cte.columns.clone(),
+                None => schema.table(&name)?.columns.clone(),
+            };
+            (name, columns, alias)
+        }
+        TableFactor::Derived { subquery, alias, .. } => (String::new(), query_columns_with(schema, subquery, ctes), alias),
+        TableFactor::NestedJoin { table_with_joins, alias } => {
+            let source = table_factor_source(schema, &table_with_joins.relation, ctes)?;
+            (source.name, source.columns, alias)
+        }
+        _ => return None,
+    };
+    let name = match alias {
+        Some(alias) => {
+            rename_columns(&mut columns, &alias.columns);
+            alias.name.value.clone()
+        }
+        None => name,
+    };
+    Some(Source { name, columns })
+}
+
+/// Column for a projected expression; the name is left empty when there is no natural one
+fn expr_column(expr: &Expr, sources: &[Source]) -> Column {
+    let derived = |name: &str, data_type: DataType, nullable: bool| Column {
+        name: name.to_string(),
+        data_type,
+        nullable,
+        default: None,
+        generated: false,
+        comment: None,
+    };
+    match expr {
+        Expr::Identifier(ident) => sources.iter()
+            .find_map(|source| source.columns.iter().find(|c| c.name.eq_ignore_ascii_case(&ident.value)))
+            .cloned()
+            .unwrap_or_else(|| derived(&ident.value, DataType::Text, true)),
+        Expr::CompoundIdentifier(idents) => {
+            let column = match idents.as_slice() {
+                [.., table, column] => find_source(sources, &table.value)
+                    .and_then(|source| source.columns.iter().find(|c| c.name.eq_ignore_ascii_case(&column.value))),
+                _ => None,
+            };
+            let name = idents.last().map(|i| i.value.as_str()).unwrap_or_default();
+            column.cloned().unwrap_or_else(|| derived(name, DataType::Text, true))
+        }
+        Expr::Nested(expr) => expr_column(expr, sources),
+        Expr::Cast { expr, data_type, .. } => {
+            let inner = expr_column(expr, sources);
+            derived(&inner.name, data_type.clone(), inner.nullable)
+        }
+        Expr::Value(value) => match value {
+            Value::Number(number, _) if number.contains(['.', 'e', 'E']) => derived("", DataType::Double, false),
+            Value::Number(..) => derived("", DataType::Integer(None), false),
+            Value::Boolean(_) => derived("", DataType::Boolean, false),
+            Value::Null => derived("", DataType::Text, true),
+            _ => derived("", DataType::Text, false),
+        },
+        Expr::IsNull(_) | Expr::IsNotNull(_) | Expr::IsTrue(_) | Expr::IsFalse(_) | Expr::Exists { .. } => {
+            derived("", DataType::Boolean, false)
+        }
+        Expr::BinaryOp { left, op, right } => {
+            let (left, right) = (expr_column(left, sources), expr_column(right, sources));
+            let nullable = left.nullable || right.nullable;
+            match op {
+                BinaryOperator::Eq | BinaryOperator::NotEq | BinaryOperator::Lt | BinaryOperator::LtEq
+                | BinaryOperator::Gt | BinaryOperator::GtEq | BinaryOperator::And | BinaryOperator::Or => {
+                    derived("", DataType::Boolean, nullable)
+                }
+                BinaryOperator::StringConcat => derived("", DataType::Text, nullable),
+                _ => derived("", left.data_type, nullable),
+            }
+        }
+        Expr::Case { results, else_result, .. } => {
+            let mut columns = results.iter().chain(else_result.as_deref()).map(|e| expr_column(e, sources));
+            let first = columns.next().unwrap_or_else(|| derived("", DataType::Text, true));
+            let nullable = else_result.is_none() || first.nullable || columns.any(|c| c.nullable);
+            derived("", first.data_type, nullable)
+        }
+        Expr::Function(function) => {
+            let name = object_name(&function.name).to_lowercase();
+            let args: Vec<Column> = match &function.args {
+                FunctionArguments::List(list) => list.args.iter()
+                    .filter_map(|arg| match arg {
+                        FunctionArg::Unnamed(FunctionArgExpr::Expr(expr)) | FunctionArg::Named { arg: FunctionArgExpr::Expr(expr), .. } => {
+                            Some(expr_column(expr, sources))
+                        }
+                        _ => None,
+                    })
+                    .collect(),
+                _ => Vec::new(),
+            };
+            match (name.as_str(), args.first()) {
+                ("count", _) => derived(&name, DataType::BigInt(None), false),
+                ("avg", _) => derived(&name, DataType::Double, true),
+                ("sum" | "min" | "max", Some(arg)) => derived(&name, arg.data_type.clone(), true),
+                ("coalesce" | "ifnull" | "nvl", Some(arg)) => {
+                    derived(&name, arg.data_type.clone(), args.iter().all(|a| a.nullable))
+                }
+                ("lower" | "upper" | "trim" | "concat" | "substr" | "substring" | "replace", _) => {
+                    derived(&name, DataType::Text, args.iter().any(|a| a.nullable))
+                }
+                _ => derived(&name, DataType::Text, true),
+            }
+        }
+        _ => derived("", DataType::Text, true),
+    }
+}
+
+/// Rename the columns after a column alias list, by position
+fn rename_columns(columns: &mut [Column], names: &[Ident]) {
+    for (column, name) in columns.iter_mut().zip(names) {
+        column.name = name.value.clone();
+    }
+}
+
+fn make_nullable(columns: &mut [Column]) {
+    columns.iter_mut().for_each(|c| c.nullable = true);
+}
+
+fn find_source<'a>(sources: &'a [Source], name: &str) -> Option<&'a Source> {
+    sources.iter().find(|s| s.name.eq_ignore_ascii_case(name))
+}
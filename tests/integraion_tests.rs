@@ -1,5 +1,5 @@
 use std::path::PathBuf;
-use noorm::{parser::{DummyAst, Language}, prelude::*};
+use noorm::parser::*;
 
 #[test]
 fn test_parser_builder() {
@@ -13,7 +13,1019 @@ fn test_parser_builder() {
         language: Language::Dummy(d_ast),
         migrations,
         queries,
+        output: None,
+        targets: Vec::new(),
+        lint: LintConfig::default(),
     });
     let parser = Parser::new().set_config(config.clone());
     assert_eq!(&config, parser.get_config())
 }
+
+#[test]
+fn test_parse_create_table_errors() {
+    let sql = "CREATE TABLE person ( id INT, name TEXT )";
+    assert!(rust_ast_to_string(&rust_parse_create_table(sql).unwrap()).contains("pub struct Person {"));
+    assert!(python_parse_create_table(sql).unwrap().contains("class Person:"));
+    assert!(javascript_ast_to_string(&javascript_parse_create_table(sql).unwrap()).contains("person"));
+
+    let invalid = "CREATE TABLE person ( id INT,";
+    assert!(matches!(rust_parse_create_table(invalid), Err(ParserError::Sql(_))));
+    assert!(matches!(python_parse_create_table(invalid), Err(ParserError::Sql(_))));
+    assert!(matches!(javascript_parse_create_table(invalid), Err(ParserError::Sql(_))));
+}
+
+#[test]
+fn test_insert_types_omit_generated_columns() {
+    let sql = "CREATE TABLE person ( id SERIAL, name VARCHAR(255), status TEXT DEFAULT 'active' )";
+    let schema = Schema::from_sql(sql, &Dialect::Generic).unwrap();
+    let person = &schema.tables[0];
+    let insertable: Vec<&str> = person.insertable_columns().map(|c| c.name.as_str()).collect();
+    assert_eq!(insertable, vec!["name", "status"]);
+
+    let rust_code = rust_ast_to_string(&rust_schema_to_ast(&schema, &Dialect::Generic, &RustOptions::default()));
+    assert!(rust_code.contains("pub struct NewPerson {\n    pub name: String,\n    pub status: Option<String>,\n}"));
+    assert!(rust_code.contains("pub fn into_person(self, id: i64) -> Person"));
+
+    let python_code = python_ast_to_string(&python_schema_to_ast(&schema));
+    assert!(python_code.contains("class NewPerson:\n    name: str\n    status: str = \"active\"\n"));
+}
+
+#[test]
+fn test_patch_update_sql_placeholders() {
+    let sql = "CREATE TABLE person ( id SERIAL PRIMARY KEY, name VARCHAR(255), nick TEXT NULL )";
+    let schema = Schema::from_sql(sql, &Dialect::PostgreSQL).unwrap();
+    assert_eq!(schema.tables[0].primary_key, vec!["id"]);
+
+    let rust_code = rust_ast_to_string(&rust_schema_to_ast(&schema, &Dialect::PostgreSQL, &RustOptions::default()));
+    assert!(rust_code.contains("pub struct PersonPatch {\n    pub name: Option<String>,\n    pub nick: Option<Option<String>>,\n}"));
+    assert!(rust_code.contains(r#"format!("{} = ${}", column, i + 2)"#));
+    assert!(rust_code.contains(r#""UPDATE \"person\" SET {} WHERE \"id\" = $1""#));
+    assert!(rust_code.contains(r#"columns.push("\"name\"");"#));
+
+    let rust_code = rust_ast_to_string(&rust_schema_to_ast(&schema, &Dialect::MySQL, &RustOptions::default()));
+    assert!(rust_code.contains(r#""UPDATE `person` SET {} WHERE `id` = ?""#));
+}
+
+#[test]
+fn test_crud_generation_with_composite_key() {
+    let sql = "CREATE TABLE membership ( person_id INTEGER, office_id INTEGER, role TEXT, PRIMARY KEY (person_id, office_id) )";
+    let schema = Schema::from_sql(sql, &Dialect::SQLite).unwrap();
+    let options = RustOptions { backend: Some(RustBackend::Rusqlite), ..Default::default() };
+    let rust_code = rust_ast_to_string(&rust_schema_to_ast(&schema, &Dialect::SQLite, &options));
+    assert!(rust_code.starts_with("use rusqlite::OptionalExtension;\n"));
+    assert!(rust_code.contains("pub struct MembershipKey {\n    pub person_id: i64,\n    pub office_id: i64,\n}"));
+    assert!(rust_code.contains("pub fn get_by_pk(\n        conn: &rusqlite::Connection,\n        key: MembershipKey,\n    )"));
+    assert!(rust_code.contains(r#""SELECT \"person_id\", \"office_id\", \"role\" FROM \"membership\" WHERE \"person_id\" = ?1 AND \"office_id\" = ?2""#));
+    assert!(rust_code.contains(r#""UPDATE \"membership\" SET \"role\" = ?3 WHERE \"person_id\" = ?1 AND \"office_id\" = ?2""#));
+    assert!(rust_code.contains(r#""DELETE FROM \"membership\" WHERE \"person_id\" = ?1 AND \"office_id\" = ?2""#));
+}
+
+#[test]
+fn test_lookups_from_unique_constraints_and_indexes() {
+    let sql = "
+        CREATE TABLE person ( id SERIAL PRIMARY KEY, email TEXT UNIQUE, name TEXT, office_id INTEGER, deleted_at TEXT NULL );
+        CREATE INDEX person_office ON person (office_id);
+        CREATE UNIQUE INDEX person_name ON person (name) WHERE deleted_at IS NULL;
+        CREATE INDEX person_lower_email ON person (lower(email));
+    ";
+    let schema = Schema::from_sql(sql, &Dialect::PostgreSQL).unwrap();
+    let person = schema.table("person").unwrap();
+    assert_eq!(person.unique_keys, vec![vec!["email".to_string()]]);
+    assert_eq!(person.indexes.len(), 3);
+    let lookups: Vec<(String, bool, bool)> = person.lookups().iter()
+        .map(|l| (l.columns[0].name.clone(), l.unique, l.predicate.is_some()))
+        .collect();
+    assert_eq!(lookups, vec![
+        ("email".to_string(), true, false),
+        ("name".to_string(), true, true),
+        ("office_id".to_string(), false, false),
+    ]);
+
+    let options = RustOptions { backend: Some(RustBackend::Sqlx), ..Default::default() };
+    let rust_code = rust_ast_to_string(&rust_schema_to_ast(&schema, &Dialect::PostgreSQL, &options));
+    assert!(rust_code.contains("pub async fn find_by_email<'e, E>(\n        executor: E,\n        email: &str,\n    ) -> Result<Option<Person>, sqlx::Error>"));
+    assert!(rust_code.contains("pub async fn list_by_office_id<'e, E>(\n        executor: E,\n        office_id: i32,\n    ) -> Result<Vec<Person>, sqlx::Error>"));
+    assert!(rust_code.contains(r#""SELECT \"id\", \"email\", \"name\", \"office_id\", \"deleted_at\" FROM \"person\" WHERE \"name\" = $1 AND (deleted_at IS NULL)""#));
+}
+
+#[test]
+fn test_relations_from_foreign_keys() {
+    let sql = "
+        CREATE TABLE person ( id SERIAL PRIMARY KEY, name TEXT );
+        CREATE TABLE office ( id SERIAL PRIMARY KEY, name TEXT, person_id INTEGER REFERENCES person(id) );
+        CREATE TABLE tag ( id SERIAL PRIMARY KEY, label TEXT );
+        CREATE TABLE person_tag ( person_id INTEGER, tag_id INTEGER, PRIMARY KEY (person_id, tag_id) );
+        ALTER TABLE person_tag ADD CONSTRAINT person_tag_person FOREIGN KEY (person_id) REFERENCES person(id);
+        ALTER TABLE person_tag ADD CONSTRAINT person_tag_tag FOREIGN KEY (tag_id) REFERENCES tag(id);
+    ";
+    let schema = Schema::from_sql(sql, &Dialect::PostgreSQL).unwrap();
+    assert!(schema.table("person_tag").unwrap().is_join_table());
+    let listing: Vec<String> = schema.relations_of("person").iter().map(|r| r.to_string()).collect();
+    assert_eq!(listing, vec![
+        "person(id) -> office(person_id) [one-to-many]",
+        "person(id) -> person_tag(person_id) [one-to-many]",
+        "person(id) -> person_tag(person_id | tag_id) -> tag(id) [many-to-many]",
+    ]);
+
+    let options = RustOptions { backend: Some(RustBackend::TokioPostgres), ..Default::default() };
+    let rust_code = rust_ast_to_string(&rust_schema_to_ast(&schema, &Dialect::PostgreSQL, &options));
+    assert!(rust_code.contains("pub async fn offices(\n        &self,\n        client: &impl tokio_postgres::GenericClient,\n    ) -> Result<Vec<Office>, tokio_postgres::Error>"));
+    assert!(rust_code.contains("pub async fn person(\n        &self,\n        client: &impl tokio_postgres::GenericClient,\n    ) -> Result<Option<Person>, tokio_postgres::Error>"));
+    assert!(rust_code.contains(r#""SELECT \"tag\".\"id\", \"tag\".\"label\" FROM \"tag\" JOIN \"person_tag\" ON \"person_tag\".\"tag_id\" = \"tag\".\"id\" WHERE \"person_tag\".\"person_id\" = $1""#));
+    assert!(rust_code.contains("pub struct OfficeWithPerson {\n    pub office: Office,\n    pub person: Person,\n}"));
+
+    // Relation helpers do not take the names of the other functions
+    let sql = "
+        CREATE TABLE todo_list ( id SERIAL PRIMARY KEY );
+        CREATE TABLE item ( id SERIAL PRIMARY KEY, list_id INTEGER REFERENCES todo_list(id), insert_id INTEGER REFERENCES todo_list(id) );
+    ";
+    let schema = Schema::from_sql(sql, &Dialect::PostgreSQL).unwrap();
+    let rust_code = rust_ast_to_string(&rust_schema_to_ast(&schema, &Dialect::PostgreSQL, &options));
+    for name in ["list(", "list_row(", "insert(", "insert_row(", "list_with_list_row(", "list_with_insert_row("] {
+        assert_eq!(rust_code.matches(&format!("pub async fn {}", name)).count(), if name == "list(" || name == "insert(" { 2 } else { 1 }, "{}: {}", name, rust_code);
+    }
+}
+
+#[test]
+fn test_rust_integer_types_per_dialect() {
+    let sql = "CREATE TABLE person ( id SERIAL PRIMARY KEY, age SMALLINT NULL DEFAULT 18, score INTEGER, visits BIGINT, big_id BIGSERIAL )";
+    let schema = Schema::from_sql(sql, &Dialect::PostgreSQL).unwrap();
+    let rust_code = |dialect: Dialect, backend: Option<RustBackend>| {
+        let options = RustOptions { backend, ..Default::default() };
+        rust_ast_to_string(&rust_schema_to_ast(&schema, &dialect, &options))
+    };
+
+    // Postgres drivers only read each integer type into a Rust type of the same width
+    let postgres_fields = "pub struct Person {\n    pub id: i32,\n    pub age: Option<i16>,\n    pub score: i32,\n    pub visits: i64,\n    pub big_id: i64,\n}";
+    for (dialect, backend) in [
+        (Dialect::PostgreSQL, RustBackend::TokioPostgres),
+        (Dialect::PostgreSQL, RustBackend::Sqlx),
+        (Dialect::Generic, RustBackend::Sqlx),
+        (Dialect::SQLite, RustBackend::TokioPostgres),
+    ] {
+        let code = rust_code(dialect, Some(backend));
+        assert!(code.contains(postgres_fields), "{}", code);
+        assert!(code.contains("pub fn default_age() -> Option<i16> {\n        Some(18)\n    }"), "{}", code);
+    }
+    let widest_fields = "pub struct Person {\n    pub id: i64,\n    pub age: Option<i64>,\n    pub score: i64,\n    pub visits: i64,\n    pub big_id: i64,\n}";
+    for (dialect, backend) in [
+        (Dialect::PostgreSQL, None),
+        (Dialect::MySQL, Some(RustBackend::Sqlx)),
+        (Dialect::SQLite, Some(RustBackend::Sqlx)),
+        (Dialect::PostgreSQL, Some(RustBackend::Rusqlite)),
+    ] {
+        let code = rust_code(dialect, backend);
+        assert!(code.contains(widest_fields), "{}", code);
+    }
+
+    // A row of unexpected types is an error, not a panic
+    let code = rust_code(Dialect::PostgreSQL, Some(RustBackend::TokioPostgres));
+    assert!(code.contains("pub fn from_row(row: &tokio_postgres::Row) -> Result<Self, tokio_postgres::Error> {"), "{}", code);
+    assert!(code.contains("id: row.try_get(\"id\")?,"), "{}", code);
+    assert!(code.contains("pub async fn get_by_pk(\n        client: &impl tokio_postgres::GenericClient,\n        id: i32,\n    )"), "{}", code);
+    assert!(!code.contains("row.get("), "{}", code);
+}
+
+#[test]
+fn test_rust_column_types_per_backend() {
+    let sql = "CREATE TABLE reading ( id SERIAL PRIMARY KEY, level REAL, ratio DOUBLE PRECISION, flag TINYINT, data BYTEA, code SMALLINT DEFAULT 100000 )";
+    let schema = Schema::from_sql(sql, &Dialect::PostgreSQL).unwrap();
+    let rust_code = |dialect: Dialect, backend: RustBackend| {
+        let options = RustOptions { backend: Some(backend), ..Default::default() };
+        rust_ast_to_string(&rust_schema_to_ast(&schema, &dialect, &options))
+    };
+    let code = rust_code(Dialect::PostgreSQL, RustBackend::TokioPostgres);
+    assert!(code.contains("pub level: f32,\n    pub ratio: f64,\n    pub flag: i64,\n    pub data: Vec<u8>,\n    pub code: i16,"), "{}", code);
+    // The default does not fit the field, so it is left to the database
+    assert!(!code.contains("default_code"), "{}", code);
+    assert!(code.contains("    pub code: Option<i16>,\n}"), "{}", code);
+    let code = rust_code(Dialect::MySQL, RustBackend::Sqlx);
+    assert!(code.contains("pub level: f64,\n    pub ratio: f64,"), "{}", code);
+
+    // Without a crate for its type, a column cannot be read by the backend
+    let sql = "CREATE TABLE event ( id SERIAL PRIMARY KEY, day DATE )";
+    let mut language = Language::Rust(RustAst::with_options(RustOptions { backend: Some(RustBackend::Sqlx), ..Default::default() }));
+    assert!(matches!(
+        language.parse_create_table(sql, &Dialect::PostgreSQL),
+        Err(ParserError::ColumnType { table, column, data_type }) if table == "event" && column == "day" && data_type == "DATE"
+    ));
+    let types = RustTypeCrates { datetime: Some(DateTimeCrate::Chrono), ..Default::default() };
+    let mut language = Language::Rust(RustAst::with_options(RustOptions { backend: Some(RustBackend::Sqlx), types, ..Default::default() }));
+    language.parse_create_table(sql, &Dialect::PostgreSQL).unwrap();
+    assert!(language.to_string().contains("pub day: chrono::NaiveDate,"));
+    let mut language = Language::Rust(RustAst::default());
+    language.parse_create_table(sql, &Dialect::PostgreSQL).unwrap();
+    assert!(language.to_string().contains("pub day: String,"));
+}
+
+#[test]
+fn test_sql_names_quoted_per_dialect() {
+    // Unquoted PostgreSQL names are folded to lowercase, as the database does
+    let sql = r#"CREATE TABLE "user" ( Id SERIAL PRIMARY KEY, "CreatedAt" TEXT, "order" INTEGER )"#;
+    let schema = Schema::from_sql(sql, &Dialect::PostgreSQL).unwrap();
+    assert_eq!(schema.tables[0].columns.iter().map(|c| c.name.as_str()).collect::<Vec<_>>(), vec!["id", "CreatedAt", "order"]);
+    let options = RustOptions { backend: Some(RustBackend::Sqlx), ..Default::default() };
+    let rust_code = rust_ast_to_string(&rust_schema_to_ast(&schema, &Dialect::PostgreSQL, &options));
+    assert!(rust_code.contains("pub id: i32,\n    #[sqlx(rename = \"CreatedAt\")]\n    pub createdat: String,"), "{}", rust_code);
+    assert!(rust_code.contains(r#"columns.push("\"CreatedAt\"");"#), "{}", rust_code);
+    assert!(rust_code.contains(r#""INSERT INTO \"user\" ({}) VALUES ({}) RETURNING \"id\", \"CreatedAt\", \"order\"","#), "{}", rust_code);
+
+    // MySQL returns the generated id only for a single AUTO_INCREMENT key
+    let sql = "CREATE TABLE `order` ( id INT AUTO_INCREMENT PRIMARY KEY, `desc` TEXT );
+        CREATE TABLE line ( order_id INT, pos INT, PRIMARY KEY (order_id, pos) );";
+    let schema = Schema::from_sql(sql, &Dialect::MySQL).unwrap();
+    let rust_code = rust_ast_to_string(&rust_schema_to_ast(&schema, &Dialect::MySQL, &options));
+    let (order, line) = rust_code.split_once("pub struct Line {").unwrap();
+    assert!(order.contains(r#""INSERT INTO `order` ({}) VALUES ({})""#), "{}", rust_code);
+    assert!(order.contains("Ok(query.execute(executor).await?.last_insert_id())"), "{}", rust_code);
+    assert!(line.contains(r#"columns.push("`order_id`");"#), "{}", rust_code);
+    assert!(line.contains("Ok(query.execute(executor).await?.rows_affected())"), "{}", rust_code);
+}
+
+#[test]
+fn test_rust_identifiers_from_sql_names() {
+    let sql = r#"CREATE TABLE "type" ( id SERIAL PRIMARY KEY, "type" TEXT, "my col" INTEGER NULL, "1st" TEXT NULL, "self" TEXT NULL, "fn" INTEGER NULL DEFAULT 3 );
+        CREATE INDEX type_type ON "type" ("type");
+        CREATE TABLE "my table" ( id SERIAL PRIMARY KEY, type_id INTEGER REFERENCES "type"(id) );"#;
+    let schema = Schema::from_sql(sql, &Dialect::PostgreSQL).unwrap();
+    let options = RustOptions { backend: Some(RustBackend::Sqlx), ..Default::default() };
+    let rust_code = rust_ast_to_string(&rust_schema_to_ast(&schema, &Dialect::PostgreSQL, &options));
+    assert!(rust_code.contains("pub struct Type {\n    pub id: i32,\n    pub r#type: String,\n    #[sqlx(rename = \"my col\")]\n    pub my_col: Option<i32>,\n    #[sqlx(rename = \"1st\")]\n    pub _1st: Option<String>,\n    #[sqlx(rename = \"self\")]\n    pub self_: Option<String>,\n    pub r#fn: Option<i32>,\n}"), "{}", rust_code);
+    assert!(rust_code.contains("pub fn default_fn() -> Option<i32> {"), "{}", rust_code);
+    assert!(rust_code.contains("pub async fn list_by_type<'e, E>("), "{}", rust_code);
+    assert!(rust_code.contains("pub async fn my_tables<'e, E>("), "{}", rust_code);
+    assert!(rust_code.contains("pub struct My_table {"), "{}", rust_code);
+    assert!(rust_code.contains("pub async fn r#type<'e, E>("), "{}", rust_code);
+
+    // The generated fields are read back as the same columns
+    let structs = "#[noorm]\npub struct Type {\n    #[noorm(primary_key)]\n    pub id: i32,\n    pub r#type: String,\n}";
+    let schema = Schema::from_rust(structs, &Dialect::PostgreSQL).unwrap();
+    assert_eq!(schema.tables[0].columns[1].name, "type");
+
+    // Python keywords get a trailing underscore
+    let python_sql = r#"CREATE TABLE "import" ( id SERIAL PRIMARY KEY, class TEXT, "from" INTEGER NULL, "my col" TEXT NULL )"#;
+    let python_code = python_ast_to_string(&python_schema_to_ast(&Schema::from_sql(python_sql, &Dialect::PostgreSQL).unwrap()));
+    assert!(python_code.contains("class Import:\n    id: int\n    class_: str\n    from_: Optional[int]\n    my_col: Optional[str]\n"), "{}", python_code);
+    assert!(python_code.contains("    def into_import_(self, id: int) -> \"Import\":\n        return Import(\n            id=id,\n            class_=self.class_,\n"), "{}", python_code);
+    let python_files = python_schema_to_files(&Schema::from_sql(python_sql, &Dialect::PostgreSQL).unwrap());
+    assert_eq!(python_files.get("__init__.py").unwrap().contents, "from .import_ import Import, NewImport\n");
+
+    // Class and variable names come from the sanitized table names
+    let sql_names = r#"CREATE TABLE class ( id SERIAL PRIMARY KEY ); CREATE TABLE "2fa_codes" ( id SERIAL PRIMARY KEY );
+        CREATE TABLE "user-data" ( id SERIAL PRIMARY KEY, "my col" TEXT NULL DEFAULT 'x' )"#;
+    let schema_names = Schema::from_sql(sql_names, &Dialect::PostgreSQL).unwrap();
+    let python_code = python_ast_to_string(&python_schema_to_ast(&schema_names));
+    for class in ["class Class:", "class NewClass:", "class _2fa_codes:", "class New_2fa_codes:", "class User_data:", "def into_user_data(self, id: int) -> \"User_data\":"] {
+        assert!(python_code.contains(class), "{}", python_code);
+    }
+    let js_code = javascript_ast_to_string(&javascript_schema_to_ast(&schema_names));
+    for decl in ["var class_ = {", "var newClass_ = {", "var _2fa_codes = {", "var user_data = {", "\"my col\": \"string | null\"", "var newUser_dataToUser_data = (newUser_data, generated)=>({", "\"my col\": newUser_data[\"my col\"] ?? \"x\""] {
+        assert!(js_code.contains(decl), "{}", js_code);
+    }
+
+    let files = rust_schema_to_files(&Schema::from_sql(sql, &Dialect::PostgreSQL).unwrap(), &Dialect::PostgreSQL, &options);
+    assert!(files.get("type.rs").is_some() && files.get("my_table.rs").is_some());
+    assert!(files.get("mod.rs").unwrap().contents.contains("pub mod r#type;\npub use r#type::*;\n"));
+}
+
+#[test]
+fn test_sql_comments_become_doc_comments() {
+    let sql = "
+        -- People we know
+        CREATE TABLE person (
+            -- Primary key
+            id SERIAL PRIMARY KEY,
+            name TEXT -- Full name
+        );
+        COMMENT ON COLUMN person.name IS 'Given and family name';
+    ";
+    let schema = Schema::from_sql(sql, &Dialect::PostgreSQL).unwrap();
+    let person = schema.table("person").unwrap();
+    assert_eq!(person.comment.as_deref(), Some("People we know"));
+    assert_eq!(person.column("id").unwrap().comment.as_deref(), Some("Primary key"));
+    assert_eq!(person.column("name").unwrap().comment.as_deref(), Some("Given and family name"));
+
+    let rust_code = rust_ast_to_string(&rust_schema_to_ast(&schema, &Dialect::PostgreSQL, &RustOptions::default()));
+    assert!(rust_code.contains("/// People we know\npub struct Person {\n    /// Primary key\n    pub id: i64,"));
+    let js_code = javascript_ast_to_string(&javascript_schema_to_ast(&schema));
+    assert!(js_code.contains("/** People we know */ var person = {\n    /** Primary key */ id: \"number\","));
+    let py_code = python_ast_to_string(&python_schema_to_ast(&schema));
+    assert!(py_code.contains("class Person:\n    \"\"\"People we know\"\"\"\n    id: int\n    \"\"\"Primary key\"\"\"\n"));
+}
+
+#[test]
+fn test_literal_defaults_in_generated_code() {
+    let sql = "CREATE TABLE account ( id INT PRIMARY KEY, balance INT DEFAULT 0, active BOOLEAN DEFAULT TRUE, created_at TEXT DEFAULT now() )";
+    let schema = Schema::from_sql(sql, &Dialect::PostgreSQL).unwrap();
+    let account = &schema.tables[0];
+    assert_eq!(account.column("balance").unwrap().default_value(), Some(DefaultValue::Number("0".to_string())));
+    assert!(account.column("created_at").unwrap().generated);
+
+    let rust_code = rust_ast_to_string(&rust_schema_to_ast(&schema, &Dialect::PostgreSQL, &RustOptions::default()));
+    assert!(rust_code.contains("pub struct NewAccount {\n    pub id: i64,\n    pub balance: Option<i64>,\n    pub active: Option<bool>,\n}"));
+    assert!(rust_code.contains("pub fn default_balance() -> i64 {\n        0\n    }"));
+    assert!(rust_code.contains("pub fn default_active() -> bool {\n        true\n    }"));
+    assert!(rust_code.contains("balance: self.balance.unwrap_or_else(Account::default_balance)"));
+
+    let python_code = python_ast_to_string(&python_schema_to_ast(&schema));
+    assert!(python_code.contains("class NewAccount:\n    id: int\n    balance: int = 0\n    active: bool = True\n"));
+    let js_code = javascript_ast_to_string(&javascript_schema_to_ast(&schema));
+    assert!(js_code.contains("balance: newAccount.balance ?? 0,"));
+}
+
+#[test]
+fn test_view_columns_inferred_from_tables() {
+    let sql = "
+        CREATE TABLE person ( id SERIAL PRIMARY KEY, name TEXT );
+        CREATE TABLE office ( id SERIAL PRIMARY KEY, city TEXT, person_id INTEGER REFERENCES person(id) );
+        CREATE VIEW person_office AS
+            SELECT p.id, p.name AS person_name, o.city, COUNT(*) AS offices
+            FROM person p LEFT JOIN office o ON o.person_id = p.id
+            GROUP BY p.id, p.name, o.city;
+        CREATE MATERIALIZED VIEW city (name) AS SELECT DISTINCT city FROM office;
+    ";
+    let schema = Schema::from_sql(sql, &Dialect::PostgreSQL).unwrap();
+    let view = schema.table("person_office").unwrap();
+    assert_eq!(view.kind, TableKind::View);
+    let columns: Vec<(&str, String, bool)> = view.columns.iter()
+        .map(|c| (c.name.as_str(), c.data_type.to_string(), c.nullable))
+        .collect();
+    assert_eq!(columns, vec![
+        ("id", "SERIAL".to_string(), false),
+        ("person_name", "TEXT".to_string(), false),
+        ("city", "TEXT".to_string(), true),
+        ("offices", "BIGINT".to_string(), false),
+    ]);
+    assert_eq!(schema.table("city").unwrap().kind, TableKind::MaterializedView);
+    assert_eq!(schema.table("city").unwrap().columns[0].name, "name");
+
+    let rust_code = rust_ast_to_string(&rust_schema_to_ast(&schema, &Dialect::PostgreSQL, &RustOptions::default()));
+    assert!(rust_code.contains("pub struct Person_office {\n    pub id: i64,\n    pub person_name: String,\n    pub city: Option<String>,\n    pub offices: i64,\n}"));
+    assert!(!rust_code.contains("NewPerson_office"));
+    assert!(!rust_code.contains("Person_officePatch"));
+    let python_code = python_ast_to_string(&python_schema_to_ast(&schema));
+    assert!(python_code.contains("class Person_office:"));
+    assert!(!python_code.contains("class NewPerson_office:"));
+}
+
+#[test]
+fn test_generated_files_written_with_index() {
+    let sql = "
+        CREATE TABLE person ( id SERIAL PRIMARY KEY, name TEXT );
+        CREATE TABLE office ( id SERIAL PRIMARY KEY, person_id INTEGER REFERENCES person(id) );
+    ";
+    let schema = Schema::from_sql(sql, &Dialect::SQLite).unwrap();
+    let options = RustOptions { backend: Some(RustBackend::Rusqlite), ..Default::default() };
+    let files = rust_schema_to_files(&schema, &Dialect::SQLite, &options);
+    let paths: Vec<String> = files.files.iter().map(|f| f.path.display().to_string()).collect();
+    assert_eq!(paths, vec!["person.rs", "office.rs", "mod.rs"]);
+    assert_eq!(files.get("mod.rs").unwrap().contents, "pub mod person;\npub use person::*;\npub mod office;\npub use office::*;\n");
+    assert!(files.get("office.rs").unwrap().contents.contains("use super::Person;\n"));
+    let python_files = python_schema_to_files(&schema);
+    assert_eq!(python_files.get("__init__.py").unwrap().contents, "from .person import Person, NewPerson\nfrom .office import Office, NewOffice\n");
+
+    let dir = std::env::temp_dir().join(format!("noorm-output-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("handwritten.rs"), "").unwrap();
+    files.write_to(&dir).unwrap();
+    assert!(dir.join("office.rs").exists());
+
+    let modified = |file: &str| std::fs::metadata(dir.join(file)).unwrap().modified().unwrap();
+    let person_modified = modified("person.rs");
+    files.write_to(&dir).unwrap();
+    assert_eq!(modified("person.rs"), person_modified);
+
+    let schema = Schema::from_sql("CREATE TABLE person ( id SERIAL PRIMARY KEY, name TEXT )", &Dialect::SQLite).unwrap();
+    rust_schema_to_files(&schema, &Dialect::SQLite, &options).write_to(&dir).unwrap();
+    assert!(dir.join("person.rs").exists());
+    assert!(!dir.join("office.rs").exists());
+    assert!(dir.join("handwritten.rs").exists());
+
+    // Files edited by hand are kept, and only the paths inside the directory are removed
+    files.write_to(&dir).unwrap();
+    let office = std::fs::read_to_string(dir.join("office.rs")).unwrap();
+    std::fs::write(dir.join("office.rs"), format!("{}// TODO\n", office)).unwrap();
+    let kept = rust_schema_to_files(&schema, &Dialect::SQLite, &options).write_to(&dir).unwrap();
+    assert_eq!(kept, vec![PathBuf::from("office.rs")]);
+    assert!(dir.join("office.rs").exists());
+    std::fs::write(dir.join(MANIFEST_FILE), "person.rs\n../outside.rs\n").unwrap();
+    let error = files.write_to(&dir).unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_parser_loads_sql_files() {
+    let dir = std::env::temp_dir().join(format!("noorm-load-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(dir.join("migrations")).unwrap();
+    std::fs::write(dir.join("migrations/002_office.sql"), "CREATE TABLE office ( id INTEGER, person_id INTEGER REFERENCES person(id) )").unwrap();
+    std::fs::write(dir.join("migrations/001_person.sql"), "CREATE TABLE person ( id INTEGER, name TEXT )").unwrap();
+    std::fs::write(dir.join("migrations/notes.txt"), "not SQL").unwrap();
+
+    let mut config = ParserConfig::new();
+    config.migrations = dir.join("migrations");
+    let parser = Parser::new().set_config(config.clone()).load().unwrap();
+    let schema = parser.schema().unwrap();
+    let names: Vec<&str> = schema.tables.iter().map(|t| t.name.as_str()).collect();
+    assert_eq!(names, vec!["person", "office"]);
+    assert!(schema.tables[1].source.as_deref().unwrap().ends_with("002_office.sql"));
+
+    std::fs::write(dir.join("migrations/003_broken.sql"), "CREATE TABLE broken ( id INT,, )").unwrap();
+    let error = Parser::new().set_config(config.clone()).load().err().unwrap();
+    assert!(error.to_string().starts_with("SQL parsing error in"));
+    assert!(error.to_string().contains("003_broken.sql"));
+
+    config.migrations = dir.join("missing");
+    assert!(matches!(Parser::new().set_config(config).load(), Err(ParserError::Read { .. })));
+    assert_eq!(Dialect::new("SQLite"), Some(Dialect::SQLite));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_sources_merged_with_duplicates() {
+    let mut parser = Parser::new()
+        .add_source("001_person.sql", "CREATE TABLE person ( id INTEGER PRIMARY KEY, name TEXT );")
+        .add_source("002_office.sql", "CREATE TABLE office ( id INTEGER, person_id INTEGER REFERENCES person(id) );");
+    let schema = parser.schema().unwrap();
+    let sources: Vec<_> = schema.tables.iter().map(|t| t.source.as_deref()).collect();
+    assert_eq!(sources, vec![Some("001_person.sql"), Some("002_office.sql")]);
+    assert_eq!(schema.relations().len(), 2);
+    parser.parse().unwrap();
+    let code = parser.output.unwrap();
+    assert!(code.contains("CREATE TABLE person"));
+    assert!(code.contains("CREATE TABLE office"));
+
+    let duplicate = Parser::new()
+        .add_source("001_person.sql", "CREATE TABLE person ( id INTEGER );")
+        .add_source("003_person.sql", "CREATE TABLE person ( id INTEGER, age INTEGER );");
+    match duplicate.schema() {
+        Err(ParserError::DuplicateTable { name, first, second }) => {
+            assert_eq!(name, "person");
+            assert_eq!(first.as_deref(), Some("001_person.sql"));
+            assert_eq!(second.as_deref(), Some("003_person.sql"));
+        }
+        other => panic!("expected a duplicate table, got {:?}", other.map(|_| ())),
+    }
+
+    let schema = Parser::new()
+        .add_source("001.sql", "CREATE TABLE person ( id INTEGER );")
+        .add_source("002.sql", "CREATE TABLE IF NOT EXISTS person ( id INTEGER, age INTEGER );")
+        .schema()
+        .unwrap();
+    assert_eq!(schema.tables[0].columns.len(), 1);
+    let schema = Parser::new()
+        .add_source("001.sql", "CREATE TABLE person ( id INTEGER );")
+        .add_source("002.sql", "DROP TABLE person; CREATE TABLE person ( id INTEGER, age INTEGER );")
+        .schema()
+        .unwrap();
+    assert_eq!(schema.tables[0].columns.len(), 2);
+    assert_eq!(schema.tables[0].source.as_deref(), Some("002.sql"));
+    assert!(matches!(Parser::new().schema(), Err(ParserError::Statement)));
+}
+
+#[test]
+fn test_check_detects_drift() {
+    let dir = std::env::temp_dir().join(format!("noorm-check-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let generate = |sql: &str| python_schema_to_files(&Schema::from_sql(sql, &Dialect::Generic).unwrap());
+    let person = "CREATE TABLE person ( id INTEGER, name TEXT );";
+    let office = "CREATE TABLE office ( id INTEGER );";
+
+    let files = generate(&format!("{}{}", person, office));
+    assert!(files.check(&dir).unwrap().iter().all(|d| d.kind == DriftKind::Missing));
+    files.write_to(&dir).unwrap();
+    assert!(files.check(&dir).unwrap().is_empty());
+    let on_disk = std::fs::read_to_string(dir.join("person.py")).unwrap();
+    assert!(on_disk.starts_with("# @generated by noorm-gen, do not edit. checksum: "));
+
+    std::fs::write(dir.join("person.py"), on_disk.replacen("name: str", "name: bytes", 1)).unwrap();
+    let drifts = generate(&format!("{}{}", person, office)).check(&dir).unwrap();
+    assert_eq!(drifts.len(), 1);
+    assert_eq!(drifts[0].to_string(), "person.py: edited by hand");
+    assert!(drifts[0].diff.contains("-    name: bytes\n+    name: str\n"));
+
+    files.write_to(&dir).unwrap();
+    let drifts = generate("CREATE TABLE person ( id INTEGER, name TEXT NULL );").check(&dir).unwrap();
+    let kinds: Vec<(String, DriftKind)> = drifts.iter().map(|d| (d.path.display().to_string(), d.kind)).collect();
+    assert_eq!(kinds, vec![
+        ("person.py".to_string(), DriftKind::Stale),
+        ("__init__.py".to_string(), DriftKind::Stale),
+        ("office.py".to_string(), DriftKind::Removed),
+    ]);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_config_from_file_and_discovery() {
+    let dir = std::env::temp_dir().join(format!("noorm-config-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(dir.join("db/migrations")).unwrap();
+    std::fs::create_dir_all(dir.join("db/queries")).unwrap();
+    std::fs::create_dir_all(dir.join("src/nested")).unwrap();
+    std::fs::write(dir.join("Cargo.toml"), r#"
+[package]
+name = "app"
+version = "0.1.0"
+
+[package.metadata.noorm]
+migrations = "db/migrations"
+queries = "db/queries"
+sql_dialect = {type = "SQLite"}
+output = "src/db"
+"#).unwrap();
+
+    let config = ParserConfig::discover_from(dir.join("src/nested")).unwrap();
+    assert_eq!(config.sql_dialect, Dialect::SQLite);
+    assert_eq!(config.migrations, dir.join("db/migrations"));
+    assert_eq!(config.output, Some(dir.join("src/db")));
+
+    std::fs::write(dir.join("src").join(CONFIG_FILE), "migrations = \"../db/migrations\"\nqueries = \"../db/missing\"\n").unwrap();
+    let error = ParserConfig::discover_from(dir.join("src/nested")).err().unwrap();
+    assert!(matches!(error, ParserConfigError::Invalid { line: 2, column: 11, .. }), "{}", error);
+
+    // The queries are optional, and an empty path is not the directory of the file
+    for toml in ["migrations = \"../db/migrations\"\n", "migrations = \"../db/migrations\"\nqueries = \"\"\n"] {
+        std::fs::write(dir.join("src").join(CONFIG_FILE), toml).unwrap();
+        let config = ParserConfig::from_file(dir.join("src").join(CONFIG_FILE)).unwrap();
+        assert_eq!(config.migrations, dir.join("src").join("../db/migrations"));
+        assert_eq!(config.queries, PathBuf::new());
+    }
+
+    std::fs::write(dir.join("src").join(CONFIG_FILE), "migrations = \"../db/migrations\"\nqueries = \"../db/queries\"\ndialect = {type = \"SQLite\"}\n").unwrap();
+    let error = ParserConfig::from_file(dir.join("src").join(CONFIG_FILE)).err().unwrap();
+    assert!(error.to_string().ends_with("noorm.toml:3:1: unknown field `dialect`, expected one of `sql_dialect`, `language`, `migrations`, `queries`, `output`, `targets`, `lint`"), "{}", error);
+
+    std::fs::write(dir.join("Cargo.toml"), "[package]\nname = \"app\"\n").unwrap();
+    assert!(matches!(ParserConfig::from_file(dir.join("Cargo.toml")), Err(ParserConfigError::MissingSection(_))));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_language_selection_in_toml() {
+    let config = ParserConfig::from_toml("migrations = \".\"\nqueries = \".\"\nlanguage = \"Python\"\n").unwrap();
+    assert_eq!(config.language, Language::new("python").unwrap());
+
+    let config = ParserConfig::from_toml(r#"
+        migrations = "."
+        queries = "."
+        [language.rust]
+        backend = "rusqlite"
+        derives = ["Debug", "Clone"]
+        types = { datetime = "chrono", uuid = true }
+    "#).unwrap();
+    let Language::Rust(ast) = &config.language else { panic!("Rust expected") };
+    assert_eq!(ast.options().backend, Some(RustBackend::Rusqlite));
+    assert_eq!(ast.options().types.datetime, Some(DateTimeCrate::Chrono));
+    assert_eq!(ast.options().naming, RustNaming::Capitalize);
+
+    let sql = "CREATE TABLE person_office ( id UUID PRIMARY KEY, opened TIMESTAMP NOT NULL, closed DATE NULL )";
+    let mut parser = Parser::new().set_config(config).statement(sql);
+    parser.parse().unwrap();
+    let rust_code = parser.output.unwrap();
+    assert!(rust_code.contains("#[derive(Debug, Clone)]\npub struct Person_office {\n    pub id: uuid::Uuid,\n    pub opened: chrono::NaiveDateTime,\n    pub closed: Option<chrono::NaiveDate>,\n}"));
+    assert!(rust_code.contains("#[derive(Debug, Clone, Default)]\npub struct Person_officePatch {"));
+
+    let options = RustOptions { naming: RustNaming::PascalCase, ..Default::default() };
+    let rust_code = rust_ast_to_string(&rust_schema_to_ast(&Schema::from_sql(sql, &Dialect::Generic).unwrap(), &Dialect::Generic, &options));
+    assert!(rust_code.contains("pub struct PersonOffice {"), "{}", rust_code);
+    assert!(rust_code.contains("pub struct NewPersonOffice {"), "{}", rust_code);
+
+    for (language, message) in [
+        ("\"cobol\"", "unknown variant `cobol`"),
+        ("{ rust = { derives = [\"Debug(\"] } }", "invalid derive `Debug(`"),
+        ("{ rust = { naming = \"snake\" } }", "unknown variant `snake`"),
+        ("{ python = { backend = \"sqlx\" } }", "unknown field `backend`"),
+        ("{ javascript = { backend = \"sqlx\" } }", "unknown field `backend`"),
+    ] {
+        let toml = format!("migrations = \".\"\nqueries = \".\"\nlanguage = {}\n", language);
+        let Err(ParserConfigError::TomlImport(error)) = ParserConfig::from_toml(&toml) else { panic!("{} accepted", language) };
+        assert!(error.message().starts_with(message), "{}", error);
+    }
+}
+
+#[test]
+fn test_several_targets_in_one_parse() {
+    let config = ParserConfig::from_toml(r#"
+        migrations = "."
+        queries = "."
+
+        [[targets]]
+        language = "python"
+        output = "py"
+
+        [[targets]]
+        language = "javascript"
+
+        [[targets]]
+        language = { rust = { backend = "rusqlite" } }
+        output = "rs"
+    "#).unwrap();
+    let sql = "CREATE TABLE person ( id INTEGER PRIMARY KEY, name TEXT NOT NULL )";
+    let mut parser = Parser::new().set_config(config).statement(sql);
+    parser.parse().unwrap();
+
+    let languages: Vec<&str> = parser.targets.iter().map(|t| t.language).collect();
+    assert_eq!(languages, vec!["python", "javascript", "rust"]);
+    assert_eq!(parser.targets[1].output, None);
+    assert!(parser.targets[0].code.contains("class Person:"));
+    assert!(parser.targets[2].code.contains("pub fn list(conn: &rusqlite::Connection)"));
+    assert!(parser.targets[2].files.get("person.rs").is_some());
+    assert_eq!(parser.output.as_ref(), Some(&parser.targets[0].code));
+
+    let mut parser = Parser::new().set_config(parser.get_config().clone()).statement("CREATE TABLE person ( id INTEGER,, )");
+    assert!(matches!(parser.parse(), Err(ParserError::Sql(_))));
+}
+
+#[test]
+fn test_schema_diff_to_migration() {
+    let current = "CREATE TABLE person ( id INTEGER PRIMARY KEY, name TEXT, email TEXT NULL );
+        CREATE TABLE legacy ( id INTEGER );
+        CREATE VIEW names AS SELECT name FROM person;";
+    let target = "CREATE TABLE person ( id INTEGER PRIMARY KEY, full_name TEXT, email VARCHAR(200) NULL, UNIQUE (email) );
+        CREATE TABLE desk ( id INTEGER PRIMARY KEY, office_id INTEGER REFERENCES office(id) );
+        CREATE TABLE office ( id INTEGER PRIMARY KEY, person_id INTEGER NULL REFERENCES person(id) );
+        CREATE INDEX office_person ON office (person_id);
+        CREATE VIEW names AS SELECT full_name FROM person;";
+    for dialect in [Dialect::Generic, Dialect::PostgreSQL, Dialect::MySQL, Dialect::SQLite] {
+        let mut schema = Schema::from_sql(current, &dialect).unwrap();
+        let target = Schema::from_sql(target, &dialect).unwrap();
+        let diff = schema.diff(&target);
+        let changes: Vec<String> = diff.changes.iter().map(|c| c.to_string()).collect();
+        assert_eq!(changes, vec![
+            "drop view names",
+            "rename column person.name to full_name",
+            "create table office",
+            "create table desk",
+            "alter column person.email",
+            "drop table legacy",
+            "add unique key person(email)",
+            "create view names",
+        ]);
+        assert!(matches!(&diff.changes[3], SchemaChange::CreateTable(table) if table.name == "desk"));
+
+        // Replaying the migration gives the target schema
+        let sql = diff.to_sql(&dialect);
+        schema.add_sql(&sql, Some("002_target.sql"), &dialect).unwrap();
+        assert!(schema.diff(&target).is_empty(), "{:?}: {}", dialect, sql);
+        assert!(target.diff(&target).is_empty());
+    }
+
+    let sql = |dialect: Dialect| {
+        let schema = Schema::from_sql(current, &dialect).unwrap();
+        schema.diff(&Schema::from_sql(target, &dialect).unwrap()).to_sql(&dialect)
+    };
+    assert!(sql(Dialect::PostgreSQL).contains("ALTER TABLE person ALTER COLUMN email SET DATA TYPE VARCHAR(200);"));
+    assert!(sql(Dialect::MySQL).contains("ALTER TABLE person MODIFY COLUMN email VARCHAR(200) NULL;"));
+    let sqlite = sql(Dialect::SQLite);
+    assert!(sqlite.contains("INSERT INTO person_new (id, full_name, email) SELECT id, full_name, email FROM person;"));
+    assert!(sqlite.contains("ALTER TABLE person_new RENAME TO person;"));
+    assert!(!sqlite.contains("ALTER COLUMN"));
+}
+
+#[test]
+fn test_migration_lints() {
+    let base = "CREATE TABLE person ( id INTEGER PRIMARY KEY, name VARCHAR(100), age INTEGER NULL );
+        CREATE TABLE office ( id INTEGER PRIMARY KEY );";
+    let migration = "ALTER TABLE person ALTER COLUMN name SET DATA TYPE VARCHAR(50);
+ALTER TABLE person ALTER COLUMN age SET DATA TYPE BIGINT;
+ALTER TABLE person ADD COLUMN email TEXT NOT NULL;
+ALTER TABLE person ADD COLUMN nick TEXT NULL; ALTER TABLE person RENAME COLUMN nick TO nickname;
+-- noorm:allow(drop-table)
+DROP TABLE office;
+CREATE TABLE desk ( id INTEGER, label TEXT );
+ALTER TABLE desk ADD COLUMN x INTEGER NOT NULL;
+CREATE INDEX desk_label ON desk (label);
+CREATE INDEX person_name ON person (name);
+DELETE FROM person; -- noorm:allow(data-loss)";
+    let mut schema = Schema::from_sql(base, &Dialect::PostgreSQL).unwrap();
+    let lints = schema.lint_migration("002.sql", migration, &Dialect::PostgreSQL).unwrap();
+    let found: Vec<(Option<usize>, &str, Severity)> = lints.iter().map(|l| (l.line, l.rule, l.severity)).collect();
+    assert_eq!(found, vec![
+        (Some(1), "narrow-type", Severity::Error),
+        (Some(2), "change-type", Severity::Warning),
+        (Some(3), "add-not-null-column", Severity::Error),
+        (Some(4), "rename", Severity::Warning),
+        (Some(10), "create-index-lock", Severity::Warning),
+    ]);
+    assert!(schema.table("office").is_none());
+    assert!(schema.table("person").unwrap().column("nickname").is_some());
+
+    let mut schema = Schema::from_sql(base, &Dialect::PostgreSQL).unwrap();
+    let lints = schema.lint_migration("002.sql", &format!("-- noorm:allow-file(all)\n{}", migration), &Dialect::PostgreSQL).unwrap();
+    assert!(lints.is_empty());
+
+    // SQLite rebuilds are reported as rewrites, along with the changes to the rebuilt table
+    let rebuild = "CREATE TABLE person_new ( id INTEGER PRIMARY KEY, name VARCHAR(100), age INTEGER );
+INSERT INTO person_new (id, name, age) SELECT id, name, age FROM person;
+DROP TABLE person;
+ALTER TABLE person_new RENAME TO person;";
+    let mut schema = Schema::from_sql(base, &Dialect::SQLite).unwrap();
+    let lints = schema.lint_migration("003.sql", rebuild, &Dialect::SQLite).unwrap();
+    let rules: Vec<&str> = lints.iter().map(|l| l.rule).collect();
+    assert_eq!(rules, vec!["table-rewrite", "set-not-null"]);
+    assert_eq!(lints[0].to_string(), "003.sql:4: warning[table-rewrite]: rebuilds table person, copying every row");
+}
+
+#[test]
+fn test_schema_design_lints() {
+    let sql = "CREATE TABLE person ( id INTEGER PRIMARY KEY, \"fullName\" VARCHAR, active BOOLEAN NULL );
+        CREATE TABLE \"order\" ( person_id INTEGER REFERENCES person(id), total INTEGER );
+        CREATE TABLE desk ( id INTEGER PRIMARY KEY, person_id INTEGER REFERENCES person(id) );
+        CREATE INDEX desk_person ON desk (person_id, id);";
+    let schema = Schema::from_sources([("001.sql", sql)], &Dialect::PostgreSQL).unwrap();
+    let lints = schema.lint(&LintConfig::default());
+    let found: Vec<(&str, &str)> = lints.iter().map(|l| (l.rule, l.message.as_str())).collect();
+    assert_eq!(found, vec![
+        ("naming", "column fullName is not snake_case"),
+        ("varchar-without-length", "person.fullName is a VARCHAR without a length"),
+        ("nullable-boolean", "person.active is a nullable boolean, with three states"),
+        ("reserved-word", "table order is a reserved word, to be quoted in every query"),
+        ("missing-primary-key", "table order has no primary key"),
+        ("unindexed-foreign-key", "foreign key order(person_id) -> person has no index, slowing the joins and the deletes of person"),
+    ]);
+    assert_eq!(lints[0].to_string(), "001.sql: warning[naming]: column fullName is not snake_case");
+
+    // Rules are turned off or made errors in the configuration, and the naming convention is chosen
+    let config = ParserConfig::from_toml(r#"
+        migrations = "m"
+        queries = "q"
+        [lint]
+        naming = "camelCase"
+        rules = { missing-primary-key = "error", reserved-word = "off", varchar-without-length = "off" }
+    "#).unwrap();
+    let found: Vec<(&str, Severity)> = schema.lint(&config.lint).iter().map(|l| (l.rule, l.severity)).collect();
+    assert_eq!(found, vec![
+        ("nullable-boolean", Severity::Warning),
+        ("missing-primary-key", Severity::Error),
+        ("unindexed-foreign-key", Severity::Warning),
+        ("naming", Severity::Warning),
+        ("naming", Severity::Warning),
+    ]);
+    assert!(ParserConfig::from_toml("migrations = \".\"\nqueries = \".\"\n[lint]\nrules = { no-such-rule = \"off\" }\n").is_err());
+    let error = toml::from_str::<LintConfig>("rules = { no-such-rule = \"off\" }").unwrap_err();
+    assert!(error.to_string().contains("unknown lint rule `no-such-rule`"), "{}", error);
+
+    let json: serde_json::Value = serde_json::from_str(&lints_to_json(&lints[..1])).unwrap();
+    assert_eq!(json, serde_json::json!([{
+        "rule": "naming", "severity": "warning", "message": "column fullName is not snake_case", "source": "001.sql", "line": null,
+    }]));
+    let sarif: serde_json::Value = serde_json::from_str(&lints_to_sarif(&lints)).unwrap();
+    assert_eq!(sarif["version"], "2.1.0");
+    assert_eq!(sarif["runs"][0]["tool"]["driver"]["rules"].as_array().unwrap().len(), RULES.len());
+    assert_eq!(sarif["runs"][0]["results"][4]["ruleId"], "missing-primary-key");
+    assert_eq!(sarif["runs"][0]["results"][4]["locations"][0]["physicalLocation"]["artifactLocation"]["uri"], "001.sql");
+}
+
+#[test]
+fn test_rust_structs_to_create_table() {
+    let source = r#"
+        /// A person
+        #[noorm]
+        pub struct Person {
+            #[noorm(primary_key, auto_increment)]
+            pub id: i64,
+            #[noorm(unique)]
+            pub email: String,
+            pub age: Option<i32>,
+            #[noorm(skip)]
+            pub cache: Vec<String>,
+        }
+
+        #[noorm(table = "desks")]
+        pub struct OfficeDesk {
+            #[noorm(primary_key)]
+            pub id: i32,
+            #[noorm(references = "person", index)]
+            pub person_id: Option<i64>,
+            #[noorm(sql_type = "VARCHAR(20)", column = "label", default = "'free'")]
+            pub r#type: String,
+        }
+
+        struct NotATable { map: std::collections::HashMap<String, String> }
+    "#;
+    let schema = Schema::from_rust(source, &Dialect::SQLite).unwrap();
+    let names: Vec<&str> = schema.tables.iter().map(|t| t.name.as_str()).collect();
+    assert_eq!(names, vec!["person", "desks"]);
+    assert_eq!(schema.tables[0].comment.as_deref(), Some("A person"));
+    let desk = schema.table("desks").unwrap();
+    assert_eq!(desk.foreign_keys[0].referred_columns, vec!["id"]);
+    assert_eq!(desk.column("label").unwrap().default_value(), Some(DefaultValue::String("free".to_string())));
+
+    // The statements create the same schema, in every dialect
+    for dialect in [Dialect::Generic, Dialect::MySQL, Dialect::PostgreSQL, Dialect::SQLite] {
+        let schema = Schema::from_rust(source, &dialect).unwrap();
+        let sql = schema.create_sql(&dialect);
+        let parsed = Schema::from_sql(&sql, &dialect).unwrap();
+        assert!(schema.diff(&parsed).is_empty(), "{:?}\n{}", dialect, sql);
+    }
+    assert!(Schema::from_rust(source, &Dialect::PostgreSQL).unwrap().create_sql(&Dialect::PostgreSQL).starts_with(
+        "CREATE TABLE person (\n    id BIGINT NOT NULL GENERATED BY DEFAULT AS IDENTITY,\n    email TEXT NOT NULL,\n    age INTEGER NULL,"
+    ));
+
+    let error = Schema::from_rust("#[noorm]\nstruct A {\n    #[noorm(primary)]\n    id: i64,\n}", &Dialect::Generic).unwrap_err();
+    assert!(error.to_string().starts_with("Rust parsing error at line 3: unknown noorm field attribute"), "{}", error);
+    let error = Schema::from_rust("#[noorm]\nstruct A {\n    id: (i64, i64),\n}", &Dialect::Generic).unwrap_err();
+    assert!(error.to_string().contains("no SQL type for the Rust type of a.id"), "{}", error);
+    let error = Schema::from_rust("#[noorm]\nstruct A {\n    #[noorm(auto_increment)]\n    id: String,\n}", &Dialect::Generic).unwrap_err();
+    assert!(error.to_string().starts_with("Rust parsing error at line 4: auto_increment needs an integer column, a.id is TEXT"), "{}", error);
+    let error = Schema::from_rust("#[noorm]\nstruct A {\n    #[noorm(sql_type = \"INT garbage\")]\n    id: i32,\n}", &Dialect::Generic).unwrap_err();
+    assert!(error.to_string().contains("invalid sql_type of a.id"), "{}", error);
+    let schema = Schema::from_rust("#[noorm]\nstruct A {\n    id: u64,\n}", &Dialect::Generic).unwrap();
+    assert_eq!(schema.tables[0].columns[0].data_type.to_string(), "NUMERIC(20)");
+}
+
+#[cfg(feature = "sqlite")]
+#[test]
+fn test_sqlite_database_as_schema() {
+    let dir = std::env::temp_dir().join(format!("noorm-sqlite-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let sql = "CREATE TABLE person (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            -- Login email
+            email VARCHAR(100) NOT NULL UNIQUE,
+            name TEXT NULL,
+            status TEXT NOT NULL DEFAULT 'active'
+        );
+        CREATE TABLE office (
+            id INTEGER NOT NULL,
+            code TEXT NOT NULL,
+            person_id INTEGER NULL REFERENCES person(id) ON DELETE CASCADE,
+            boss_id INTEGER NULL,
+            PRIMARY KEY (code, id),
+            FOREIGN KEY (boss_id) REFERENCES person (id)
+        );
+        CREATE INDEX office_person ON office (person_id) WHERE person_id IS NOT NULL;
+        CREATE UNIQUE INDEX office_boss ON office (boss_id, person_id);
+        CREATE VIEW office_people AS SELECT office.code, person.name FROM office JOIN person ON person.id = office.person_id;";
+    let path = dir.join("legacy.sqlite");
+    rusqlite::Connection::open(&path).unwrap().execute_batch(sql).unwrap();
+    assert!(is_sqlite_database(&path));
+
+    // The introspected schema is the one of the SQL
+    let database = Schema::from_sqlite(&path).unwrap();
+    let mut parsed = Schema::from_sql(sql, &Dialect::SQLite).unwrap();
+    for table in &mut parsed.tables {
+        table.source = Some(path.display().to_string());
+    }
+    assert_eq!(database, parsed);
+    assert_eq!(database.table("person").unwrap().column("email").unwrap().comment.as_deref(), Some("Login email"));
+
+    // The generators run against the database given as migrations
+    let mut config = ParserConfig::new();
+    config.migrations = path.clone();
+    config.sql_dialect = Dialect::SQLite;
+    config.language = Language::new("rust").unwrap();
+    let mut parser = Parser::new().set_config(config).load().unwrap();
+    assert_eq!(parser.schema().unwrap().relations().len(), 4);
+    parser.parse().unwrap();
+    assert!(parser.output.unwrap().contains("pub struct Office_people {"));
+
+    std::fs::write(dir.join("broken.sqlite"), "SQLite format 3\0 but not a database").unwrap();
+    assert!(matches!(Schema::from_sqlite(dir.join("broken.sqlite")), Err(ParserError::Sqlite { .. })));
+    assert!(!is_sqlite_database(dir.join("missing.sqlite")));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_schema_json_and_yaml() {
+    let sql = "-- People
+        CREATE TABLE person ( id SERIAL PRIMARY KEY, email VARCHAR(100) NOT NULL UNIQUE, born TIMESTAMP WITH TIME ZONE NULL DEFAULT now() );
+        CREATE TABLE office ( id INT PRIMARY KEY, person_id INT NULL REFERENCES person(id) ON DELETE SET NULL, tags TEXT[] NULL );
+        CREATE INDEX office_person ON office (person_id) WHERE person_id IS NOT NULL;
+        CREATE VIEW office_people AS SELECT office.id, person.email FROM office JOIN person ON person.id = office.person_id;";
+    let schema = Schema::from_sources([("001.sql", sql)], &Dialect::PostgreSQL).unwrap();
+    assert_eq!(Schema::from_json(&schema.to_json()).unwrap(), schema);
+    assert_eq!(Schema::from_yaml(&schema.to_yaml()).unwrap(), schema);
+
+    let json: serde_json::Value = serde_json::from_str(&schema.to_json()).unwrap();
+    assert_eq!(json["tables"][0]["comment"], "People");
+    assert_eq!(json["tables"][0]["columns"][2]["data_type"], "TIMESTAMP WITH TIME ZONE");
+    assert_eq!(json["tables"][0]["columns"][2]["default"], "now()");
+    assert_eq!(json["tables"][1]["foreign_keys"][0]["on_delete"], "SET NULL");
+    assert_eq!(json["tables"][1]["indexes"][0]["predicate"], "person_id IS NOT NULL");
+    assert_eq!(json["tables"][2]["kind"], "view");
+
+    // A hand-written schema is an input like the migrations
+    let dir = std::env::temp_dir().join(format!("noorm-schema-format-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("schema.yaml"), "
+tables:
+  - name: person
+    columns:
+      - { name: id, data_type: BIGINT, generated: true }
+      - { name: name, data_type: TEXT, nullable: true }
+    primary_key: [id]
+  - name: office
+    columns:
+      - { name: id, data_type: INT }
+      - { name: person_id, data_type: BIGINT }
+    foreign_keys:
+      - { columns: [person_id], foreign_table: person }
+").unwrap();
+    assert!(is_schema_file(dir.join("schema.yaml")));
+    let mut config = ParserConfig::new();
+    config.migrations = dir.join("schema.yaml");
+    let parser = Parser::new().set_config(config).load().unwrap();
+    let loaded = parser.schema().unwrap();
+    assert_eq!(loaded.tables[1].foreign_keys[0].referred_columns, vec!["id"]);
+    assert_eq!(loaded.relations().len(), 2);
+
+    std::fs::write(dir.join("broken.json"), r#"{ "tables": [{ "name": "t", "columns": [{ "name": "id", "data_type": "INT((" }] }] }"#).unwrap();
+    let error = Schema::from_schema_file(dir.join("broken.json")).unwrap_err();
+    assert!(error.to_string().contains("broken.json: invalid SQL `INT((`"), "{}", error);
+    assert!(matches!(Schema::from_yaml("tables: [{ name: t, colums: [] }]"), Err(ParserError::SchemaFormat(_))));
+    let error = Schema::from_yaml("tables: [{ name: t, columns: [{ name: id, data_type: INT garbage }] }]").unwrap_err();
+    assert!(matches!(&error, ParserError::SchemaFormat(message) if message.contains("invalid SQL `INT garbage`")), "{}", error);
+    let error = Schema::from_yaml("tables: [{ name: t, columns: [{ name: id, data_type: INT, default: 1 2 }] }]").unwrap_err();
+    assert!(matches!(&error, ParserError::SchemaFormat(message) if message.contains("invalid SQL `1 2`")), "{}", error);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_er_diagrams() {
+    let sql = "CREATE TABLE person ( id INT PRIMARY KEY, score NUMERIC(10,2) NULL, email TEXT UNIQUE );
+        CREATE TABLE passport ( id INT PRIMARY KEY, person_id INT UNIQUE REFERENCES person(id) );
+        CREATE TABLE office ( id INT PRIMARY KEY, person_id INT NULL REFERENCES person(id) );
+        CREATE TABLE person_log ( id INT, person_id INT REFERENCES person(id) );
+        CREATE VIEW office_people AS SELECT office.id FROM office;";
+    let schema = Schema::from_sql(sql, &Dialect::PostgreSQL).unwrap();
+
+    let mermaid = schema.to_mermaid();
+    assert!(mermaid.contains("    person {\n        INT id PK\n        NUMERIC(10_2) score\n        TEXT email UK\n    }\n"), "{}", mermaid);
+    assert!(mermaid.contains("    person ||--o| passport : \"person_id\"\n"), "{}", mermaid);
+    assert!(mermaid.contains("    person |o--o{ office : \"person_id\"\n"), "{}", mermaid);
+    assert!(mermaid.contains("    person ||--o{ person_log : \"person_id\"\n"), "{}", mermaid);
+    assert!(!mermaid.contains("office_people"));
+
+    let dot = schema.to_dot();
+    assert!(dot.contains("<TR><TD PORT=\"score\" ALIGN=\"LEFT\">score</TD><TD ALIGN=\"LEFT\">NUMERIC(10,2) NULL</TD><TD></TD></TR>"), "{}", dot);
+    assert!(dot.contains("\"passport\":\"person_id\" -> \"person\":\"id\" [arrowtail=teeodot, arrowhead=teetee];"), "{}", dot);
+    assert!(dot.contains("\"office\":\"person_id\" -> \"person\":\"id\" [arrowtail=crowodot, arrowhead=teeodot];"), "{}", dot);
+
+    // Relationships to the tables left out are not drawn
+    let subset = schema.subset(&["P*"], &["*_log"]);
+    let names: Vec<&str> = subset.tables.iter().map(|t| t.name.as_str()).collect();
+    assert_eq!(names, vec!["person", "passport"]);
+    assert_eq!(subset.to_mermaid().matches("--").count(), 1);
+    assert_eq!(schema.subset(&["office"], &[]).to_dot().matches("->").count(), 0);
+}
+
+#[test]
+fn test_schema_docs() {
+    let sql = "-- Offices of the company
+        CREATE TABLE office ( id SERIAL PRIMARY KEY, name VARCHAR(100) UNIQUE, city TEXT NULL DEFAULT 'Rome' );
+        CREATE TABLE person ( id BIGINT PRIMARY KEY, email TEXT, office_id INT NULL REFERENCES office(id) ON DELETE CASCADE );
+        CREATE INDEX person_email ON person (email) WHERE email IS NOT NULL;
+        CREATE TABLE \"index\" ( id INT PRIMARY KEY, person_id BIGINT REFERENCES person(id) );
+        CREATE VIEW office_names AS SELECT name FROM office;";
+    let schema = Schema::from_sources([("001_init.sql", sql)], &Dialect::PostgreSQL).unwrap();
+
+    let docs = schema.to_markdown_docs();
+    let paths: Vec<String> = docs.files.iter().map(|f| f.path.display().to_string()).collect();
+    assert_eq!(paths, vec!["office.md", "person.md", "index_table.md", "office_names.md", "index.md"]);
+    let index = &docs.get("index.md").unwrap().contents;
+    assert!(index.contains("| [office](office.md) | table | 3 | Offices of the company |\n"), "{}", index);
+    assert!(index.contains("| [index](index_table.md) | table | 2 |  |\n"), "{}", index);
+    assert!(index.contains("| [office_names](office_names.md) | view | 1 |  |\n"), "{}", index);
+
+    let office = &docs.get("office.md").unwrap().contents;
+    assert!(office.starts_with("[Schema](index.md)\n\n# office\n\nOffices of the company\n\nTable defined in `001_init.sql`.\n"), "{}", office);
+    assert!(office.contains("| `id` | `SERIAL` | no | generated | PK |  |\n"), "{}", office);
+    assert!(office.contains("| `city` | `TEXT` | yes | `'Rome'` |  |  |\n"), "{}", office);
+    assert!(office.contains("| unique key | `name` | yes |  |\n"), "{}", office);
+    assert!(office.contains("## Referenced by\n\n| Table | Columns | Referred columns |\n| --- | --- | --- |\n| [person](person.md) | `office_id` | `id` |\n"), "{}", office);
+    assert!(!office.contains("## Foreign keys"));
+
+    let person = &docs.get("person.md").unwrap().contents;
+    assert!(person.contains("| `person_email` | `email` | no | `email IS NOT NULL` |\n"), "{}", person);
+    assert!(person.contains("| `office_id` | [office](office.md) | `id` | CASCADE |  |\n"), "{}", person);
+    assert!(person.contains("| [index](index_table.md) | `person_id` | `id` |\n"), "{}", person);
+    let view = &docs.get("office_names.md").unwrap().contents;
+    assert!(view.contains("## Definition\n\n```sql\nCREATE VIEW office_names AS SELECT name FROM office\n```\n"), "{}", view);
+
+    let docs = schema.subset(&["person"], &[]).to_html_docs();
+    let person = &docs.get("person.html").unwrap().contents;
+    assert!(person.starts_with("<!DOCTYPE html>\n"));
+    assert!(person.contains("<h1>person</h1>"));
+    assert!(person.contains("<tr><td><code>office_id</code></td><td>office</td><td><code>id</code></td><td>CASCADE</td><td></td></tr>"), "{}", person);
+    assert!(docs.get("index.html").unwrap().contents.contains("<td><a href=\"person.html\">person</a></td>"));
+
+    // The pages are generated files, checked like the code
+    let dir = std::env::temp_dir().join(format!("noorm-docs-{}", std::process::id()));
+    docs.write_to(&dir).unwrap();
+    assert!(std::fs::read_to_string(dir.join("index.html")).unwrap().starts_with("<!-- @generated by noorm-gen"));
+    assert!(docs.check(&dir).unwrap().is_empty());
+    let edited = std::fs::read_to_string(dir.join("person.html")).unwrap().replace("<h1>person</h1>", "<h1>people</h1>");
+    std::fs::write(dir.join("person.html"), edited).unwrap();
+    let drifts = docs.check(&dir).unwrap();
+    assert_eq!(drifts.len(), 1);
+    assert_eq!(drifts[0].kind, DriftKind::Edited);
+    let stale = schema.subset(&["person", "office"], &[]).to_html_docs();
+    stale.write_to(&dir).unwrap();
+    assert_eq!(docs.check(&dir).unwrap().iter().map(|d| d.kind).collect::<Vec<_>>(), vec![DriftKind::Stale, DriftKind::Stale, DriftKind::Removed]);
+    std::fs::remove_dir_all(&dir).unwrap();
+}